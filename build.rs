@@ -0,0 +1,8 @@
+fn main() {
+    // Functions missing from an older system duckdb must only fail when
+    // called, so that the version check can report them first
+    let linux = std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("linux");
+    if linux && std::env::var_os("CARGO_FEATURE_BUNDLED").is_none() {
+        println!("cargo:rustc-link-arg=-Wl,-z,lazy");
+    }
+}
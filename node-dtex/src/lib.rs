@@ -0,0 +1,75 @@
+use std::io::Cursor;
+
+use ::dtex::{arrow::ipc::reader::StreamReader, DataFrame, Source};
+use napi::{bindgen_prelude::*, Task};
+use napi_derive::napi;
+
+/// A source displayed under a custom tab name
+#[napi(object)]
+pub struct Named {
+    pub name: String,
+    /// Arrow IPC stream
+    pub data: Buffer,
+}
+
+/// Decode an Arrow IPC stream, as written by `tableToIPC(table, 'stream')`
+fn decode(bytes: &[u8]) -> Result<DataFrame> {
+    let reader = StreamReader::try_new(Cursor::new(bytes), None)
+        .map_err(|e| Error::from_reason(e.to_string()))?;
+    reader
+        .map(|batch| batch.map_err(|e| Error::from_reason(e.to_string())))
+        .collect()
+}
+
+/// Run the explorer on the libuv thread pool so the event loop, and `stats`,
+/// keep running
+pub struct Explore(Vec<Source>);
+
+impl Task for Explore {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<()> {
+        ::dtex::run(std::mem::take(&mut self.0).into_iter());
+        Ok(())
+    }
+
+    fn resolve(&mut self, _env: Env, _output: ()) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[napi(ts_return_type = "Promise<void>")]
+pub fn ex(sources: Vec<Either<Buffer, Named>>) -> Result<AsyncTask<Explore>> {
+    let sources = sources
+        .into_iter()
+        .map(|source| {
+            let (name, data) = match source {
+                Either::A(data) => ("js".into(), data),
+                Either::B(named) => (named.name, named.data),
+            };
+            Source::from_mem(name, decode(&data)?).map_err(|e| Error::from_reason(e.0))
+        })
+        .collect::<Result<_>>()?;
+    Ok(AsyncTask::new(Explore(sources)))
+}
+
+/// Resource usage of the running explorer
+#[napi(object)]
+pub struct Stats {
+    pub tabs: u32,
+    pub rows: i64,
+    pub bytes: i64,
+    pub tasks: u32,
+}
+
+#[napi]
+pub fn stats() -> Stats {
+    let stats = ::dtex::stats();
+    Stats {
+        tabs: stats.tabs as u32,
+        rows: stats.rows as i64,
+        bytes: stats.bytes as i64,
+        tasks: stats.tasks as u32,
+    }
+}
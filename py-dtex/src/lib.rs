@@ -9,7 +9,11 @@ use ::dtex::{
     DataFrame,
 };
 use pyo3::{
-    exceptions::PyValueError, ffi::Py_uintptr_t, prelude::*, types::PyList, wrap_pyfunction,
+    exceptions::PyValueError,
+    ffi::Py_uintptr_t,
+    prelude::*,
+    types::{PyDict, PyList},
+    wrap_pyfunction,
 };
 
 static CACHE: OnceLock<Extractor> = OnceLock::new();
@@ -148,17 +152,35 @@ impl Args {
 }
 
 #[pyfunction]
-fn ex(sources: Vec<Args>) -> PyResult<()> {
-    ::dtex::run(sources.into_iter().map(|s| {
-        let (name, df) = s.parts();
-        ::dtex::Source::from_mem(name, df)
-    }));
+fn ex(py: Python<'_>, sources: Vec<Args>) -> PyResult<()> {
+    let sources: Vec<_> = sources
+        .into_iter()
+        .map(|s| {
+            let (name, df) = s.parts();
+            ::dtex::Source::from_mem(name, df).map_err(|e| PyValueError::new_err(e.0))
+        })
+        .collect::<PyResult<_>>()?;
+    // Release the GIL so other threads can run, and poll `stats`, meanwhile
+    py.allow_threads(|| ::dtex::run(sources.into_iter()));
     Ok(())
 }
 
+/// Resource usage of the running explorer
+#[pyfunction]
+fn stats(py: Python<'_>) -> PyResult<&PyDict> {
+    let stats = ::dtex::stats();
+    let dict = PyDict::new(py);
+    dict.set_item("tabs", stats.tabs)?;
+    dict.set_item("rows", stats.rows)?;
+    dict.set_item("bytes", stats.bytes)?;
+    dict.set_item("tasks", stats.tasks)?;
+    Ok(dict)
+}
+
 #[pymodule]
 fn dtex(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(ex))?;
+    m.add_wrapped(wrap_pyfunction!(stats))?;
 
     Ok(())
 }
@@ -0,0 +1,96 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use flate2::read::GzDecoder;
+
+use crate::{error::Result, source::Source, temp::TempFile};
+
+/// File stored in an archive
+pub struct Member {
+    /// Path inside the archive
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Whether a file is a zip or tar archive, from its extension
+pub fn is_archive(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    [".zip", ".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|ext| path.ends_with(ext))
+}
+
+/// Members of an archive dtex can open
+pub fn members(path: &Path) -> Result<Vec<Member>> {
+    let mut members = vec![];
+    if path.to_string_lossy().ends_with(".zip") {
+        let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+        for i in 0..archive.len() {
+            let file = archive.by_index(i)?;
+            if file.is_file() {
+                members.push(Member {
+                    name: file.name().into(),
+                    size: file.size(),
+                    modified: None,
+                });
+            }
+        }
+    } else {
+        let mut archive = tar(path)?;
+        for entry in archive.entries()? {
+            let entry = entry?;
+            if entry.header().entry_type().is_file() {
+                members.push(Member {
+                    name: entry.path()?.to_string_lossy().to_string(),
+                    size: entry.size(),
+                    modified: entry
+                        .header()
+                        .mtime()
+                        .ok()
+                        .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
+                });
+            }
+        }
+    }
+    members.retain(|m| Source::is_supported(Path::new(&m.name)));
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(members)
+}
+
+/// Extract a member in the temporary directory, keeping its extensions
+pub fn extract(path: &Path, name: &str) -> Result<TempFile> {
+    let file_name = Path::new(name).file_name().unwrap_or_default();
+    let file_name = file_name.to_string_lossy();
+    let suffix = file_name.find('.').map_or("", |pos| &file_name[pos..]);
+    let extracted = TempFile::new(suffix)?;
+    let mut out = extracted.write()?;
+    if path.to_string_lossy().ends_with(".zip") {
+        let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+        std::io::copy(&mut archive.by_name(name)?, &mut out)?;
+    } else {
+        let mut archive = tar(path)?;
+        let mut entry = archive
+            .entries()?
+            .filter_map(|e| e.ok())
+            .find(|e| e.path().is_ok_and(|p| p.to_string_lossy() == name))
+            .ok_or_else(|| format!("No member {name} in the archive"))?;
+        std::io::copy(&mut entry, &mut out)?;
+    }
+    Ok(extracted)
+}
+
+/// Tar archive, decompressed on the fly when gzipped
+fn tar(path: &Path) -> Result<tar::Archive<Box<dyn Read>>> {
+    let file = BufReader::new(File::open(path)?);
+    let reader: Box<dyn Read> = if path.to_string_lossy().ends_with(".tar") {
+        Box::new(file)
+    } else {
+        Box::new(GzDecoder::new(file))
+    };
+    Ok(tar::Archive::new(reader))
+}
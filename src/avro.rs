@@ -0,0 +1,1031 @@
+use std::{borrow::Cow, collections::HashMap, fmt::Write, io::Read, sync::Arc};
+
+use arrow::{
+    array::{
+        new_null_array, ArrayRef, AsArray, BinaryArray, BooleanArray, Decimal128Array,
+        Decimal256Array, FixedSizeBinaryArray, Float32Array, Float64Array, Int32Array, Int64Array,
+        ListArray, MapArray, StringArray, StructArray,
+    },
+    buffer::{NullBuffer, OffsetBuffer},
+    compute::cast,
+    datatypes::{
+        i256, DataType, Field, FieldRef, Fields, Schema as ArrowSchema, SchemaRef, TimeUnit,
+    },
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use flate2::read::DeflateDecoder;
+use serde_json::{Map, Value as Json};
+
+use crate::error::Result;
+
+/// Number of records decoded per batch
+const BATCH_ROWS: usize = 1024;
+
+/// Maximum nesting of the values of recursive types, deeper values would
+/// overflow the stack
+const MAX_DEPTH: usize = 64;
+
+/// Maximum number of items of an array or a map taking no byte, nothing else
+/// bounds how many a file claims
+const MAX_EMPTY_ITEMS: u64 = 1 << 16;
+
+/// Type of the values of an Avro file
+#[derive(Clone)]
+enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Record(Vec<(String, Schema)>),
+    Enum(Vec<String>),
+    Array(Box<Schema>),
+    Map(Box<Schema>),
+    Union(Vec<Schema>),
+    Fixed(usize),
+    /// Interpretation of the underlying type
+    Logical(Logical, Box<Schema>),
+    /// Named type referenced from its own definition, its values are shown as
+    /// json since an arrow type cannot contain itself
+    Recursive(String),
+}
+
+/// Meaning of the values of an underlying type
+#[derive(Clone, Copy)]
+enum Logical {
+    /// Big endian two's complement unscaled value, with its precision and scale
+    Decimal(u8, i8),
+    /// Days since the unix epoch
+    Date,
+    TimeMillis,
+    TimeMicros,
+    /// Time since the unix epoch, in UTC or local time
+    Timestamp(TimeUnit, bool),
+    Uuid,
+}
+
+/// Decoded value, before it is added to its column
+enum Value {
+    Null,
+    Boolean(bool),
+    Int(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    Record(Vec<Value>),
+    Array(Vec<Value>),
+    Map(Vec<(String, Value)>),
+    /// Value of a union of several types, shown as json
+    Json(Json),
+}
+
+impl Schema {
+    /// Parse a json schema, `named` holds the types defined so far
+    fn parse(json: &Json, named: &mut HashMap<String, Schema>) -> Result<Self> {
+        let object = match json {
+            Json::String(name) => return Self::primitive(name, named),
+            Json::Array(branches) => {
+                let branches = branches.iter().map(|b| Self::parse(b, named));
+                return Ok(Self::Union(branches.collect::<Result<_>>()?));
+            }
+            Json::Object(object) => object,
+            _ => return Err("Invalid Avro schema".into()),
+        };
+        let ty = match object.get("type") {
+            Some(Json::String(ty)) => ty.as_str(),
+            Some(ty) => return Self::parse(ty, named),
+            None => return Err("Avro schema without type".into()),
+        };
+        let field = |key: &str| object.get(key).ok_or(format!("Avro {ty} without {key}"));
+        // Named types are registered before their definition, which can refer
+        // to them, by their name and their full name
+        let mut names = vec![];
+        if let Some(name) = object.get("name").and_then(Json::as_str) {
+            let short = name.rsplit('.').next().unwrap_or(name);
+            match object.get("namespace").and_then(Json::as_str) {
+                Some(namespace) if short == name => names.push(format!("{namespace}.{name}")),
+                _ => names.push(name.to_string()),
+            }
+            names.push(short.to_string());
+        }
+        for name in &names {
+            named.insert(name.clone(), Self::Recursive(names[0].clone()));
+        }
+        let schema = match ty {
+            "record" | "error" => {
+                let mut fields = vec![];
+                for f in field("fields")?.as_array().ok_or("Invalid Avro fields")? {
+                    let name = f["name"].as_str().ok_or("Avro field without name")?;
+                    fields.push((name.to_string(), Self::parse(&f["type"], named)?));
+                }
+                Self::Record(fields)
+            }
+            "enum" => {
+                let symbols = field("symbols")?.as_array().ok_or("Invalid Avro symbols")?;
+                let symbols = symbols.iter().filter_map(|s| s.as_str().map(String::from));
+                Self::Enum(symbols.collect())
+            }
+            "array" => Self::Array(Box::new(Self::parse(field("items")?, named)?)),
+            "map" => Self::Map(Box::new(Self::parse(field("values")?, named)?)),
+            "fixed" => Self::Fixed(field("size")?.as_u64().ok_or("Invalid Avro size")? as usize),
+            primitive => Self::primitive(primitive, named)?,
+        };
+        let schema = Self::logical(object, schema);
+        for name in names {
+            named.insert(name, schema.clone());
+        }
+        Ok(schema)
+    }
+
+    fn primitive(name: &str, named: &HashMap<String, Schema>) -> Result<Self> {
+        Ok(match name {
+            "null" => Self::Null,
+            "boolean" => Self::Boolean,
+            "int" => Self::Int,
+            "long" => Self::Long,
+            "float" => Self::Float,
+            "double" => Self::Double,
+            "bytes" => Self::Bytes,
+            "string" => Self::String,
+            name => named
+                .get(name)
+                .or_else(|| named.get(name.rsplit('.').next().unwrap_or(name)))
+                .cloned()
+                .ok_or(format!("Unknown Avro type {name}"))?,
+        })
+    }
+
+    /// Logical type annotating a schema, the specification asks to ignore
+    /// invalid ones
+    fn logical(object: &Map<String, Json>, schema: Self) -> Self {
+        let Some(logical) = object.get("logicalType").and_then(Json::as_str) else {
+            return schema;
+        };
+        let number = |key: &str| object.get(key).and_then(Json::as_u64).unwrap_or(0);
+        let (precision, scale) = (number("precision"), number("scale"));
+        let logical = match (logical, &schema) {
+            ("decimal", Self::Bytes | Self::Fixed(_)) if (1..=76).contains(&precision) => {
+                if scale > precision {
+                    return schema;
+                }
+                Logical::Decimal(precision as u8, scale as i8)
+            }
+            ("date", Self::Int) => Logical::Date,
+            ("time-millis", Self::Int) => Logical::TimeMillis,
+            ("time-micros", Self::Long) => Logical::TimeMicros,
+            ("timestamp-millis", Self::Long) => Logical::Timestamp(TimeUnit::Millisecond, true),
+            ("timestamp-micros", Self::Long) => Logical::Timestamp(TimeUnit::Microsecond, true),
+            ("timestamp-nanos", Self::Long) => Logical::Timestamp(TimeUnit::Nanosecond, true),
+            ("local-timestamp-millis", Self::Long) => {
+                Logical::Timestamp(TimeUnit::Millisecond, false)
+            }
+            ("local-timestamp-micros", Self::Long) => {
+                Logical::Timestamp(TimeUnit::Microsecond, false)
+            }
+            ("local-timestamp-nanos", Self::Long) => {
+                Logical::Timestamp(TimeUnit::Nanosecond, false)
+            }
+            ("uuid", Self::String | Self::Fixed(16)) => Logical::Uuid,
+            _ => return schema,
+        };
+        Self::Logical(logical, Box::new(schema))
+    }
+
+    /// Only type of a union besides null, None if it has several
+    fn nullable(branches: &[Schema]) -> Option<&Schema> {
+        let mut others = branches.iter().filter(|b| !matches!(b, Self::Null));
+        match (others.next(), others.next()) {
+            (Some(branch), None) => Some(branch),
+            (None, _) => Some(&Self::Null),
+            _ => None,
+        }
+    }
+
+    /// Minimum number of bytes taken by a value
+    fn min_size(&self) -> usize {
+        match self {
+            Self::Null | Self::Recursive(_) => 0,
+            Self::Float => 4,
+            Self::Double => 8,
+            Self::Fixed(size) => *size,
+            Self::Record(fields) => fields.iter().map(|(_, f)| f.min_size()).sum(),
+            Self::Logical(_, schema) => schema.min_size(),
+            _ => 1,
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            Self::Null => DataType::Null,
+            Self::Boolean => DataType::Boolean,
+            Self::Int => DataType::Int32,
+            Self::Long => DataType::Int64,
+            Self::Float => DataType::Float32,
+            Self::Double => DataType::Float64,
+            Self::Bytes => DataType::Binary,
+            Self::String | Self::Enum(_) | Self::Recursive(_) => DataType::Utf8,
+            Self::Record(fields) => DataType::Struct(Self::fields(fields)),
+            Self::Array(items) => DataType::List(Self::item(items)),
+            Self::Map(values) => DataType::Map(Self::entries(values), false),
+            Self::Union(branches) => match Self::nullable(branches) {
+                Some(branch) => branch.data_type(),
+                None => DataType::Utf8,
+            },
+            Self::Fixed(size) => DataType::FixedSizeBinary(*size as i32),
+            Self::Logical(logical, _) => match logical {
+                Logical::Decimal(precision, scale) if *precision <= 38 => {
+                    DataType::Decimal128(*precision, *scale)
+                }
+                Logical::Decimal(precision, scale) => DataType::Decimal256(*precision, *scale),
+                Logical::Date => DataType::Date32,
+                Logical::TimeMillis => DataType::Time32(TimeUnit::Millisecond),
+                Logical::TimeMicros => DataType::Time64(TimeUnit::Microsecond),
+                Logical::Timestamp(unit, utc) => {
+                    DataType::Timestamp(*unit, utc.then(|| "UTC".into()))
+                }
+                Logical::Uuid => DataType::Utf8,
+            },
+        }
+    }
+
+    fn fields(fields: &[(String, Schema)]) -> Fields {
+        let fields = fields
+            .iter()
+            .map(|(name, f)| Field::new(name, f.data_type(), true));
+        fields.collect()
+    }
+
+    fn item(items: &Schema) -> FieldRef {
+        Arc::new(Field::new("item", items.data_type(), true))
+    }
+
+    fn entries(values: &Schema) -> FieldRef {
+        let entry = DataType::Struct(Self::entry(values));
+        Arc::new(Field::new("entries", entry, false))
+    }
+
+    fn entry(values: &Schema) -> Fields {
+        Fields::from(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("value", values.data_type(), true),
+        ])
+    }
+
+    /// Decode a value, `named` holds the types of the file and `depth` counts
+    /// the recursive types being decoded
+    fn decode(
+        &self,
+        r: &mut &[u8],
+        named: &HashMap<String, Schema>,
+        depth: usize,
+    ) -> Result<Value> {
+        Ok(match self {
+            Self::Null => Value::Null,
+            Self::Boolean => Value::Boolean(byte(r)? != 0),
+            Self::Int | Self::Long => Value::Int(long(r)?),
+            Self::Float => {
+                let mut buf = [0; 4];
+                r.read_exact(&mut buf)?;
+                Value::Float(f32::from_le_bytes(buf).into())
+            }
+            Self::Double => {
+                let mut buf = [0; 8];
+                r.read_exact(&mut buf)?;
+                Value::Float(f64::from_le_bytes(buf))
+            }
+            Self::Bytes => Value::Bytes(bytes(r)?),
+            Self::String => Value::String(String::from_utf8(bytes(r)?)?),
+            Self::Record(fields) => {
+                let values = fields
+                    .iter()
+                    .map(|(_, schema)| schema.decode(r, named, depth));
+                Value::Record(values.collect::<Result<_>>()?)
+            }
+            Self::Enum(symbols) => {
+                let symbol = usize::try_from(long(r)?).ok().and_then(|i| symbols.get(i));
+                Value::String(symbol.ok_or("Invalid Avro enum symbol")?.clone())
+            }
+            Self::Array(items) => {
+                let mut values = vec![];
+                blocks(r, items.min_size(), |r| {
+                    values.push(items.decode(r, named, depth)?);
+                    Ok(())
+                })?;
+                Value::Array(values)
+            }
+            Self::Map(values) => {
+                let mut entries = vec![];
+                blocks(r, 1 + values.min_size(), |r| {
+                    let key = String::from_utf8(bytes(r)?)?;
+                    entries.push((key, values.decode(r, named, depth)?));
+                    Ok(())
+                })?;
+                Value::Map(entries)
+            }
+            Self::Union(branches) => {
+                let branch = usize::try_from(long(r)?).ok().and_then(|i| branches.get(i));
+                let branch = branch.ok_or("Invalid Avro union branch")?;
+                match (branch.decode(r, named, depth)?, Self::nullable(branches)) {
+                    (value @ Value::Null, _) | (value, Some(_)) => value,
+                    (value, None) => Value::Json(branch.json(value)),
+                }
+            }
+            Self::Fixed(size) => Value::Bytes(read(r, *size)?),
+            Self::Logical(_, schema) => schema.decode(r, named, depth)?,
+            Self::Recursive(name) => {
+                let schema = named.get(name).ok_or(format!("Unknown Avro type {name}"))?;
+                if depth >= MAX_DEPTH {
+                    return Err("Avro value nested too deeply".into());
+                }
+                Value::Json(schema.json(schema.decode(r, named, depth + 1)?))
+            }
+        })
+    }
+
+    /// Json of a value of this type, binary data is written in hexadecimal
+    fn json(&self, value: Value) -> Json {
+        match (self, value) {
+            (_, Value::Null) => Json::Null,
+            (_, Value::Boolean(b)) => Json::Bool(b),
+            (_, Value::Int(i)) => Json::from(i),
+            (_, Value::Float(f)) => {
+                serde_json::Number::from_f64(f).map_or(Json::Null, Json::Number)
+            }
+            (Self::Logical(Logical::Uuid, _), Value::Bytes(b)) => Json::String(uuid(&b)),
+            (_, Value::Bytes(b)) => Json::String(hex(&b)),
+            (_, Value::String(s)) => Json::String(s),
+            (Self::Record(fields), Value::Record(values)) => {
+                let fields = fields.iter().zip(values);
+                let fields = fields.map(|((name, f), v)| (name.clone(), f.json(v)));
+                Json::Object(fields.collect())
+            }
+            (Self::Array(items), Value::Array(values)) => {
+                Json::Array(values.into_iter().map(|v| items.json(v)).collect())
+            }
+            (Self::Map(schema), Value::Map(entries)) => {
+                let entries = entries.into_iter().map(|(k, v)| (k, schema.json(v)));
+                Json::Object(entries.collect())
+            }
+            (_, Value::Json(json)) => json,
+            (Self::Union(branches), value) => match Self::nullable(branches) {
+                Some(branch) => branch.json(value),
+                None => Json::Null,
+            },
+            _ => Json::Null,
+        }
+    }
+
+    /// Column of decoded values of this type, `Value::Null` are nulls
+    fn array(&self, values: Vec<Value>) -> Result<ArrayRef> {
+        let nulls = || {
+            let valid: Vec<_> = values.iter().map(|v| !matches!(v, Value::Null)).collect();
+            Some(NullBuffer::from(valid)).filter(|n| n.null_count() > 0)
+        };
+        let ints = || values.iter().map(Value::int);
+        let strings = || Arc::new(values.iter().map(Value::text).collect::<StringArray>());
+        Ok(match self {
+            Self::Null => new_null_array(&DataType::Null, values.len()),
+            Self::Boolean => Arc::new(values.iter().map(Value::boolean).collect::<BooleanArray>()),
+            Self::Int => Arc::new(ints().map(|i| i.map(|i| i as i32)).collect::<Int32Array>()),
+            Self::Long => Arc::new(ints().collect::<Int64Array>()),
+            Self::Float => {
+                let floats = values.iter().map(|v| v.float().map(|f| f as f32));
+                Arc::new(floats.collect::<Float32Array>())
+            }
+            Self::Double => Arc::new(values.iter().map(Value::float).collect::<Float64Array>()),
+            Self::Bytes => Arc::new(values.iter().map(Value::bytes).collect::<BinaryArray>()),
+            Self::String | Self::Enum(_) | Self::Recursive(_) => strings(),
+            Self::Union(branches) => match Self::nullable(branches) {
+                Some(branch) => branch.array(values)?,
+                None => strings(),
+            },
+            Self::Fixed(size) => Arc::new(FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                values.iter().map(Value::bytes),
+                *size as i32,
+            )?),
+            Self::Record(fields) => {
+                let nulls = nulls();
+                let len = values.len();
+                let mut columns: Vec<_> = fields.iter().map(|_| Vec::with_capacity(len)).collect();
+                for value in values {
+                    match value {
+                        Value::Record(record) => {
+                            columns.iter_mut().zip(record).for_each(|(c, v)| c.push(v))
+                        }
+                        _ => columns.iter_mut().for_each(|c| c.push(Value::Null)),
+                    }
+                }
+                let arrays = fields.iter().zip(columns).map(|((_, f), c)| f.array(c));
+                let arrays = arrays.collect::<Result<Vec<_>>>()?;
+                if arrays.is_empty() {
+                    Arc::new(StructArray::new_empty_fields(len, nulls))
+                } else {
+                    Arc::new(StructArray::try_new(Self::fields(fields), arrays, nulls)?)
+                }
+            }
+            Self::Array(items) => {
+                let nulls = nulls();
+                let (mut lengths, mut flat) = (Vec::with_capacity(values.len()), vec![]);
+                for value in values {
+                    let items = match value {
+                        Value::Array(items) => items,
+                        _ => vec![],
+                    };
+                    lengths.push(items.len());
+                    flat.extend(items);
+                }
+                let offsets = OffsetBuffer::from_lengths(lengths);
+                let flat = items.array(flat)?;
+                Arc::new(ListArray::try_new(Self::item(items), offsets, flat, nulls)?)
+            }
+            Self::Map(schema) => {
+                let nulls = nulls();
+                let (mut lengths, mut keys, mut flat) = (vec![], vec![], vec![]);
+                for value in values {
+                    let entries = match value {
+                        Value::Map(entries) => entries,
+                        _ => vec![],
+                    };
+                    lengths.push(entries.len());
+                    for (key, value) in entries {
+                        keys.push(key);
+                        flat.push(value);
+                    }
+                }
+                let keys = Arc::new(StringArray::from(keys));
+                let entries = vec![keys, schema.array(flat)?];
+                let entries = StructArray::try_new(Self::entry(schema), entries, None)?;
+                let offsets = OffsetBuffer::from_lengths(lengths);
+                let field = Self::entries(schema);
+                Arc::new(MapArray::try_new(field, offsets, entries, nulls, false)?)
+            }
+            Self::Logical(logical, schema) => match logical {
+                Logical::Decimal(precision, scale) if *precision <= 38 => {
+                    let decimals = values.iter().map(|v| {
+                        let bytes = v.bytes().map(sign_extend);
+                        bytes.transpose().map(|b| b.map(i128::from_be_bytes))
+                    });
+                    let decimals = decimals.collect::<Result<Decimal128Array>>()?;
+                    Arc::new(decimals.with_precision_and_scale(*precision, *scale)?)
+                }
+                Logical::Decimal(precision, scale) => {
+                    let decimals = values.iter().map(|v| {
+                        let bytes = v.bytes().map(sign_extend);
+                        bytes.transpose().map(|b| b.map(i256::from_be_bytes))
+                    });
+                    let decimals = decimals.collect::<Result<Decimal256Array>>()?;
+                    Arc::new(decimals.with_precision_and_scale(*precision, *scale)?)
+                }
+                Logical::Uuid => match schema.as_ref() {
+                    Self::Fixed(_) => {
+                        let uuids = values.iter().map(|v| v.bytes().map(uuid));
+                        Arc::new(uuids.collect::<StringArray>())
+                    }
+                    schema => schema.array(values)?,
+                },
+                // The underlying integers are cast to their temporal type
+                _ => cast(&schema.array(values)?, &self.data_type())?,
+            },
+        })
+    }
+}
+
+impl Value {
+    fn boolean(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn int(&self) -> Option<i64> {
+        match self {
+            Self::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    fn float(&self) -> Option<f64> {
+        match self {
+            Self::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    fn text(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::String(s) => Some(Cow::Borrowed(s)),
+            Self::Json(json) => Some(Cow::Owned(json.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Batches of the records of an Avro object container file, decoded one block
+/// at a time, values that are not records are put in a `value` column
+pub fn batches(mut reader: impl Read) -> Result<impl Iterator<Item = Result<RecordBatch>>> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"Obj\x01" {
+        return Err("Not an Avro object container file".into());
+    }
+    let mut meta = HashMap::new();
+    loop {
+        let count = long(&mut reader)?;
+        if count == 0 {
+            break;
+        }
+        // A negative count is followed by the size of the block in bytes
+        if count < 0 {
+            long(&mut reader)?;
+        }
+        for _ in 0..count.unsigned_abs() {
+            let key = String::from_utf8(bytes(&mut reader)?)?;
+            meta.insert(key, bytes(&mut reader)?);
+        }
+    }
+    let schema = meta.get("avro.schema").ok_or("Avro file without schema")?;
+    let mut named = HashMap::new();
+    let schema = Schema::parse(&serde_json::from_slice(schema)?, &mut named)?;
+    let codec = match meta.get("avro.codec").map(|c| c.as_slice()) {
+        None | Some(b"null") => Codec::Null,
+        Some(b"deflate") => Codec::Deflate,
+        Some(b"snappy") => Codec::Snappy,
+        Some(codec) => {
+            let codec = String::from_utf8_lossy(codec);
+            return Err(format!("Unsupported Avro codec {codec}").into());
+        }
+    };
+    let mut sync = [0; 16];
+    reader.read_exact(&mut sync)?;
+    let columns = Arc::new(match &schema {
+        Schema::Record(fields) => ArrowSchema::new(Schema::fields(fields)),
+        schema => ArrowSchema::new(vec![Field::new("value", schema.data_type(), true)]),
+    });
+
+    let top = schema.clone();
+    let (mut block, mut pos, mut remaining) = (vec![], 0, 0);
+    let mut next = move || -> Result<Option<Value>> {
+        while remaining == 0 {
+            // The file ends after the sync marker of a block
+            let mut first = [0];
+            if reader.read(&mut first)? == 0 {
+                return Ok(None);
+            }
+            remaining = long(&mut first.as_slice().chain(&mut reader))?;
+            let size = usize::try_from(long(&mut reader)?).map_err(|_| "Invalid Avro block")?;
+            let data = read(&mut reader, size)?;
+            let mut marker = [0; 16];
+            reader.read_exact(&mut marker)?;
+            if marker != sync {
+                return Err("Corrupted Avro block".into());
+            }
+            block = codec.decompress(data)?;
+            pos = 0;
+            if remaining <= 0 || !fits(remaining.unsigned_abs(), schema.min_size(), block.len()) {
+                return Err("Invalid Avro block".into());
+            }
+        }
+        remaining -= 1;
+        let mut data = &block[pos..];
+        let value = schema.decode(&mut data, &named, 0)?;
+        pos = block.len() - data.len();
+        Ok(Some(value))
+    };
+    let mut done = false;
+    Ok(std::iter::from_fn(move || {
+        let mut values = vec![];
+        while !done && values.len() < BATCH_ROWS {
+            match next() {
+                Ok(Some(value)) => values.push(value),
+                Ok(None) => done = true,
+                Err(err) => {
+                    done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        (!values.is_empty()).then(|| batch(&top, &columns, values))
+    }))
+}
+
+fn batch(schema: &Schema, columns: &SchemaRef, values: Vec<Value>) -> Result<RecordBatch> {
+    let options = RecordBatchOptions::new().with_row_count(Some(values.len()));
+    let array = schema.array(values)?;
+    let arrays = match schema {
+        Schema::Record(_) => array.as_struct().columns().to_vec(),
+        _ => vec![array],
+    };
+    Ok(RecordBatch::try_new_with_options(
+        columns.clone(),
+        arrays,
+        &options,
+    )?)
+}
+
+/// Compression of the blocks of a file
+enum Codec {
+    Null,
+    Deflate,
+    Snappy,
+}
+
+impl Codec {
+    fn decompress(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        Ok(match self {
+            Self::Null => data,
+            Self::Deflate => {
+                let mut buf = vec![];
+                DeflateDecoder::new(data.as_slice()).read_to_end(&mut buf)?;
+                buf
+            }
+            // The compressed data is followed by the big endian crc32 checksum
+            // of the uncompressed data
+            Self::Snappy => {
+                let Some(pos) = data.len().checked_sub(4) else {
+                    return Err("Invalid Avro block".into());
+                };
+                let (data, crc) = data.split_at(pos);
+                let data = snap::raw::Decoder::new().decompress_vec(data)?;
+                if crc32fast::hash(&data).to_be_bytes() != crc {
+                    return Err("Corrupted Avro block".into());
+                }
+                data
+            }
+        })
+    }
+}
+
+/// Items of an array or a map, in blocks prefixed by their count, each item
+/// taking at least `size` bytes
+fn blocks(
+    r: &mut &[u8],
+    size: usize,
+    mut item: impl FnMut(&mut &[u8]) -> Result<()>,
+) -> Result<()> {
+    let mut total = 0;
+    loop {
+        let count = long(r)?;
+        if count == 0 {
+            return Ok(());
+        }
+        // A negative count is followed by the size of the block in bytes
+        if count < 0 {
+            long(r)?;
+        }
+        total += count.unsigned_abs();
+        if !fits(count.unsigned_abs(), size, r.len()) || (size == 0 && total > MAX_EMPTY_ITEMS) {
+            return Err("Invalid Avro block".into());
+        }
+        for _ in 0..count.unsigned_abs() {
+            item(r)?;
+        }
+    }
+}
+
+/// Whether `count` items of at least `size` bytes can be read from `len`
+/// bytes, items taking no byte are bounded by `MAX_EMPTY_ITEMS`
+fn fits(count: u64, size: usize, len: usize) -> bool {
+    match size {
+        0 => count <= MAX_EMPTY_ITEMS,
+        size => count <= (len / size) as u64,
+    }
+}
+
+/// Zigzag encoded variable length integer
+fn long(r: &mut impl Read) -> Result<i64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = byte(r)?;
+        value |= u64::from(b & 0x7f) << shift;
+        if b & 0x80 == 0 {
+            return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+        }
+    }
+    Err("Invalid Avro integer".into())
+}
+
+fn byte(r: &mut impl Read) -> Result<u8> {
+    let mut buf = [0];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn bytes(r: &mut impl Read) -> Result<Vec<u8>> {
+    let len = usize::try_from(long(r)?).map_err(|_| "Invalid Avro length")?;
+    read(r, len)
+}
+
+/// Exactly `len` bytes, the buffer grows as they are read since the length
+/// comes from the file and can be anything
+fn read(r: &mut impl Read, len: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![];
+    r.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() < len {
+        return Err("Truncated Avro file".into());
+    }
+    Ok(buf)
+}
+
+/// Big endian two's complement integer extended to `N` bytes
+fn sign_extend<const N: usize>(bytes: &[u8]) -> Result<[u8; N]> {
+    if bytes.len() > N {
+        return Err("Avro decimal out of range".into());
+    }
+    let fill = if bytes.first().is_some_and(|b| b & 0x80 != 0) {
+        0xff
+    } else {
+        0
+    };
+    let mut buf = [fill; N];
+    buf[N - bytes.len()..].copy_from_slice(bytes);
+    Ok(buf)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, b| {
+        write!(hex, "{b:02x}").unwrap();
+        hex
+    })
+}
+
+/// Hyphenated form of a 16 bytes uuid
+fn uuid(bytes: &[u8]) -> String {
+    let hex = hex(bytes);
+    if hex.len() != 32 {
+        return hex;
+    }
+    let (a, rest) = hex.split_at(8);
+    let (b, rest) = rest.split_at(4);
+    let (c, rest) = rest.split_at(4);
+    let (d, e) = rest.split_at(4);
+    format!("{a}-{b}-{c}-{d}-{e}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use arrow::{
+        array::Array,
+        datatypes::{Date32Type, Decimal128Type, Int32Type, Int64Type},
+    };
+
+    use super::*;
+
+    const SYNC: [u8; 16] = [7; 16];
+
+    fn write_long(buf: &mut Vec<u8>, value: i64) {
+        let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+        while zigzag >= 0x80 {
+            buf.push(zigzag as u8 | 0x80);
+            zigzag >>= 7;
+        }
+        buf.push(zigzag as u8);
+    }
+
+    fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+        write_long(buf, bytes.len() as i64);
+        buf.extend_from_slice(bytes);
+    }
+
+    /// Container file holding blocks of encoded records with their count
+    fn file(schema: &str, codec: &str, blocks: &[(i64, Vec<u8>)]) -> Vec<u8> {
+        let mut buf = b"Obj\x01".to_vec();
+        write_long(&mut buf, 2);
+        write_bytes(&mut buf, b"avro.schema");
+        write_bytes(&mut buf, schema.as_bytes());
+        write_bytes(&mut buf, b"avro.codec");
+        write_bytes(&mut buf, codec.as_bytes());
+        write_long(&mut buf, 0);
+        buf.extend(SYNC);
+        for (count, data) in blocks {
+            let data = match codec {
+                "deflate" => {
+                    let mut encoder =
+                        flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+                    encoder.write_all(data).unwrap();
+                    encoder.finish().unwrap()
+                }
+                "snappy" => {
+                    let mut compressed = snap::raw::Encoder::new().compress_vec(data).unwrap();
+                    compressed.extend(crc32fast::hash(data).to_be_bytes());
+                    compressed
+                }
+                _ => data.clone(),
+            };
+            write_long(&mut buf, *count);
+            write_bytes(&mut buf, &data);
+            buf.extend(SYNC);
+        }
+        buf
+    }
+
+    fn decode(file: &[u8]) -> Result<Vec<RecordBatch>> {
+        batches(file)?.collect()
+    }
+
+    const PEOPLE: &str = r#"{"type": "record", "name": "person", "fields": [
+        {"name": "id", "type": "long"},
+        {"name": "name", "type": "string"}
+    ]}"#;
+
+    fn people() -> Vec<u8> {
+        let mut data = vec![];
+        for (id, name) in [(1, "ada"), (-2, "grace")] {
+            write_long(&mut data, id);
+            write_bytes(&mut data, name.as_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn codecs() {
+        for codec in ["null", "deflate", "snappy"] {
+            let batches = decode(&file(PEOPLE, codec, &[(2, people()), (2, people())])).unwrap();
+            assert_eq!(batches.len(), 1, "{codec}");
+            let batch = &batches[0];
+            assert_eq!(batch.num_rows(), 4);
+            let ids = batch.column(0).as_primitive::<Int64Type>();
+            assert_eq!(ids.values().to_vec(), vec![1, -2, 1, -2]);
+            let names = batch.column(1).as_string::<i32>();
+            assert_eq!(names.value(1), "grace");
+        }
+    }
+
+    #[test]
+    fn corrupted_snappy_block() {
+        let mut file = file(PEOPLE, "snappy", &[(2, people())]);
+        // Last byte of the checksum, before the sync marker
+        let pos = file.len() - SYNC.len() - 1;
+        file[pos] ^= 1;
+        assert!(decode(&file).is_err());
+    }
+
+    #[test]
+    fn truncated() {
+        let file = file(PEOPLE, "null", &[(2, people())]);
+        for len in [3, 20, file.len() - 20, file.len() - 1] {
+            assert!(decode(&file[..len]).is_err(), "{len}");
+        }
+    }
+
+    #[test]
+    fn invalid_counts_and_lengths() {
+        // Negative record count
+        assert!(decode(&file(PEOPLE, "null", &[(-2, people())])).is_err());
+        // More records than the block can hold
+        assert!(decode(&file(PEOPLE, "null", &[(1 << 40, people())])).is_err());
+        // String longer than the file
+        let mut data = vec![];
+        write_long(&mut data, 1);
+        write_long(&mut data, 1 << 60);
+        assert!(decode(&file(PEOPLE, "null", &[(1, data)])).is_err());
+        // Endless array of nulls
+        let mut data = vec![];
+        write_long(&mut data, i64::MAX);
+        let schema = r#"{"type": "array", "items": "null"}"#;
+        assert!(decode(&file(schema, "null", &[(1, data)])).is_err());
+    }
+
+    #[test]
+    fn unions() {
+        let schema = r#"{"type": "record", "name": "r", "fields": [
+            {"name": "a", "type": ["null", "string"]},
+            {"name": "b", "type": ["int", "string"]}
+        ]}"#;
+        let mut data = vec![];
+        write_long(&mut data, 0);
+        write_long(&mut data, 0);
+        write_long(&mut data, 3);
+        write_long(&mut data, 1);
+        write_bytes(&mut data, b"x");
+        write_long(&mut data, 1);
+        write_bytes(&mut data, b"y");
+        let batch = &decode(&file(schema, "null", &[(2, data)])).unwrap()[0];
+        let a = batch.column(0).as_string::<i32>();
+        assert!(a.is_null(0));
+        assert_eq!(a.value(1), "x");
+        let b = batch.column(1).as_string::<i32>();
+        assert_eq!(b.value(0), "3");
+        assert_eq!(b.value(1), r#""y""#);
+    }
+
+    #[test]
+    fn maps_and_enums() {
+        let schema = r#"{"type": "record", "name": "r", "fields": [
+            {"name": "m", "type": {"type": "map", "values": "int"}},
+            {"name": "e", "type": {"type": "enum", "name": "e", "symbols": ["A", "B"]}}
+        ]}"#;
+        let mut data = vec![];
+        write_long(&mut data, 2);
+        write_bytes(&mut data, b"k");
+        write_long(&mut data, 7);
+        write_bytes(&mut data, b"l");
+        write_long(&mut data, -7);
+        write_long(&mut data, 0);
+        write_long(&mut data, 1);
+        let batch = &decode(&file(schema, "null", &[(1, data)])).unwrap()[0];
+        let map = batch.column(0).as_map();
+        assert_eq!(map.value_length(0), 2);
+        assert_eq!(map.keys().as_string::<i32>().value(1), "l");
+        let values = map.values().as_primitive::<Int32Type>();
+        assert_eq!(values.values().to_vec(), vec![7, -7]);
+        assert_eq!(batch.column(1).as_string::<i32>().value(0), "B");
+    }
+
+    #[test]
+    fn negative_block_counts() {
+        let schema = r#"{"type": "array", "items": "int"}"#;
+        let mut items = vec![];
+        write_long(&mut items, 4);
+        write_long(&mut items, 5);
+        let mut data = vec![];
+        // A negative count is followed by the size of its items
+        write_long(&mut data, -2);
+        write_long(&mut data, items.len() as i64);
+        data.extend(items);
+        write_long(&mut data, 1);
+        write_long(&mut data, 6);
+        write_long(&mut data, 0);
+        let batch = &decode(&file(schema, "null", &[(1, data)])).unwrap()[0];
+        assert_eq!(batch.schema().field(0).name(), "value");
+        let list = batch.column(0).as_list::<i32>();
+        let values = list.values().as_primitive::<Int32Type>();
+        assert_eq!(values.values().to_vec(), vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn binary() {
+        let schema = r#"{"type": "record", "name": "r", "fields": [
+            {"name": "b", "type": "bytes"},
+            {"name": "f", "type": {"type": "fixed", "name": "f", "size": 2}}
+        ]}"#;
+        let mut data = vec![];
+        write_bytes(&mut data, &[0xff, 0, 0x89]);
+        data.extend([0xc3, 0x28]);
+        let batch = &decode(&file(schema, "null", &[(1, data)])).unwrap()[0];
+        assert_eq!(batch.column(0).as_binary::<i32>().value(0), [0xff, 0, 0x89]);
+        let fixed = batch.column(1).as_fixed_size_binary();
+        assert_eq!(fixed.value(0), [0xc3, 0x28]);
+    }
+
+    #[test]
+    fn logical_types() {
+        let schema = r#"{"type": "record", "name": "r", "fields": [
+            {"name": "d", "type": {"type": "bytes", "logicalType": "decimal", "precision": 5, "scale": 2}},
+            {"name": "day", "type": {"type": "int", "logicalType": "date"}},
+            {"name": "ts", "type": {"type": "long", "logicalType": "timestamp-micros"}},
+            {"name": "id", "type": {"type": "string", "logicalType": "uuid"}}
+        ]}"#;
+        let mut data = vec![];
+        // -12345 in two's complement
+        write_bytes(&mut data, &[0xcf, 0xc7]);
+        write_long(&mut data, 19000);
+        write_long(&mut data, 1_700_000_000_000_000);
+        write_bytes(&mut data, b"0f8fad5b-d9cb-469f-a165-70867728950e");
+        let batch = &decode(&file(schema, "null", &[(1, data)])).unwrap()[0];
+        let decimals = batch.column(0).as_primitive::<Decimal128Type>();
+        assert_eq!(decimals.value_as_string(0), "-123.45");
+        assert_eq!(batch.column(1).as_primitive::<Date32Type>().value(0), 19000);
+        assert_eq!(
+            batch.schema().field(2).data_type(),
+            &DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+        );
+        assert_eq!(batch.column(3).data_type(), &DataType::Utf8);
+    }
+
+    #[test]
+    fn recursive() {
+        let schema = r#"{"type": "record", "name": "node", "namespace": "list", "fields": [
+            {"name": "value", "type": "int"},
+            {"name": "next", "type": ["null", "list.node"]}
+        ]}"#;
+        let mut data = vec![];
+        for (value, next) in [(1, 1), (2, 1), (3, 0)] {
+            write_long(&mut data, value);
+            write_long(&mut data, next);
+        }
+        let batch = &decode(&file(schema, "null", &[(1, data)])).unwrap()[0];
+        let next = batch.column(1).as_string::<i32>();
+        let expected = r#"{"value":2,"next":{"value":3,"next":null}}"#;
+        assert_eq!(next.value(0), expected);
+        // A list deeper than the stack allows
+        let mut data = vec![];
+        for _ in 0..1000 {
+            write_long(&mut data, 0);
+            write_long(&mut data, 1);
+        }
+        assert!(decode(&file(schema, "null", &[(1, data)])).is_err());
+    }
+}
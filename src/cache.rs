@@ -0,0 +1,114 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::source::DataFrame;
+
+/// Memory size of the cached results of all sources in bytes
+const MAX_SIZE: usize = 256 * 1024 * 1024;
+
+/// Results of every database, so the bound holds however many tabs are open
+static ENTRIES: Mutex<Entries> = Mutex::new(Entries {
+    list: VecDeque::new(),
+    size: 0,
+});
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Complete results of recent queries keyed by their SQL, shared by all the
+/// sources of a database and evicted least recently used first
+#[derive(Clone, Default)]
+pub struct Cache(Arc<Id>);
+
+/// Database of the cached results, they are dropped with its last source
+struct Id(usize);
+
+impl Default for Id {
+    fn default() -> Self {
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Drop for Id {
+    fn drop(&mut self) {
+        ENTRIES.lock().unwrap().retain(|id, _| id != self.0);
+    }
+}
+
+struct Entries {
+    list: VecDeque<(usize, String, DataFrame)>,
+    size: usize,
+}
+
+impl Entries {
+    fn position(&self, id: usize, sql: &str) -> Option<usize> {
+        self.list
+            .iter()
+            .position(|(i, key, _)| *i == id && key == sql)
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(usize, &str) -> bool) {
+        let size = &mut self.size;
+        self.list.retain(|(id, key, df)| {
+            let kept = keep(*id, key);
+            if !kept {
+                *size -= df.mem_size();
+            }
+            kept
+        });
+    }
+}
+
+impl Cache {
+    pub fn get(&self, sql: &str) -> Option<DataFrame> {
+        let mut entries = ENTRIES.lock().unwrap();
+        let pos = entries.position(self.0 .0, sql)?;
+        let entry = entries.list.remove(pos)?;
+        let df = entry.2.clone();
+        entries.list.push_back(entry);
+        Some(df)
+    }
+
+    pub fn contains(&self, sql: &str) -> bool {
+        ENTRIES.lock().unwrap().position(self.0 .0, sql).is_some()
+    }
+
+    pub fn insert(&self, sql: String, df: DataFrame) {
+        let size = df.mem_size();
+        if size > MAX_SIZE {
+            return;
+        }
+        let mut entries = ENTRIES.lock().unwrap();
+        if let Some(pos) = entries.position(self.0 .0, &sql) {
+            let (_, _, prev) = entries.list.remove(pos).unwrap();
+            entries.size -= prev.mem_size();
+        }
+        while entries.size + size > MAX_SIZE {
+            let Some((_, _, old)) = entries.list.pop_front() else {
+                break;
+            };
+            entries.size -= old.mem_size();
+        }
+        entries.size += size;
+        entries.list.push_back((self.0 .0, sql, df));
+    }
+
+    /// Forget the result of a query, its data is no longer wanted
+    pub fn remove(&self, sql: &str) {
+        let id = self.0 .0;
+        ENTRIES
+            .lock()
+            .unwrap()
+            .retain(|i, key| i != id || key != sql);
+    }
+
+    /// Forget every result, the data they were computed from changed
+    pub fn clear(&self) {
+        let id = self.0 .0;
+        ENTRIES.lock().unwrap().retain(|i, _| i != id);
+    }
+}
@@ -0,0 +1,189 @@
+use std::{collections::HashSet, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, StringArray},
+    record_batch::RecordBatch,
+};
+
+use crate::{
+    error::Result,
+    grid::{Grid, Settings},
+    source::{DataFrame, Source},
+    sql,
+    task::{DuckTask, Runner},
+    view::{View, ViewState},
+};
+
+const OBJECTS: &str = "\
+SELECT 'table' AS kind, schema_name, table_name FROM duckdb_tables() \
+UNION ALL SELECT 'view', schema_name, view_name FROM duckdb_views() WHERE NOT internal \
+UNION ALL SELECT DISTINCT 'macro', schema_name, function_name FROM duckdb_functions() \
+WHERE function_type IN ('macro', 'table_macro') AND NOT internal \
+ORDER BY 1, 2, 3";
+
+const COLUMNS: &str = "\
+SELECT schema_name, table_name, column_name, data_type FROM duckdb_columns() \
+ORDER BY schema_name, table_name, column_index";
+
+/// Table, view or macro of the database
+pub struct Object {
+    pub kind: String,
+    pub schema: String,
+    pub name: String,
+    columns: Vec<(String, String)>,
+}
+
+impl Object {
+    /// Qualified name usable in a query
+    pub fn ident(&self) -> String {
+        if self.schema == "main" {
+            sql::ident(&self.name)
+        } else {
+            format!("{}.{}", sql::ident(&self.schema), sql::ident(&self.name))
+        }
+    }
+
+    /// Whether it can be queried on its own
+    pub fn is_relation(&self) -> bool {
+        self.kind != "macro"
+    }
+}
+
+/// Tables, views and macros visible to a source connection
+pub struct CatalogView {
+    task: Option<DuckTask<Vec<Object>>>,
+    objects: Vec<Object>,
+    expanded: HashSet<usize>,
+    /// Object of each displayed row
+    rows: Vec<usize>,
+    df: DataFrame,
+    error: Option<String>,
+    pub grid: Grid,
+}
+
+impl CatalogView {
+    pub fn new(source: Arc<Source>, runner: &Runner, settings: Settings) -> Self {
+        Self {
+            task: Some(runner.duckdb(source, move |_, con| {
+                let mut objects: Vec<Object> = vec![];
+                let df = con
+                    .query(OBJECTS)?
+                    .map(|d| d.map_err(|e| e.into()))
+                    .collect::<Result<DataFrame>>()?;
+                for row in 0..df.num_rows() {
+                    let [kind, schema, name] =
+                        [0, 1, 2].map(|i| df.value(row, i).unwrap_or_default());
+                    objects.push(Object {
+                        kind,
+                        schema,
+                        name,
+                        columns: vec![],
+                    })
+                }
+                let df = con
+                    .query(COLUMNS)?
+                    .map(|d| d.map_err(|e| e.into()))
+                    .collect::<Result<DataFrame>>()?;
+                for row in 0..df.num_rows() {
+                    let [schema, table, name, ty] =
+                        [0, 1, 2, 3].map(|i| df.value(row, i).unwrap_or_default());
+                    if let Some(object) = objects
+                        .iter_mut()
+                        .find(|o| o.is_relation() && o.schema == schema && o.name == table)
+                    {
+                        object.columns.push((name, ty));
+                    }
+                }
+                Ok(objects)
+            })),
+            objects: vec![],
+            expanded: HashSet::new(),
+            rows: vec![],
+            df: DataFrame::empty(),
+            error: None,
+            grid: Grid::new(settings),
+        }
+    }
+
+    /// Object under the cursor
+    pub fn selected(&self) -> Option<&Object> {
+        self.rows
+            .get(self.grid.nav.c_row())
+            .map(|idx| &self.objects[*idx])
+    }
+
+    /// Show or hide the columns of the object under the cursor
+    pub fn toggle(&mut self) {
+        if let Some(idx) = self.rows.get(self.grid.nav.c_row()) {
+            if !self.expanded.remove(idx) {
+                self.expanded.insert(*idx);
+            }
+            self.rebuild();
+        }
+    }
+
+    fn rebuild(&mut self) {
+        let mut cols: [Vec<String>; 4] = Default::default();
+        self.rows.clear();
+        for (idx, object) in self.objects.iter().enumerate() {
+            let expanded = self.expanded.contains(&idx);
+            let mark = match (object.columns.is_empty(), expanded) {
+                (true, _) => ' ',
+                (false, true) => '▾',
+                (false, false) => '▸',
+            };
+            for (col, value) in cols.iter_mut().zip([
+                format!("{mark} {}", object.name),
+                object.kind.clone(),
+                object.schema.clone(),
+                String::new(),
+            ]) {
+                col.push(value);
+            }
+            self.rows.push(idx);
+            if expanded {
+                for (name, ty) in &object.columns {
+                    for (col, value) in cols.iter_mut().zip([
+                        format!("    {name}"),
+                        "column".into(),
+                        object.schema.clone(),
+                        ty.clone(),
+                    ]) {
+                        col.push(value);
+                    }
+                    self.rows.push(idx);
+                }
+            }
+        }
+        let cols = ["name", "kind", "schema", "type"]
+            .into_iter()
+            .zip(cols)
+            .map(|(name, values)| (name, Arc::new(StringArray::from(values)) as ArrayRef));
+        self.df = RecordBatch::try_from_iter(cols).unwrap().into();
+    }
+}
+
+impl View for CatalogView {
+    fn tick(&mut self) -> ViewState {
+        match self.task.as_mut().and_then(|t| t.tick()) {
+            Some(Ok(objects)) => {
+                self.objects = objects;
+                self.task = None;
+                self.rebuild();
+            }
+            Some(Err(it)) => {
+                self.error = Some(it.0);
+                self.task = None;
+            }
+            None => {}
+        }
+
+        ViewState {
+            loading: self.task.as_ref().map(|t| ("catalog".into(), t.progress())),
+            streaming: false,
+            frame: &self.df,
+            grid: &mut self.grid,
+            err: self.error.as_deref(),
+        }
+    }
+}
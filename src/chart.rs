@@ -0,0 +1,152 @@
+use arrow::{
+    array::AsArray,
+    compute::cast,
+    datatypes::{DataType, Float64Type},
+};
+use libduckdb_sys::duckdb_query_progress_type;
+use tui::{none, unicode_width::UnicodeWidthStr, Canvas, Style};
+
+use crate::{error::Result, source::DataFrame, sql, style};
+
+/// Full screen visualisation computed in the background
+pub trait Chart {
+    fn label(&self) -> &'static str;
+    fn tick(&mut self) -> Option<(&'static str, duckdb_query_progress_type)>;
+    fn err(&self) -> Option<&str>;
+    fn draw(&self, c: &mut Canvas);
+}
+
+/// Expression converting a column to a double, temporal values as epoch seconds
+pub fn numeric(name: &str, ty: &DataType) -> Option<String> {
+    if ty.is_temporal() {
+        Some(format!("epoch({})", sql::ident(name)))
+    } else if ty.is_numeric() {
+        Some(format!("{}::DOUBLE", sql::ident(name)))
+    } else {
+        None
+    }
+}
+
+/// Extract the columns of a frame as doubles
+pub fn columns(df: &DataFrame) -> Result<Vec<Vec<Option<f64>>>> {
+    (0..df.num_columns()).map(|idx| column(df, idx)).collect()
+}
+
+/// Extract a double column from a frame
+fn column(df: &DataFrame, idx: usize) -> Result<Vec<Option<f64>>> {
+    let mut values = Vec::with_capacity(df.num_rows());
+    for batch in &df.0.batchs {
+        let array = cast(batch.column(idx), &DataType::Float64)?;
+        values.extend(array.as_primitive::<Float64Type>().iter());
+    }
+    Ok(values)
+}
+
+/// Compact number label
+pub fn num(v: f64) -> String {
+    if v != 0. && !(1e-3..1e6).contains(&v.abs()) {
+        format!("{v:.2e}")
+    } else {
+        let str = format!("{v:.3}");
+        str.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// Range of values, widened if empty
+pub fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+    if min > max {
+        (0., 1.)
+    } else if min == max {
+        (min - 0.5, max + 0.5)
+    } else {
+        (min, max)
+    }
+}
+
+/// Width of the labels of a y axis
+pub fn gutter((min, max): (f64, f64)) -> usize {
+    num(min).width().max(num(max).width())
+}
+
+/// Draw a y axis labelled area, calling `row` to fill the content of each line
+/// Return the width of the axis gutter
+pub fn draw_y_axis(
+    c: &mut Canvas,
+    (min, max): (f64, f64),
+    height: usize,
+    mut row: impl FnMut(usize, &mut Vec<(char, Style)>),
+) -> usize {
+    let mut buf = Vec::new();
+    let (top, btm) = (num(max), num(min));
+    let gutter = gutter((min, max));
+    for r in 0..height {
+        let label = match r {
+            0 => top.as_str(),
+            r if r + 1 == height => btm.as_str(),
+            _ => "",
+        };
+        let mut l = c.top();
+        l.draw(format_args!("{label:>gutter$}"), style::index());
+        l.draw("│", style::separator());
+        buf.clear();
+        row(r, &mut buf);
+        for (c, style) in &buf {
+            l.draw(c, *style);
+        }
+    }
+    gutter
+}
+
+/// Draw the x axis labels under a chart
+pub fn draw_x_axis(c: &mut Canvas, gutter: usize, (min, max): (f64, f64)) {
+    let mut l = c.top();
+    l.draw(format_args!("{:gutter$}└", ""), style::separator());
+    l.draw(num(min), style::index());
+    l.rdraw(num(max), style::index());
+}
+
+/// Braille dots canvas, each cell holding 2x4 dots
+pub struct Braille {
+    width: usize,
+    cells: Vec<(u8, usize)>,
+}
+
+impl Braille {
+    const DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            cells: vec![(0, 0); width * height],
+        }
+    }
+
+    /// Set the dot at this position using normalized coordinates
+    pub fn dot(&mut self, x: f64, y: f64, series: usize) {
+        let height = self.cells.len() / self.width.max(1);
+        if self.width == 0 || height == 0 || !(0. ..=1.).contains(&x) || !(0. ..=1.).contains(&y) {
+            return;
+        }
+        let px = (x * (self.width * 2 - 1) as f64).round() as usize;
+        let py = ((1. - y) * (height * 4 - 1) as f64).round() as usize;
+        let cell = &mut self.cells[(py / 4) * self.width + px / 2];
+        cell.0 |= Self::DOTS[py % 4][px % 2];
+        cell.1 = series;
+    }
+
+    pub fn row(&self, row: usize, buf: &mut Vec<(char, Style)>) {
+        for (bits, series) in &self.cells[row * self.width..(row + 1) * self.width] {
+            if *bits == 0 {
+                buf.push((' ', none()));
+            } else {
+                let c = char::from_u32(0x2800 + *bits as u32).unwrap();
+                buf.push((c, style::series(*series)));
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use std::io::{self, Write};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Copy text to the system clipboard using the OSC 52 terminal sequence
+/// Work over ssh and inside tmux without any native dependency
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    write!(out, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    out.flush()
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    let mut buf = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | ((*b as u32) << (16 - i * 8)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                buf.push(BASE64[((n >> (18 - i * 6)) & 0x3F) as usize] as char);
+            } else {
+                buf.push('=');
+            }
+        }
+    }
+    buf
+}
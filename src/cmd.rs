@@ -0,0 +1,361 @@
+use tui::{
+    crossterm::event::{KeyCode as Key, KeyEvent, KeyModifiers},
+    none, Canvas,
+};
+
+use crate::{
+    shell::prompt::{Prompt, PromptCmd},
+    style,
+};
+
+/// How a pattern is matched against values
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Match {
+    Literal,
+    Regex,
+}
+
+impl Match {
+    fn toggle(&mut self) {
+        *self = match self {
+            Match::Literal => Match::Regex,
+            Match::Regex => Match::Literal,
+        }
+    }
+}
+
+/// Action triggered by a command line
+#[derive(Clone, Copy)]
+pub enum Action {
+    Export,
+    ExportAll,
+    Search(Match),
+    SearchRow(Match),
+    Filter(Match),
+    Derive,
+    Rename,
+    Cast,
+    Plot,
+    Duplicates,
+    Validate,
+    ReadOptions,
+    SearchAll,
+}
+
+impl Action {
+    fn label(&self) -> &'static str {
+        match self {
+            Action::Export => "export",
+            Action::ExportAll => "export full result",
+            Action::Search(Match::Literal) => "search",
+            Action::Search(Match::Regex) => "search regex",
+            Action::SearchRow(Match::Literal) => "search row",
+            Action::SearchRow(Match::Regex) => "search row regex",
+            Action::Filter(Match::Literal) => "filter",
+            Action::Filter(Match::Regex) => "filter regex",
+            Action::Derive => "column",
+            Action::Rename => "rename",
+            Action::Cast => "cast int|double|date [fmt]|text",
+            Action::Plot => "plot columns",
+            Action::Duplicates => "duplicates by columns (all if empty)",
+            Action::Validate => "rule or @file (rerun if empty)",
+            Action::SearchAll => "search all tabs",
+            Action::ReadOptions => {
+                "read options (e.g. delim=';', header=true, Ctrl+U union by name, Ctrl+S delimiter, Ctrl+T header)"
+            }
+        }
+    }
+}
+
+/// Read option merging the columns of multiple files by name
+pub const UNION_BY_NAME: &str = "union_by_name=true";
+
+/// Delimiters cycled through when editing the read options
+const DELIMITERS: [&str; 4] = ["','", "';'", "'\\t'", "'|'"];
+
+/// Read option reading the first line as data
+const NO_HEADER: &str = "header=false";
+
+/// Options of a list of reader options, separated by commas outside of
+/// quotes and brackets
+fn split_options(options: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let (mut start, mut depth, mut quote) = (0, 0, None);
+    for (i, c) in options.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(&options[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&options[start..]);
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// Name and raw value of an option, the value is empty without `=`
+fn split_option(option: &str) -> (&str, &str) {
+    match option.split_once('=') {
+        Some((name, value)) => (name.trim(), value.trim()),
+        None => (option.trim(), ""),
+    }
+}
+
+/// Raw value of an option in a list of reader options, names are case
+/// insensitive like in duckdb
+pub fn option_value<'a>(options: &'a str, name: &str) -> Option<&'a str> {
+    split_options(options)
+        .into_iter()
+        .map(split_option)
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+/// Value of an option as text, a string literal is unquoted
+pub fn option_text(options: &str, name: &str) -> Option<String> {
+    let value = option_value(options, name)?;
+    Some(
+        match value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+            Some(quoted) => quoted.replace("''", "'"),
+            None => value.into(),
+        },
+    )
+}
+
+/// Remove every occurrence of an option from a list of reader options
+pub fn remove_option(options: &str, name: &str) -> String {
+    split_options(options)
+        .into_iter()
+        .filter(|option| !split_option(option).0.eq_ignore_ascii_case(name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Add an option to a list of reader options unless it is already set,
+/// whatever its value
+pub fn default_option(options: &str, option: &str) -> String {
+    let (name, _) = split_option(option);
+    match option_value(options, name) {
+        Some(_) => options.into(),
+        None => toggle_option(options, option),
+    }
+}
+
+/// Replace the delimiter option by the next common delimiter
+fn cycle_delimiter(options: &str) -> String {
+    let next = option_value(options, "delim")
+        .and_then(|value| DELIMITERS.iter().position(|d| *d == value))
+        .map_or(DELIMITERS[1], |pos| {
+            DELIMITERS[(pos + 1) % DELIMITERS.len()]
+        });
+    toggle_option(options, &format!("delim={next}"))
+}
+
+/// Add an option to a list of reader options, replacing another value of
+/// the same option, or remove it if present with this value
+pub fn toggle_option(options: &str, option: &str) -> String {
+    let (name, value) = split_option(option);
+    let present = option_value(options, name) == Some(value);
+    let mut options = remove_option(options, name);
+    if !present {
+        if !options.is_empty() {
+            options.push_str(", ");
+        }
+        options.push_str(option);
+    }
+    options
+}
+
+pub enum Outcome {
+    Continue,
+    Cancel,
+    Submit(Action, String),
+}
+
+/// Single line prompt used to parametrize an action
+pub struct CmdLine {
+    action: Action,
+    prompt: Prompt<0>,
+}
+
+impl CmdLine {
+    pub fn new(action: Action) -> Self {
+        Self {
+            action,
+            prompt: Prompt::new(""),
+        }
+    }
+
+    /// Prompt starting with an editable value
+    pub fn with_input(action: Action, input: &str) -> Self {
+        Self {
+            action,
+            prompt: Prompt::new(input),
+        }
+    }
+
+    pub fn on_key(&mut self, event: &KeyEvent) -> Outcome {
+        let cmd = match event.code {
+            Key::Char('r') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                match &mut self.action {
+                    Action::Search(m) | Action::SearchRow(m) | Action::Filter(m) => m.toggle(),
+                    _ => {}
+                }
+                return Outcome::Continue;
+            }
+            Key::Char('u')
+                if event.modifiers.contains(KeyModifiers::CONTROL)
+                    && matches!(self.action, Action::ReadOptions) =>
+            {
+                let (str, _) = self.prompt.state();
+                let toggled = toggle_option(str, UNION_BY_NAME);
+                self.prompt.set(&toggled);
+                return Outcome::Continue;
+            }
+            Key::Char('s')
+                if event.modifiers.contains(KeyModifiers::CONTROL)
+                    && matches!(self.action, Action::ReadOptions) =>
+            {
+                let (str, _) = self.prompt.state();
+                let cycled = cycle_delimiter(str);
+                self.prompt.set(&cycled);
+                return Outcome::Continue;
+            }
+            Key::Char('t')
+                if event.modifiers.contains(KeyModifiers::CONTROL)
+                    && matches!(self.action, Action::ReadOptions) =>
+            {
+                let (str, _) = self.prompt.state();
+                let toggled = toggle_option(str, NO_HEADER);
+                self.prompt.set(&toggled);
+                return Outcome::Continue;
+            }
+            Key::Char(c) => PromptCmd::Write(c),
+            Key::Left => PromptCmd::Left,
+            Key::Right => PromptCmd::Right,
+            Key::Backspace => PromptCmd::Delete,
+            Key::Esc => return Outcome::Cancel,
+            Key::Enter => {
+                let (str, _) = self.prompt.state();
+                return Outcome::Submit(self.action, str.trim().to_string());
+            }
+            _ => return Outcome::Continue,
+        };
+        self.prompt.exec(cmd);
+        Outcome::Continue
+    }
+
+    pub fn draw(&mut self, c: &mut Canvas) {
+        let mut l = c.btm();
+        l.draw(format_args!("{} ", self.action.label()), style::separator());
+        let (str, cursor) = self.prompt.state();
+        l.draw(&str[..cursor], none());
+        l.cursor();
+        l.draw(&str[cursor..], none());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values() {
+        let options = "delim=';', header = true, columns={'a': 'INT', 'b,c': 'VARCHAR'}";
+        assert_eq!(option_value(options, "delim"), Some("';'"));
+        assert_eq!(option_value(options, "HEADER"), Some("true"));
+        assert_eq!(
+            option_value(options, "columns"),
+            Some("{'a': 'INT', 'b,c': 'VARCHAR'}")
+        );
+        assert_eq!(option_value(options, "c"), None);
+        assert_eq!(option_value("", "delim"), None);
+    }
+
+    #[test]
+    fn text() {
+        let options = "sheet='it''s, here', range=A1:B2";
+        assert_eq!(option_text(options, "sheet").as_deref(), Some("it's, here"));
+        assert_eq!(option_text(options, "range").as_deref(), Some("A1:B2"));
+        assert_eq!(option_text(options, "delim"), None);
+    }
+
+    #[test]
+    fn names_are_exact() {
+        // Option names within another option value are not options
+        let options = "comment='union_by_name=true, ignore_errors', quote='\"'";
+        assert_eq!(option_value(options, "union_by_name"), None);
+        assert_eq!(option_value(options, "ignore_errors"), None);
+        assert_eq!(option_value(options, "quote"), Some("'\"'"));
+        assert_eq!(option_value("delimiter=','", "delim"), None);
+        assert_eq!(option_value("escape='''', delim=','", "delim"), Some("','"));
+    }
+
+    #[test]
+    fn toggle() {
+        assert_eq!(toggle_option("", UNION_BY_NAME), UNION_BY_NAME);
+        assert_eq!(
+            toggle_option("header=true", UNION_BY_NAME),
+            "header=true, union_by_name=true"
+        );
+        assert_eq!(
+            toggle_option("header=true, union_by_name=true", UNION_BY_NAME),
+            "header=true"
+        );
+        assert_eq!(
+            toggle_option("union_by_name=false, header=true", UNION_BY_NAME),
+            "header=true, union_by_name=true"
+        );
+        let options = "comment='union_by_name=true'";
+        assert_eq!(
+            toggle_option(options, UNION_BY_NAME),
+            "comment='union_by_name=true', union_by_name=true"
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(
+            default_option("", "ignore_errors=true"),
+            "ignore_errors=true"
+        );
+        assert_eq!(
+            default_option("ignore_errors=false", "ignore_errors=true"),
+            "ignore_errors=false"
+        );
+        assert_eq!(
+            default_option("comment='ignore_errors'", "ignore_errors=true"),
+            "comment='ignore_errors', ignore_errors=true"
+        );
+    }
+
+    #[test]
+    fn remove() {
+        assert_eq!(
+            remove_option("sheet='a,b', range=A1:B2", "sheet"),
+            "range=A1:B2"
+        );
+        assert_eq!(remove_option("encoding=latin1", "encoding"), "");
+        assert_eq!(remove_option("delim=','", "encoding"), "delim=','");
+    }
+
+    #[test]
+    fn delimiters() {
+        assert_eq!(cycle_delimiter(""), "delim=';'");
+        assert_eq!(
+            cycle_delimiter("delim=';', header=true"),
+            "header=true, delim='\\t'"
+        );
+        assert_eq!(cycle_delimiter("delim='|'"), "delim=','");
+    }
+}
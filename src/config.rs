@@ -0,0 +1,45 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{error::StrError, grid::MaxWidth, style::Theme, Options, Result};
+
+/// Config file, in `$XDG_CONFIG_HOME` or `~/.config`
+pub fn path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("dtex").join("config.toml"))
+}
+
+/// Settings of the config file, missing ones keep their default
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    max_width: Option<MaxWidth>,
+    theme: Option<Theme>,
+}
+
+impl Options {
+    /// Default options overridden by the config file, when there is one
+    pub fn from_config() -> Result<Self> {
+        let mut options = Self::default();
+        let Some(path) = path() else {
+            return Ok(options);
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(options),
+            Err(e) => return Err(StrError(format!("{}: {e}", path.display()))),
+        };
+        let config: Config =
+            toml::from_str(&content).map_err(|e| StrError(format!("{}: {e}", path.display())))?;
+        if let Some(max_width) = config.max_width {
+            options.max_width = max_width;
+        }
+        if let Some(theme) = config.theme {
+            options.theme = theme;
+        }
+        Ok(options)
+    }
+}
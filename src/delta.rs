@@ -0,0 +1,141 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::{duckdb::Connection, error::Result, source::DataFrame, sql};
+
+/// Data files of a version of a Delta Lake table, the latest by default, and
+/// whether they are partitioned into hive style directories
+///
+/// The log is replayed from the last checkpoint before the version, the
+/// checkpoints are parquet files read with duckdb.
+pub fn files(con: &Connection, table: &Path, version: Option<u64>) -> Result<(Vec<PathBuf>, bool)> {
+    let log = table.join("_delta_log");
+    let mut commits = vec![];
+    let mut checkpoints: BTreeMap<u64, Vec<PathBuf>> = BTreeMap::new();
+    for entry in std::fs::read_dir(&log)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some((num, ext)) = name.split_once('.') else {
+            continue;
+        };
+        let Ok(num) = num.parse::<u64>() else {
+            continue;
+        };
+        if ext == "json" {
+            commits.push(num);
+        } else if ext.starts_with("checkpoint") && ext.ends_with(".parquet") {
+            checkpoints.entry(num).or_default().push(path);
+        }
+    }
+    let latest = commits
+        .iter()
+        .chain(checkpoints.keys())
+        .max()
+        .copied()
+        .ok_or("Delta table without any commit")?;
+    let version = version.unwrap_or(latest);
+    if version > latest {
+        return Err(format!("Delta table has no version {version}, the latest is {latest}").into());
+    }
+
+    let mut active = BTreeSet::new();
+    let mut partitioned = false;
+    let start = match checkpoints.range(..=version).next_back() {
+        Some((num, parts)) => {
+            let list: Vec<_> = parts
+                .iter()
+                .map(|p| sql::lit(&p.to_string_lossy()))
+                .collect();
+            let checkpoint = format!("read_parquet([{}])", list.join(", "));
+            let paths = query(
+                con,
+                &format!("SELECT add.path FROM {checkpoint} WHERE add IS NOT NULL"),
+            )?;
+            active.extend((0..paths.num_rows()).filter_map(|row| paths.value(row, 0)));
+            let meta = query(
+                con,
+                &format!(
+                    "SELECT len(metaData.partitionColumns) > 0 FROM {checkpoint} \
+                    WHERE metaData IS NOT NULL"
+                ),
+            )?;
+            partitioned = meta.value(0, 0).as_deref() == Some("true");
+            num + 1
+        }
+        None => 0,
+    };
+    for num in start..=version {
+        let commit = log.join(format!("{num:020}.json"));
+        let content = std::fs::read_to_string(&commit)
+            .map_err(|_| format!("Delta table misses the commit of version {num}"))?;
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            let action: Value = serde_json::from_str(line)?;
+            if let Some(add) = action.get("add") {
+                if add.get("deletionVector").is_some_and(|d| !d.is_null()) {
+                    return Err("Delta tables with deletion vectors are not supported".into());
+                }
+                active.insert(file_path(add)?);
+            } else if let Some(remove) = action.get("remove") {
+                active.remove(&file_path(remove)?);
+            } else if let Some(meta) = action.get("metaData") {
+                let columns = meta["partitionColumns"].as_array();
+                partitioned = columns.is_some_and(|c| !c.is_empty());
+            }
+        }
+    }
+    if active.is_empty() {
+        return Err(format!("Delta table has no data file at version {version}").into());
+    }
+    let files = active.iter().map(|path| resolve(table, path)).collect();
+    Ok((files, partitioned))
+}
+
+fn query(con: &Connection, sql: &str) -> Result<DataFrame> {
+    con.query(sql)?
+        .map(|d| d.map_err(|e| e.into()))
+        .collect::<Result<DataFrame>>()
+}
+
+fn file_path(action: &Value) -> Result<String> {
+    let path = action["path"].as_str().ok_or("Delta action without path")?;
+    Ok(path.to_string())
+}
+
+/// Local path of a file of the log, its path is an uri or is relative to the
+/// table, and is percent encoded
+fn resolve(table: &Path, path: &str) -> PathBuf {
+    let path = percent_decode(path);
+    match path.strip_prefix("file://").or(path.strip_prefix("file:")) {
+        Some(absolute) => PathBuf::from(absolute),
+        None => table.join(path),
+    }
+}
+
+fn percent_decode(str: &str) -> String {
+    let bytes = str.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
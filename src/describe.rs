@@ -1,54 +1,132 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+
+use arrow::{compute::cast, record_batch::RecordBatch};
+use libduckdb_sys::duckdb_query_progress_type;
 
 use crate::{
     error::Result,
     fmt::{Col, GridBuffer},
-    grid::{Frame, Grid},
+    grid::{Frame, Grid, Settings},
     source::{DataFrame, Source},
-    task::{DuckTask, Runner},
+    task::{DuckTask, Runner, TaskHandle},
     view::{View, ViewState},
 };
 
+/// Number of columns summarized by each scan of the source
+const BATCH: usize = 16;
+
+/// Column summaries computed so far
+#[derive(Default)]
+struct Partial {
+    batches: Vec<RecordBatch>,
+    /// Number of columns summarized, being summarized and in total
+    done: usize,
+    current: usize,
+    total: usize,
+}
+
 pub struct DescriberView {
-    task: Option<DuckTask<Description>>,
+    task: Option<DuckTask<()>>,
+    partial: Arc<Mutex<Partial>>,
     description: Description,
     error: Option<String>,
+    interrupted: bool,
     pub grid: Grid,
 }
 
 impl DescriberView {
-    pub fn new(source: Arc<Source>, runner: &Runner) -> Self {
+    pub fn new(source: Arc<Source>, runner: &Runner, settings: Settings) -> Self {
+        let partial = Arc::new(Mutex::new(Partial::default()));
         Self {
-            grid: Grid::new(),
+            grid: Grid::new(settings),
             description: Description(DataFrame::empty()),
             error: None,
+            interrupted: false,
+            partial: partial.clone(),
             task: Some(runner.duckdb(source, move |source, con| {
-                let df: Result<DataFrame> = source
-                    .describe(con)?
-                    .map(|d| d.map_err(|e| e.into()))
-                    .collect();
-                Ok(Description(df?))
+                // Summarize a few columns at once to show partial results
+                let schema = source.schema_on(&con)?;
+                partial.lock().unwrap().total = schema.fields().len();
+                let mut first = None;
+                for fields in schema.fields().chunks(BATCH) {
+                    partial.lock().unwrap().current = fields.len();
+                    for batch in source.describe(&con, fields.iter().map(|f| f.name()))? {
+                        let batch = conform(batch?, &mut first)?;
+                        partial.lock().unwrap().batches.push(batch);
+                    }
+                    partial.lock().unwrap().done += fields.len();
+                }
+                Ok(())
             })),
         }
     }
+
+    pub fn handle(&self) -> Option<TaskHandle> {
+        self.task.as_ref().map(DuckTask::handle)
+    }
+
+    /// Stop summarizing, keeping the already computed columns
+    pub fn interrupt(&mut self) {
+        if let Some(task) = &self.task {
+            task.handle().interrupt();
+            self.interrupted = true;
+        }
+    }
+
+    /// Progress over all columns, including the progress of the current query
+    fn progress(&self, current: duckdb_query_progress_type) -> duckdb_query_progress_type {
+        let partial = self.partial.lock().unwrap();
+        let running = partial.current as f64 * current.percentage.max(0.) / 100.;
+        duckdb_query_progress_type {
+            percentage: (partial.done as f64 + running) / partial.total.max(1) as f64 * 100.,
+            ..current
+        }
+    }
+}
+
+/// Summaries of different columns can have slightly different types, cast
+/// them to the ones of the first summary
+fn conform(batch: RecordBatch, first: &mut Option<RecordBatch>) -> Result<RecordBatch> {
+    let Some(first) = first else {
+        *first = Some(batch.clone());
+        return Ok(batch);
+    };
+    if first.schema() == batch.schema() {
+        return Ok(batch);
+    }
+    let columns = first
+        .schema()
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, array)| cast(array, field.data_type()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(RecordBatch::try_new(first.schema(), columns)?)
 }
 
 impl View for DescriberView {
     fn tick(&mut self) -> ViewState {
+        let batches = std::mem::take(&mut self.partial.lock().unwrap().batches);
+        if !batches.is_empty() {
+            self.description.0.extend(batches.into_iter());
+        }
         match self.task.as_mut().and_then(|t| t.tick()) {
-            Some(Ok(df)) => {
-                self.description = df;
-                self.task = None;
-            }
+            Some(Ok(())) => self.task = None,
             Some(Err(it)) => {
-                self.error = Some(it.0);
+                // Interruption is requested, keep the partial description
+                if !self.interrupted {
+                    self.error = Some(it.0);
+                }
                 self.task = None;
             }
             None => {}
         }
 
         ViewState {
-            loading: self.task.as_ref().map(|t| ("describe", t.progress())),
+            loading: self
+                .task
+                .as_ref()
+                .map(|t| ("describe".into(), self.progress(t.progress()))),
             streaming: false,
             frame: &self.description,
             grid: &mut self.grid,
@@ -79,4 +157,12 @@ impl Frame for Description {
     fn col_iter(&self, buf: &mut GridBuffer, idx: usize, skip: usize, take: usize) -> Col {
         self.0.iter(buf, idx + 1, skip, take)
     }
+
+    fn value(&self, row: usize, idx: usize) -> Option<String> {
+        self.0.value(row, idx + 1)
+    }
+
+    fn values(&self, idx: usize, skip: usize, take: usize) -> Vec<Option<String>> {
+        self.0.values(idx + 1, skip, take)
+    }
 }
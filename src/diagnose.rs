@@ -0,0 +1,55 @@
+use std::io::{self, IsTerminal};
+
+use serde_json::{json, Value};
+use tui::crossterm::terminal;
+
+use crate::{config, duckdb::DuckDb, error::Result, history, image, source::DataFrame};
+
+/// Versions, extensions, terminal capabilities and file paths of this
+/// environment as a json document, for bug reports and deployment checks
+pub fn diagnose() -> String {
+    let report = json!({
+        "dtex": env!("CARGO_PKG_VERSION"),
+        "arrow": arrow::ARROW_VERSION,
+        "duckdb": duckdb().unwrap_or_else(|err| json!({ "error": err.0 })),
+        "terminal": {
+            "stdout_is_terminal": io::stdout().is_terminal(),
+            "size": terminal::size().ok(),
+            "term": std::env::var("TERM").ok(),
+            "colorterm": std::env::var("COLORTERM").ok(),
+            "graphics": image::Protocol::detect().map(|p| p.name()),
+        },
+        "paths": {
+            "config": config::path(),
+            "history": history::path(),
+        },
+    });
+    serde_json::to_string_pretty(&report).unwrap()
+}
+
+/// Library version and extensions of duckdb
+fn duckdb() -> Result<Value> {
+    let db = DuckDb::mem()?;
+    let con = db.conn(|_| Ok(()))?;
+    let query = |sql: &str| -> Result<DataFrame> {
+        con.query(sql)?.map(|d| d.map_err(|e| e.into())).collect()
+    };
+    let version = query("SELECT version()")?.value(0, 0);
+    let df = query(
+        "SELECT extension_name, installed::VARCHAR, loaded::VARCHAR FROM duckdb_extensions() ORDER BY ALL",
+    )?;
+    let extensions: Vec<_> = (0..df.num_rows())
+        .map(|row| {
+            json!({
+                "name": df.value(row, 0),
+                "installed": df.value(row, 1).as_deref() == Some("true"),
+                "loaded": df.value(row, 2).as_deref() == Some("true"),
+            })
+        })
+        .collect();
+    Ok(json!({
+        "version": version,
+        "bundled": cfg!(feature = "bundled"),
+        "extensions": extensions,
+    }))
+}
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use crate::{
+    error::Result,
+    grid::{Grid, Settings},
+    source::{DataFrame, Source},
+    sql,
+    task::{DuckTask, Runner},
+    view::{View, ViewState},
+};
+
+/// Maximum number of distinct values listed
+const LIMIT: usize = 1000;
+
+/// Most frequent values of a column, marked rows become an IN filter
+pub struct DistinctView {
+    name: String,
+    task: Option<DuckTask<DataFrame>>,
+    df: DataFrame,
+    error: Option<String>,
+    pub grid: Grid,
+}
+
+impl DistinctView {
+    pub fn new(source: Arc<Source>, name: String, runner: &Runner, settings: Settings) -> Self {
+        let col = sql::ident(&name);
+        Self {
+            task: Some(runner.duckdb(source, move |source, con| {
+                let sql = format!(
+                    "SELECT {col}, count(*) AS count FROM ({}) GROUP BY ALL ORDER BY count DESC LIMIT {LIMIT}",
+                    source.load_sql()
+                );
+                con.query(&sql)?
+                    .map(|d| d.map_err(|e| e.into()))
+                    .collect::<Result<DataFrame>>()
+            })),
+            name,
+            df: DataFrame::empty(),
+            error: None,
+            grid: Grid::new(settings),
+        }
+    }
+
+    /// Filter keeping the marked values
+    pub fn filter(&self) -> Option<String> {
+        let values: Vec<_> = self
+            .grid
+            .selection
+            .rows()
+            .map(|row| self.df.value(row, 0))
+            .collect();
+        (!values.is_empty()).then(|| sql::is_in(&self.name, &values))
+    }
+}
+
+impl View for DistinctView {
+    fn tick(&mut self) -> ViewState {
+        match self.task.as_mut().and_then(|t| t.tick()) {
+            Some(Ok(df)) => {
+                self.df = df;
+                self.task = None;
+            }
+            Some(Err(it)) => {
+                self.error = Some(it.0);
+                self.task = None;
+            }
+            None => {}
+        }
+
+        ViewState {
+            loading: self
+                .task
+                .as_ref()
+                .map(|t| ("distinct".into(), t.progress())),
+            streaming: false,
+            frame: &self.df,
+            grid: &mut self.grid,
+            err: self.error.as_deref(),
+        }
+    }
+}
@@ -2,27 +2,32 @@ use std::{
     ffi::{CStr, CString},
     fmt::Display,
     mem::MaybeUninit,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
 };
 
 use arrow::{
-    array::{Array, StructArray},
+    array::StructArray,
+    datatypes::{Schema, SchemaRef},
     ffi::{from_ffi, FFI_ArrowArray, FFI_ArrowSchema},
-    record_batch::RecordBatch,
+    ffi_stream::FFI_ArrowArrayStream,
+    record_batch::{RecordBatch, RecordBatchIterator},
 };
 use libduckdb_sys::{
-    duckdb_arrow_array, duckdb_arrow_array_scan, duckdb_arrow_schema, duckdb_arrow_stream,
-    duckdb_close, duckdb_connect, duckdb_connection, duckdb_database, duckdb_destroy_data_chunk,
+    duckdb_arrow_array, duckdb_arrow_scan, duckdb_arrow_schema, duckdb_arrow_stream, duckdb_close,
+    duckdb_connect, duckdb_connection, duckdb_database, duckdb_destroy_data_chunk,
     duckdb_destroy_pending, duckdb_destroy_prepare, duckdb_destroy_result, duckdb_disconnect,
-    duckdb_execute_pending, duckdb_free, duckdb_interrupt, duckdb_open_ext, duckdb_pending_error,
-    duckdb_pending_execute_task, duckdb_pending_prepared_streaming, duckdb_pending_result,
-    duckdb_pending_state_DUCKDB_PENDING_ERROR,
+    duckdb_execute_pending, duckdb_free, duckdb_interrupt, duckdb_library_version, duckdb_open_ext,
+    duckdb_pending_error, duckdb_pending_execute_task, duckdb_pending_prepared_streaming,
+    duckdb_pending_result, duckdb_pending_state_DUCKDB_PENDING_ERROR,
     duckdb_pending_state_DUCKDB_PENDING_NO_TASKS_AVAILABLE,
     duckdb_pending_state_DUCKDB_PENDING_RESULT_NOT_READY,
     duckdb_pending_state_DUCKDB_PENDING_RESULT_READY, duckdb_prepare, duckdb_prepare_error,
-    duckdb_prepared_statement, duckdb_query, duckdb_query_progress, duckdb_result,
-    duckdb_result_arrow_array, duckdb_result_arrow_schema, duckdb_result_error,
-    duckdb_result_get_chunk, duckdb_result_is_streaming, duckdb_stream_fetch_chunk, DuckDBSuccess, duckdb_query_progress_type,
+    duckdb_prepared_statement, duckdb_query, duckdb_query_progress, duckdb_query_progress_type,
+    duckdb_result, duckdb_result_arrow_array, duckdb_result_arrow_schema, duckdb_result_error,
+    duckdb_result_get_chunk, duckdb_result_is_streaming, duckdb_stream_fetch_chunk, DuckDBSuccess,
 };
 
 use crate::DataFrame;
@@ -38,12 +43,69 @@ impl Display for Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Oldest duckdb release providing the C API used here
+const MIN_VERSION: (u32, u32) = (0, 9);
+
+/// Functions of the C API used here, looked up before calling any of them
+/// as an older system library misses some
+#[cfg(all(target_os = "linux", not(feature = "bundled")))]
+const FUNCTIONS: &[&str] = &[
+    "duckdb_library_version",
+    "duckdb_open_ext",
+    "duckdb_connect",
+    "duckdb_query",
+    "duckdb_query_progress",
+    "duckdb_interrupt",
+    "duckdb_prepare",
+    "duckdb_pending_prepared_streaming",
+    "duckdb_pending_execute_task",
+    "duckdb_execute_pending",
+    "duckdb_result_is_streaming",
+    "duckdb_stream_fetch_chunk",
+    "duckdb_result_get_chunk",
+    "duckdb_result_arrow_schema",
+    "duckdb_result_arrow_array",
+    "duckdb_arrow_scan",
+];
+
+/// Version of the linked duckdb library, failing when it is older than the
+/// C API used here, which happens when linking against a system library
+pub fn library_version() -> Result<String> {
+    #[cfg(all(target_os = "linux", not(feature = "bundled")))]
+    for name in FUNCTIONS {
+        // Missing functions are resolved lazily, calling one would abort
+        let symbol = CString::new(*name).unwrap();
+        if unsafe { libc::dlsym(libc::RTLD_DEFAULT, symbol.as_ptr()) }.is_null() {
+            return Err(Error(format!(
+                "duckdb has no {name} function, dtex requires v{}.{} or later",
+                MIN_VERSION.0, MIN_VERSION.1
+            )));
+        }
+    }
+    let version = unsafe { CStr::from_ptr(duckdb_library_version()) }
+        .to_string_lossy()
+        .to_string();
+    let mut parts = version
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse::<u32>().unwrap_or(0));
+    let (major, minor) = (parts.next().unwrap_or(0), parts.next().unwrap_or(0));
+    if (major, minor) < MIN_VERSION {
+        return Err(Error(format!(
+            "duckdb {version} is not supported, dtex requires v{}.{} or later",
+            MIN_VERSION.0, MIN_VERSION.1
+        )));
+    }
+    Ok(version)
+}
+
 struct DB {
     db: duckdb_database,
 }
 
 impl DB {
     pub fn tmp() -> Result<Self> {
+        library_version()?;
         let mut db: duckdb_database = std::ptr::null_mut();
         unsafe {
             let mut err = std::ptr::null_mut();
@@ -96,6 +158,12 @@ impl Chunks {
             idx: 0,
         }
     }
+
+    pub fn schema(&self) -> Result<SchemaRef> {
+        Ok(Arc::new(
+            Schema::try_from(&self.schema).map_err(|e| Error(e.to_string()))?,
+        ))
+    }
 }
 
 unsafe impl Send for Chunks {}
@@ -142,8 +210,26 @@ impl Drop for Chunks {
     }
 }
 
+/// Maximum number of idle connections kept per database
+const MAX_IDLE: usize = 4;
+
+/// Idle connection waiting in a pool
+struct Idle(duckdb_connection);
+
+unsafe impl Send for Idle {}
+
+impl Drop for Idle {
+    fn drop(&mut self) {
+        unsafe { duckdb_disconnect(&mut self.0) }
+    }
+}
+
+type Pool = Mutex<Vec<Idle>>;
+
 #[derive(Clone)]
 pub struct DuckDb {
+    // Declared first so that idle connections are closed before the database
+    pool: Arc<Pool>,
     _db: Arc<DB>,
 }
 
@@ -151,10 +237,21 @@ impl DuckDb {
     /// Open a in memory database
     pub fn mem() -> Result<Self> {
         let db = DB::tmp()?;
-        Ok(Self { _db: Arc::new(db) })
+        Ok(Self {
+            pool: Arc::new(Mutex::new(vec![])),
+            _db: Arc::new(db),
+        })
     }
 
-    pub fn conn(&self) -> Result<Connection> {
+    /// Checkout a connection from the pool, `init` is only called on newly
+    /// opened connections to set up their session
+    pub fn conn(&self, init: impl FnOnce(&Connection) -> Result<()>) -> Result<Connection> {
+        let idle = self.pool.lock().unwrap().pop();
+        if let Some(idle) = idle {
+            let con = idle.0;
+            std::mem::forget(idle);
+            return Ok(self.wrap(con));
+        }
         let mut con: duckdb_connection = std::ptr::null_mut();
         unsafe {
             if duckdb_connect(self._db.db, &mut con) != DuckDBSuccess {
@@ -162,15 +259,27 @@ impl DuckDb {
                 return Err(Error("Unknown connect error".into()));
             }
         }
-        Ok(Connection(Arc::new(Con {
+        let con = self.wrap(con);
+        init(&con)?;
+        Ok(con)
+    }
+
+    fn wrap(&self, con: duckdb_connection) -> Connection {
+        Connection(Arc::new(Con {
             _db: self._db.clone(),
+            pool: Arc::downgrade(&self.pool),
+            reusable: AtomicBool::new(true),
             con,
-        })))
+        }))
     }
 }
 
 struct Con {
     _db: Arc<DB>,
+    pool: Weak<Pool>,
+    /// Whether the session is still as initialized, and can be handed to
+    /// another task
+    reusable: AtomicBool,
     con: duckdb_connection,
 }
 
@@ -179,10 +288,23 @@ unsafe impl Sync for Con {}
 
 impl Drop for Con {
     fn drop(&mut self) {
+        // Checkin the connection if its pool is still alive and not full
+        if let Some(pool) = self
+            .pool
+            .upgrade()
+            .filter(|_| self.reusable.load(Ordering::Relaxed))
+        {
+            let mut pool = pool.lock().unwrap();
+            if pool.len() < MAX_IDLE {
+                pool.push(Idle(self.con));
+                return;
+            }
+        }
         unsafe { duckdb_disconnect(&mut self.con) }
     }
 }
 
+#[derive(Clone)]
 pub struct ConnCtx(Arc<Con>);
 
 impl ConnCtx {
@@ -191,6 +313,8 @@ impl ConnCtx {
     }
 
     pub fn interrupt(&self) {
+        // The interruption could outlive the query
+        self.0.reusable.store(false, Ordering::Relaxed);
         unsafe { duckdb_interrupt(self.0.con as *mut _) }
     }
 }
@@ -202,31 +326,48 @@ impl Connection {
         ConnCtx(self.0.clone())
     }
 
-    pub fn bind(&self, frame: DataFrame) -> Result<()> {
-        let name = CString::new("current").unwrap();
-        assert_eq!(frame.0.batchs.len(), 1, "TODO concat array");
-        let array = &frame.0.batchs[0];
-        let schema = array.schema();
-        let array = StructArray::from(array.clone());
-        let schema = FFI_ArrowSchema::try_from(schema.as_ref()).unwrap();
-        let array = FFI_ArrowArray::new(&array.to_data());
-        let schema = Box::leak(Box::new(schema));
-        let array = Box::leak(Box::new(array));
-        let mut it: duckdb_arrow_stream = std::ptr::null_mut();
+    /// Close the connection when dropped instead of returning it to the pool,
+    /// for sessions whose state could leak into unrelated tasks
+    pub fn discard(&self) {
+        self.0.reusable.store(false, Ordering::Relaxed);
+    }
+
+    /// Copy a data frame into a table of the database, seen by every connection
+    pub fn store(&self, table: &str, frame: &DataFrame) -> Result<()> {
+        self.scan(
+            table,
+            frame,
+            &format!("CREATE OR REPLACE TABLE {table} AS FROM"),
+        )
+    }
+
+    /// Copy a data frame at the end of a table of the database
+    pub fn append(&self, table: &str, frame: &DataFrame) -> Result<()> {
+        self.scan(table, frame, &format!("INSERT INTO {table} SELECT * FROM"))
+    }
+
+    /// Execute a statement reading a data frame from a temporary view
+    fn scan(&self, table: &str, frame: &DataFrame, statement: &str) -> Result<()> {
+        let batches = frame.0.batchs.clone().into_iter().map(Ok);
+        let reader = RecordBatchIterator::new(batches, frame.schema().clone());
+        let mut stream = Box::new(FFI_ArrowArrayStream::new(Box::new(reader)));
+        let scan = format!("{table}_scan");
+        let name = CString::new(scan.as_str()).unwrap();
         unsafe {
-            if duckdb_arrow_array_scan(
+            if duckdb_arrow_scan(
                 self.0.con as *mut _,
                 name.as_ptr(),
-                schema as *mut _ as *mut _,
-                array as *mut _ as *mut _,
-                &mut it as *mut _,
+                stream.as_mut() as *mut _ as duckdb_arrow_stream,
             ) != DuckDBSuccess
             {
-                Err(Error("Unknown arrow scan error".into()))
-            } else {
-                Ok(())
+                return Err(Error("Unknown arrow scan error".into()));
             }
         }
+        let result = self.execute(&format!("{statement} {scan}"));
+        self.execute(&format!("DROP VIEW {scan}"))?;
+        // The stream is released once duckdb is done with it
+        drop(stream);
+        result
     }
 
     pub fn execute(&self, query: &str) -> Result<()> {
@@ -0,0 +1,97 @@
+use arrow::record_batch::RecordBatch;
+use tui::crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::{run_hooked, Options, Source};
+
+/// Rows picked by the user in a tab
+pub struct Selection {
+    /// Name of the source of the tab
+    pub tab: String,
+    /// Marked rows, or the row under the cursor when none are marked
+    pub rows: RecordBatch,
+}
+
+/// Row activated with Enter
+pub struct Activation {
+    /// Name of the source of the tab
+    pub tab: String,
+    /// Position of the row in the loaded data
+    pub row: usize,
+    /// Values of the row
+    pub values: RecordBatch,
+}
+
+type ActivateHandler = Box<dyn FnMut(&Activation)>;
+type QuitHandler = Box<dyn FnOnce(Option<Selection>)>;
+/// Handler of a custom key binding, returning a message for the status line
+type KeyHandler = Box<dyn FnMut(&Selection) -> Option<String>>;
+
+/// Callbacks of the host application
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pub row_activate: Option<ActivateHandler>,
+    pub quit: Option<QuitHandler>,
+    pub keys: Vec<(KeyCode, KeyModifiers, KeyHandler)>,
+}
+
+impl Hooks {
+    /// Handler bound to a key
+    pub fn key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Option<&mut KeyHandler> {
+        self.keys
+            .iter_mut()
+            .find(|(c, m, _)| *c == code && *m == modifiers)
+            .map(|(_, _, handler)| handler)
+    }
+}
+
+/// Explorer embedded in a host application that reacts to the user actions
+pub struct App {
+    sources: Vec<Source>,
+    options: Options,
+    hooks: Hooks,
+}
+
+impl App {
+    pub fn new(sources: impl IntoIterator<Item = Source>) -> Self {
+        Self {
+            sources: sources.into_iter().collect(),
+            options: Options::default(),
+            hooks: Hooks::default(),
+        }
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Called when the user presses Enter on a row
+    pub fn on_row_activate(mut self, handler: impl FnMut(&Activation) + 'static) -> Self {
+        self.hooks.row_activate = Some(Box::new(handler));
+        self
+    }
+
+    /// Called once when the explorer exits, with the rows picked in the
+    /// focused tab
+    pub fn on_quit(mut self, handler: impl FnOnce(Option<Selection>) + 'static) -> Self {
+        self.hooks.quit = Some(Box::new(handler));
+        self
+    }
+
+    /// Bind a key in normal mode, taking precedence over the built-in
+    /// bindings, the returned message is shown in the status line
+    pub fn on_key(
+        mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+        handler: impl FnMut(&Selection) -> Option<String> + 'static,
+    ) -> Self {
+        self.hooks.keys.push((code, modifiers, Box::new(handler)));
+        self
+    }
+
+    /// Run the explorer until the user exits
+    pub fn run(self) {
+        run_hooked(self.sources.into_iter(), self.options, self.hooks)
+    }
+}
@@ -0,0 +1,79 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::Path,
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+
+use crate::{error::Result, temp::TempFile};
+
+/// Number of bytes inspected to guess the encoding of a file
+const SNIFF_BYTES: usize = 64 * 1024;
+
+/// Encoding of a text file named by its label (e.g. latin-1, windows-1252,
+/// shift-jis) or guessed from its first bytes, None if it is utf-8
+pub fn detect(path: &Path, label: Option<&str>) -> Result<Option<&'static Encoding>> {
+    if let Some(label) = label {
+        let encoding = Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| format!("Unknown encoding '{label}'"))?;
+        return Ok((encoding != UTF_8).then_some(encoding));
+    }
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    File::open(path)?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut head)?;
+    // The sample can end in the middle of a multibyte character
+    let utf8 = std::str::from_utf8(&head).map_or_else(|e| e.error_len().is_none(), |_| true);
+    if utf8 {
+        return Ok(None);
+    }
+    if let Some((encoding, _)) = Encoding::for_bom(&head) {
+        return Ok(Some(encoding));
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(&head, head.len() < SNIFF_BYTES);
+    Ok(Some(detector.guess(None, false)))
+}
+
+/// Utf-8 copy of a file in the temporary directory, keeping its extension
+pub struct Copy {
+    pub file: TempFile,
+    pub encoding: &'static Encoding,
+    /// Modification time of the file when last copied, None before the first
+    /// copy
+    copied: Mutex<Option<Option<SystemTime>>>,
+}
+
+impl Copy {
+    /// Empty copy, the file is only transcoded by `sync`
+    pub fn new(path: &Path, encoding: &'static Encoding) -> Result<Self> {
+        let ext = path.extension().unwrap_or_default().to_string_lossy();
+        Ok(Self {
+            file: TempFile::new(&format!(".{ext}"))?,
+            encoding,
+            copied: Mutex::new(None),
+        })
+    }
+
+    /// Transcode the file unless it did not change since the last copy
+    pub fn sync(&self, path: &Path) -> Result<()> {
+        let modified = std::fs::metadata(path)?.modified().ok();
+        let mut copied = self.copied.lock().unwrap();
+        if *copied == Some(modified) {
+            return Ok(());
+        }
+        let mut reader = DecodeReaderBytesBuilder::new()
+            .encoding(Some(self.encoding))
+            .build(File::open(path)?);
+        let mut writer = BufWriter::new(self.file.write()?);
+        std::io::copy(&mut reader, &mut writer)?;
+        writer.flush()?;
+        *copied = Some(modified);
+        Ok(())
+    }
+}
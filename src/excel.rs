@@ -0,0 +1,223 @@
+use std::{fmt::Write, fs::File, io::Read, path::Path};
+
+use tui::{
+    crossterm::event::{KeyCode as Key, KeyEvent},
+    none, Canvas, Color,
+};
+
+use crate::{
+    error::Result,
+    shell::prompt::{Prompt, PromptCmd},
+    sql, style,
+};
+
+/// Names of the sheets of a workbook, in the order of its tabs
+pub fn sheets(path: &Path) -> Result<Vec<String>> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    let mut workbook = String::new();
+    archive
+        .by_name("xl/workbook.xml")?
+        .read_to_string(&mut workbook)?;
+    Ok(workbook
+        .split("<sheet ")
+        .skip(1)
+        .filter_map(|tag| {
+            let (_, rest) = tag.split_once("name=\"")?;
+            let (name, _) = rest.split_once('"')?;
+            Some(unescape(name))
+        })
+        .collect())
+}
+
+/// Decode the predefined XML entities of an attribute value
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Columns and rows of a worksheet, up to `XFD1048576`
+const MAX_COLS: usize = 16_384;
+const MAX_ROWS: usize = 1_048_576;
+
+/// Zero based column and row of the corners of a `A1:F500` cell range
+pub fn range(range: &str) -> Option<((usize, usize), (usize, usize))> {
+    let cell = |cell: &str| {
+        let digits = cell.trim_start_matches(|c: char| c.is_ascii_uppercase());
+        let letters = &cell[..cell.len() - digits.len()];
+        if letters.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        // Bounded at each letter so that long strings cannot overflow
+        let col = letters.bytes().try_fold(0, |col, b| {
+            let col = col * 26 + (b - b'A') as usize + 1;
+            (col <= MAX_COLS).then_some(col)
+        })?;
+        let row = digits
+            .parse::<usize>()
+            .ok()
+            .filter(|row| (1..=MAX_ROWS).contains(row))?;
+        Some((col - 1, row - 1))
+    };
+    let (start, end) = range.split_once(':')?;
+    let ((c1, r1), (c2, r2)) = (cell(start)?, cell(end)?);
+    (c1 <= c2 && r1 <= r2).then_some(((c1, r1), (c2, r2)))
+}
+
+/// Letters of a zero based column, `A` to `Z` then `AA`
+pub fn column_name(mut col: usize) -> String {
+    let mut name = vec![];
+    loop {
+        name.push(b'A' + (col % 26) as u8);
+        if col < 26 {
+            break;
+        }
+        col = col / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+pub enum Outcome {
+    Continue,
+    Cancel,
+    /// Read options selecting the sheet and range
+    Pick(String),
+}
+
+/// Choice of the sheet of a workbook and of an optional cell range
+pub struct SheetPicker {
+    sheets: Vec<String>,
+    selected: usize,
+    range: Prompt<0>,
+    invalid: bool,
+}
+
+impl SheetPicker {
+    pub fn new(sheets: Vec<String>) -> Self {
+        Self {
+            sheets,
+            selected: 0,
+            range: Prompt::new(""),
+            invalid: false,
+        }
+    }
+
+    pub fn on_key(&mut self, event: &KeyEvent) -> Outcome {
+        let nb = self.sheets.len().max(1);
+        match event.code {
+            Key::Esc => return Outcome::Cancel,
+            Key::Right | Key::Tab => self.selected = (self.selected + 1) % nb,
+            Key::Left | Key::BackTab => self.selected = (self.selected + nb - 1) % nb,
+            Key::Char(c) => self.range.exec(PromptCmd::Write(c)),
+            Key::Backspace => self.range.exec(PromptCmd::Delete),
+            Key::Enter => {
+                let (range, _) = self.range.state();
+                let range = range.trim().to_uppercase();
+                self.invalid = !range.is_empty() && self::range(&range).is_none();
+                if !self.invalid {
+                    let mut options = format!("sheet={}", sql::lit(&self.sheets[self.selected]));
+                    if !range.is_empty() {
+                        write!(options, ", range={}", sql::lit(&range)).unwrap();
+                    }
+                    return Outcome::Pick(options);
+                }
+            }
+            _ => {}
+        }
+        Outcome::Continue
+    }
+
+    /// Number of lines of the picker
+    pub fn height(&self) -> usize {
+        2
+    }
+
+    pub fn draw(&mut self, c: &mut Canvas) {
+        let mut l = c.btm();
+        let label = if self.invalid {
+            "invalid range, expected e.g. A1:F500 "
+        } else {
+            "range (e.g. A1:F500, empty for all) "
+        };
+        let style = if self.invalid {
+            none().fg(Color::Red)
+        } else {
+            style::separator()
+        };
+        l.draw(label, style);
+        let (str, cursor) = self.range.state();
+        l.draw(&str[..cursor], none());
+        l.cursor();
+        l.draw(&str[cursor..], none());
+
+        let mut l = c.btm();
+        l.draw("sheet ", style::separator());
+        for (i, sheet) in self.sheets.iter().enumerate() {
+            let style = if i == self.selected {
+                style::selected()
+            } else {
+                style::primary()
+            };
+            l.draw(sheet, style);
+            l.draw("  ", none());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        assert_eq!(range("A1:A1"), Some(((0, 0), (0, 0))));
+        assert_eq!(range("B2:F500"), Some(((1, 1), (5, 499))));
+        assert_eq!(range("Z1:AA10"), Some(((25, 0), (26, 9))));
+        assert_eq!(range("AZ1:BA1"), Some(((51, 0), (52, 0))));
+        assert_eq!(range("A1:XFD1048576"), Some(((0, 0), (16_383, 1_048_575))));
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        for range in [
+            "", "A1", "A1:", ":A1", "A1:B", "1:2", "A0:B2", "a1:b2", "A1:B2:C3", "A+1:B2",
+            "A 1:B2", "B2:A1", "A2:B1",
+        ] {
+            assert_eq!(super::range(range), None, "{range}");
+        }
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        assert_eq!(range("A1:XFE1"), None);
+        assert_eq!(range("A1:A1048577"), None);
+        assert_eq!(range("A1:A99999999999999999999999"), None);
+        // Would overflow the column arithmetic without the bound
+        let letters = "Z".repeat(64);
+        assert_eq!(range(&format!("A1:{letters}1")), None);
+    }
+
+    #[test]
+    fn column_names() {
+        for (col, name) in [
+            (0, "A"),
+            (25, "Z"),
+            (26, "AA"),
+            (51, "AZ"),
+            (52, "BA"),
+            (701, "ZZ"),
+        ] {
+            assert_eq!(column_name(col), name);
+            assert_eq!(
+                range(&format!("{name}1:{name}1")),
+                Some(((col, 0), (col, 0)))
+            );
+        }
+        assert_eq!(column_name(702), "AAA");
+        assert_eq!(column_name(16_383), "XFD");
+    }
+}
@@ -0,0 +1,196 @@
+use std::{fmt::Write, sync::Arc};
+
+use arrow::{
+    compute::concat_batches,
+    datatypes::DataType,
+    record_batch::RecordBatch,
+    util::display::{ArrayFormatter, FormatOptions},
+};
+
+use crate::{
+    duckdb::DuckDb,
+    error::Result,
+    source::{DataFrame, Source},
+    sql,
+    task::{DuckTask, Runner},
+};
+
+/// Format a batch as tab separated values with a header line
+pub fn tsv(batch: &RecordBatch) -> Result<String> {
+    let mut buf = String::new();
+    for (i, field) in batch.schema().fields().iter().enumerate() {
+        if i > 0 {
+            buf.push('\t');
+        }
+        buf.push_str(field.name());
+    }
+    buf.push('\n');
+    let options = FormatOptions::default().with_display_error(false);
+    let fmts: Vec<_> = batch
+        .columns()
+        .iter()
+        .map(|array| ArrayFormatter::try_new(array, &options))
+        .collect::<std::result::Result<_, _>>()?;
+    for row in 0..batch.num_rows() {
+        for (i, fmt) in fmts.iter().enumerate() {
+            if i > 0 {
+                buf.push('\t');
+            }
+            write!(buf, "{}", fmt.value(row)).unwrap();
+        }
+        buf.push('\n');
+    }
+    Ok(buf)
+}
+
+/// Append a batch as comma separated values, nulls are empty fields
+pub fn csv(batch: &RecordBatch, header: bool, buf: &mut String) -> Result<()> {
+    if header {
+        for (i, field) in batch.schema().fields().iter().enumerate() {
+            if i > 0 {
+                buf.push(',');
+            }
+            push_csv_field(buf, field.name());
+        }
+        buf.push('\n');
+    }
+    let options = FormatOptions::default().with_display_error(false);
+    let fmts: Vec<_> = batch
+        .columns()
+        .iter()
+        .map(|array| ArrayFormatter::try_new(array, &options))
+        .collect::<std::result::Result<_, _>>()?;
+    let mut value = String::new();
+    for row in 0..batch.num_rows() {
+        for (i, (fmt, array)) in fmts.iter().zip(batch.columns()).enumerate() {
+            if i > 0 {
+                buf.push(',');
+            }
+            if !array.is_null(row) {
+                value.clear();
+                write!(value, "{}", fmt.value(row)).unwrap();
+                push_csv_field(buf, &value);
+            }
+        }
+        buf.push('\n');
+    }
+    Ok(())
+}
+
+/// Quote a field if it contains a delimiter, a quote or a line break
+fn push_csv_field(buf: &mut String, str: &str) {
+    if str.contains([',', '"', '\n', '\r']) {
+        buf.push('"');
+        buf.push_str(&str.replace('"', "\"\""));
+        buf.push('"');
+    } else {
+        buf.push_str(str);
+    }
+}
+
+/// Format a batch as a standalone styled HTML page
+pub fn html(batch: &RecordBatch) -> Result<String> {
+    let mut buf = String::from(concat!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n",
+        "table { border-collapse: collapse; font-family: monospace; }\n",
+        "th, td { border: 1px solid #ccc; padding: 2px 8px; white-space: pre; }\n",
+        "th { background: #eee; text-align: left; }\n",
+        "th small { display: block; color: #888; font-weight: normal; }\n",
+        ".nb { text-align: right; }\n",
+        ".null { color: #aaa; font-style: italic; }\n",
+        "</style>\n</head>\n<body>\n<table>\n<thead>\n<tr>"
+    ));
+    let schema = batch.schema();
+    for field in schema.fields() {
+        write!(
+            buf,
+            "<th>{}<small>{}</small></th>",
+            escape_html(field.name()),
+            escape_html(&field.data_type().to_string())
+        )
+        .unwrap();
+    }
+    buf.push_str("</tr>\n</thead>\n<tbody>\n");
+    let options = FormatOptions::default().with_display_error(false);
+    let fmts: Vec<_> = batch
+        .columns()
+        .iter()
+        .map(|array| ArrayFormatter::try_new(array, &options))
+        .collect::<std::result::Result<_, _>>()?;
+    for row in 0..batch.num_rows() {
+        buf.push_str("<tr>");
+        for ((fmt, array), field) in fmts.iter().zip(batch.columns()).zip(schema.fields()) {
+            if array.is_null(row) {
+                buf.push_str("<td class=\"null\">null</td>");
+            } else {
+                let class = if is_numeric(field.data_type()) {
+                    " class=\"nb\""
+                } else {
+                    ""
+                };
+                let value = fmt.value(row).to_string();
+                write!(buf, "<td{class}>{}</td>", escape_html(&value)).unwrap();
+            }
+        }
+        buf.push_str("</tr>\n");
+    }
+    buf.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    Ok(buf)
+}
+
+/// Whether values of this type are right aligned
+fn is_numeric(ty: &DataType) -> bool {
+    ty.is_numeric() || matches!(ty, DataType::Decimal128(..) | DataType::Decimal256(..))
+}
+
+fn escape_html(str: &str) -> String {
+    let mut buf = String::with_capacity(str.len());
+    for c in str.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '"' => buf.push_str("&quot;"),
+            c => buf.push(c),
+        }
+    }
+    buf
+}
+
+/// Write a batch into a file, duckdb infer the format from the extension
+pub fn to_file(batch: RecordBatch, path: &str) -> Result<()> {
+    if is_html(path) {
+        std::fs::write(path, html(&batch)?)?;
+        return Ok(());
+    }
+    let db = DuckDb::mem()?;
+    let con = db.conn(|_| Ok(()))?;
+    con.store("current", &batch.into())?;
+    con.execute(&format!("COPY current TO {}", sql::lit(path)))?;
+    Ok(())
+}
+
+/// Write the whole result of the source query into a file, not only the
+/// loaded rows, keeping the visible columns of `columns` and their aliases
+pub fn full(source: Arc<Source>, columns: &str, path: &str, runner: &Runner) -> DuckTask<()> {
+    let query = source.ops().select(source.init_sql(), columns);
+    let path = path.to_string();
+    runner.duckdb(source, move |_, con| {
+        if is_html(&path) {
+            let df = con
+                .query(&query)?
+                .map(|d| d.map_err(|e| e.into()))
+                .collect::<Result<DataFrame>>()?;
+            let batch = concat_batches(df.schema(), &df.0.batchs)?;
+            std::fs::write(&path, html(&batch)?)?;
+        } else {
+            con.execute(&format!("COPY ({query}) TO {}", sql::lit(&path)))?;
+        }
+        Ok(())
+    })
+}
+
+/// Whether a file is written by us as duckdb has no HTML format
+fn is_html(path: &str) -> bool {
+    path.ends_with(".html") || path.ends_with(".htm")
+}
@@ -0,0 +1,207 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use libduckdb_sys::duckdb_query_progress_type;
+
+use crate::{
+    archive,
+    error::Result,
+    fmt::{Age, Col, ColBuilder, GridBuffer, Size},
+    grid::{Frame, Grid, Settings},
+    source::Source,
+    task::{Runner, Task},
+    view::{View, ViewState},
+};
+
+const COLUMNS: [&str; 3] = ["name", "size", "modified"];
+
+/// Supported file or subdirectory of a directory, or member of an archive
+struct Entry {
+    path: PathBuf,
+    /// File name with a trailing slash for directories, path inside an archive
+    /// for members
+    name: String,
+    /// Size in bytes, None for directories
+    size: Option<u64>,
+    modified: Option<SystemTime>,
+}
+
+impl Entry {
+    fn age(&self) -> Option<Age> {
+        self.modified.and_then(|m| m.elapsed().ok()).map(Age)
+    }
+}
+
+/// Entries of a directory, subdirectories first
+struct Listing(Vec<Entry>);
+
+impl Frame for Listing {
+    fn nb_col(&self) -> usize {
+        COLUMNS.len()
+    }
+
+    fn nb_row(&self) -> usize {
+        self.0.len()
+    }
+
+    fn idx_iter(&self, buf: &mut GridBuffer, skip: usize, take: usize) -> Col {
+        let mut col = ColBuilder::new(buf);
+        for i in skip..skip + take {
+            col.add_nb(i);
+        }
+        col.build()
+    }
+
+    fn col_name(&self, idx: usize) -> String {
+        COLUMNS[idx].into()
+    }
+
+    fn col_iter(&self, buf: &mut GridBuffer, idx: usize, skip: usize, take: usize) -> Col {
+        let mut col = ColBuilder::new(buf);
+        for entry in self.0.iter().skip(skip).take(take) {
+            match idx {
+                0 => col.add_str(&entry.name),
+                1 => match entry.size {
+                    Some(size) => col.add_nb_dsp(Size(size as usize)),
+                    None => col.add_null(),
+                },
+                _ => match entry.age() {
+                    Some(age) => col.add_dsp(age),
+                    None => col.add_null(),
+                },
+            }
+        }
+        col.build()
+    }
+
+    fn value(&self, row: usize, idx: usize) -> Option<String> {
+        let entry = self.0.get(row)?;
+        match idx {
+            0 => Some(entry.name.clone()),
+            1 => entry.size.map(|size| Size(size as usize).to_string()),
+            _ => entry.age().map(|age| age.to_string()),
+        }
+    }
+}
+
+/// Data files of a directory or an archive, each opened in a new tab
+pub struct FilesView {
+    dir: PathBuf,
+    listing: Listing,
+    error: Option<String>,
+    /// Entry being opened, archive members are extracted first
+    opening: Option<Task<(), Option<Result<Source>>>>,
+    pub grid: Grid,
+}
+
+impl FilesView {
+    pub fn new(dir: PathBuf, settings: Settings) -> Self {
+        let mut view = Self {
+            dir,
+            listing: Listing(vec![]),
+            error: None,
+            opening: None,
+            grid: Grid::new(settings),
+        };
+        view.refresh();
+        view
+    }
+
+    /// List the directory or archive again
+    pub fn refresh(&mut self) {
+        let entries = if archive::is_archive(&self.dir) {
+            members(&self.dir)
+        } else {
+            list(&self.dir)
+        };
+        match entries {
+            Ok(entries) => {
+                self.listing = Listing(entries);
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err.0),
+        }
+    }
+
+    /// Open the entry under the cursor in the background, its source is
+    /// returned by `opened`
+    pub fn open(&mut self, runner: &Runner) {
+        let Some(entry) = self.listing.0.get(self.grid.nav.c_row()) else {
+            return;
+        };
+        let member = archive::is_archive(&self.dir).then(|| entry.name.clone());
+        let (dir, path) = (self.dir.clone(), entry.path.clone());
+        self.opening = Some(runner.task((), None, move |ctx| {
+            let source = match member {
+                Some(name) => Source::from_member(&dir, &name),
+                None => Source::from_path(&path),
+            };
+            ctx.lock(|r| *r = Some(source))
+        }));
+    }
+
+    /// Source of the opened entry, or why it cannot be read, once ready
+    pub fn opened(&mut self) -> Option<Result<Source>> {
+        let result = self.opening.as_ref()?.lock(Option::take)?;
+        self.opening = None;
+        Some(result)
+    }
+}
+
+/// Supported files and subdirectories of a directory, hidden ones are skipped
+fn list(dir: &Path) -> Result<Vec<Entry>> {
+    let mut entries = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let mut name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        let meta = entry.metadata()?;
+        if name.starts_with('.') || !(meta.is_dir() || Source::is_supported(&path)) {
+            continue;
+        }
+        if meta.is_dir() {
+            name.push('/');
+        }
+        entries.push(Entry {
+            path,
+            name,
+            size: (!meta.is_dir()).then_some(meta.len()),
+            modified: meta.modified().ok(),
+        });
+    }
+    entries.sort_by(|a, b| (a.size.is_some(), &a.name).cmp(&(b.size.is_some(), &b.name)));
+    Ok(entries)
+}
+
+/// Supported members of an archive
+fn members(path: &Path) -> Result<Vec<Entry>> {
+    Ok(archive::members(path)?
+        .into_iter()
+        .map(|member| Entry {
+            path: path.join(&member.name),
+            name: member.name,
+            size: Some(member.size),
+            modified: member.modified,
+        })
+        .collect())
+}
+
+impl View for FilesView {
+    fn tick(&mut self) -> ViewState {
+        let progress = duckdb_query_progress_type {
+            percentage: -1.,
+            rows_processed: 0,
+            total_rows_to_process: 0,
+        };
+        let opening = self.opening.is_some();
+        ViewState {
+            loading: opening.then(|| ("open".into(), progress)),
+            streaming: false,
+            frame: &self.listing,
+            grid: &mut self.grid,
+            err: self.error.as_deref(),
+        }
+    }
+}
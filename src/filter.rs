@@ -0,0 +1,333 @@
+use std::sync::Arc;
+
+use arrow::datatypes::DataType;
+use tui::{
+    crossterm::event::{KeyCode as Key, KeyEvent},
+    none, Canvas,
+};
+
+use crate::{
+    error::Result,
+    shell::prompt::{Prompt, PromptCmd},
+    source::{DataFrame, Source},
+    sql, style,
+    task::{DuckTask, Runner},
+};
+
+/// Maximum number of distinct values suggested for a column
+const LIMIT: usize = 1000;
+
+/// Comparison of a column with a value
+#[derive(Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    StartsWith,
+    EndsWith,
+    Regex,
+    IsTrue,
+    IsFalse,
+    IsNull,
+    NotNull,
+}
+
+impl Op {
+    /// Comparisons making sense for a column type
+    fn for_type(ty: &DataType) -> &'static [Op] {
+        use Op::*;
+        match ty {
+            DataType::Boolean => &[IsTrue, IsFalse, IsNull, NotNull],
+            DataType::Utf8 | DataType::LargeUtf8 => &[
+                Contains, Eq, Ne, StartsWith, EndsWith, Regex, IsNull, NotNull,
+            ],
+            ty if ty.is_numeric() || ty.is_temporal() => &[Eq, Ne, Lt, Le, Gt, Ge, IsNull, NotNull],
+            _ => &[Eq, Ne, Contains, IsNull, NotNull],
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Contains => "contains",
+            Op::StartsWith => "starts with",
+            Op::EndsWith => "ends with",
+            Op::Regex => "matches",
+            Op::IsTrue => "is true",
+            Op::IsFalse => "is false",
+            Op::IsNull => "is null",
+            Op::NotNull => "is not null",
+        }
+    }
+
+    /// Whether the comparison needs a value
+    fn binary(&self) -> bool {
+        !matches!(self, Op::IsTrue | Op::IsFalse | Op::IsNull | Op::NotNull)
+    }
+
+    /// Predicate comparing the column with the value, the literal is
+    /// implicitly cast by duckdb to the column type
+    fn sql(&self, name: &str, value: &str) -> String {
+        let col = sql::ident(name);
+        let val = sql::lit(value);
+        match self {
+            Op::Eq => format!("{col} = {val}"),
+            Op::Ne => format!("{col} IS DISTINCT FROM {val}"),
+            Op::Lt => format!("{col} < {val}"),
+            Op::Le => format!("{col} <= {val}"),
+            Op::Gt => format!("{col} > {val}"),
+            Op::Ge => format!("{col} >= {val}"),
+            Op::Contains => sql::matches(name, value, false),
+            Op::StartsWith => format!("starts_with({col}::VARCHAR, {val})"),
+            Op::EndsWith => format!("suffix({col}::VARCHAR, {val})"),
+            Op::Regex => sql::matches(name, value, true),
+            Op::IsTrue => format!("{col} IS TRUE"),
+            Op::IsFalse => format!("{col} IS FALSE"),
+            Op::IsNull => format!("{col} IS NULL"),
+            Op::NotNull => format!("{col} IS NOT NULL"),
+        }
+    }
+}
+
+enum Step {
+    Column,
+    Operator(usize),
+    Value(usize, Op),
+    /// Condition complete, add another one or apply
+    Next,
+}
+
+pub enum Outcome {
+    Continue,
+    Cancel,
+    Apply(String),
+}
+
+/// Guided construction of a filter, one condition at a time
+pub struct FilterBuilder {
+    source: Arc<Source>,
+    columns: Vec<(String, DataType)>,
+    /// Conditions already built, joined by AND or OR
+    expr: String,
+    /// Connector preceding the condition being built
+    connector: &'static str,
+    step: Step,
+    prompt: Prompt<0>,
+    /// Selected option of the current step
+    selected: usize,
+    task: Option<DuckTask<Vec<String>>>,
+    /// Most frequent values of the chosen column
+    values: Vec<String>,
+}
+
+impl FilterBuilder {
+    pub fn new(source: Arc<Source>, columns: Vec<(String, DataType)>) -> Self {
+        Self {
+            source,
+            columns,
+            expr: String::new(),
+            connector: "",
+            step: Step::Column,
+            prompt: Prompt::new(""),
+            selected: 0,
+            task: None,
+            values: vec![],
+        }
+    }
+
+    /// Options of the current step matching the input
+    fn options(&self) -> Vec<&str> {
+        let (input, _) = self.prompt.state();
+        let input = input.to_lowercase();
+        let matches = |s: &&str| s.to_lowercase().contains(&input);
+        match &self.step {
+            Step::Column => self
+                .columns
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .filter(matches)
+                .collect(),
+            Step::Operator(idx) => Op::for_type(&self.columns[*idx].1)
+                .iter()
+                .map(Op::label)
+                .collect(),
+            Step::Value(..) => self
+                .values
+                .iter()
+                .map(String::as_str)
+                .filter(matches)
+                .collect(),
+            Step::Next => vec![],
+        }
+    }
+
+    /// Start building a condition on a column
+    fn pick_column(&mut self, idx: usize, runner: &Runner) {
+        let col = sql::ident(&self.columns[idx].0);
+        self.values.clear();
+        self.task = Some(runner.duckdb(self.source.clone(), move |source, con| {
+            let sql = format!(
+                "SELECT {col}::VARCHAR FROM ({}) WHERE {col} IS NOT NULL GROUP BY ALL ORDER BY count(*) DESC LIMIT {LIMIT}",
+                source.load_sql()
+            );
+            let df = con
+                .query(&sql)?
+                .map(|d| d.map_err(|e| e.into()))
+                .collect::<Result<DataFrame>>()?;
+            Ok((0..df.num_rows()).filter_map(|row| df.value(row, 0)).collect())
+        }));
+        self.step = Step::Operator(idx);
+    }
+
+    /// Append the completed condition
+    fn push(&mut self, idx: usize, op: Op, value: &str) {
+        let condition = op.sql(&self.columns[idx].0, value);
+        if self.expr.is_empty() {
+            self.expr = condition;
+        } else {
+            self.expr = format!("({}) {} {condition}", self.expr, self.connector);
+        }
+        self.step = Step::Next;
+    }
+
+    pub fn on_key(&mut self, event: &KeyEvent, runner: &Runner) -> Outcome {
+        let nb_options = self.options().len();
+        match (&self.step, event.code) {
+            (_, Key::Esc) => return Outcome::Cancel,
+            (Step::Next, Key::Enter) => return Outcome::Apply(self.expr.clone()),
+            (Step::Next, Key::Char(c @ ('a' | 'o'))) => {
+                self.connector = if c == 'a' { "AND" } else { "OR" };
+                self.step = Step::Column;
+            }
+            (Step::Next, _) => {}
+            (Step::Column | Step::Value(..), Key::Char(c)) => {
+                self.prompt.exec(PromptCmd::Write(c));
+                self.selected = 0;
+                return Outcome::Continue;
+            }
+            (Step::Column | Step::Value(..), Key::Backspace) => {
+                self.prompt.exec(PromptCmd::Delete);
+                self.selected = 0;
+                return Outcome::Continue;
+            }
+            (Step::Value(..), Key::Left) => self.prompt.exec(PromptCmd::Left),
+            (Step::Value(..), Key::Right) => self.prompt.exec(PromptCmd::Right),
+            (Step::Value(..), Key::Down) => {
+                self.selected = (self.selected + 1).min(nb_options.saturating_sub(1))
+            }
+            (Step::Value(..), Key::Up) => self.selected = self.selected.saturating_sub(1),
+            (Step::Value(..), Key::Tab) => {
+                if let Some(value) = self.options().get(self.selected) {
+                    let value = value.to_string();
+                    self.prompt.set(&value);
+                }
+            }
+            (_, Key::Right | Key::Tab) if nb_options > 0 => {
+                self.selected = (self.selected + 1) % nb_options
+            }
+            (_, Key::Left) if nb_options > 0 => {
+                self.selected = (self.selected + nb_options - 1) % nb_options
+            }
+            (Step::Column, Key::Enter) => {
+                let name = self.options().get(self.selected).map(|s| s.to_string());
+                if let Some(idx) = name.and_then(|n| self.columns.iter().position(|c| c.0 == n)) {
+                    self.pick_column(idx, runner);
+                    self.prompt.set("");
+                    self.selected = 0;
+                }
+            }
+            (Step::Operator(idx), Key::Enter) => {
+                let (idx, op) = (*idx, Op::for_type(&self.columns[*idx].1)[self.selected]);
+                if op.binary() {
+                    self.step = Step::Value(idx, op);
+                } else {
+                    self.push(idx, op, "");
+                }
+                self.selected = 0;
+            }
+            (Step::Value(idx, op), Key::Enter) => {
+                let (idx, op) = (*idx, *op);
+                let (value, _) = self.prompt.state();
+                let value = value.to_string();
+                self.push(idx, op, &value);
+                self.prompt.set("");
+                self.selected = 0;
+            }
+            _ => {}
+        }
+        Outcome::Continue
+    }
+
+    /// Number of lines of the builder
+    pub fn height(&self) -> usize {
+        3
+    }
+
+    pub fn draw(&mut self, c: &mut Canvas) {
+        if let Some(result) = self.task.as_mut().and_then(|t| t.tick()) {
+            // Suggestions are optional, errors are ignored
+            self.values = result.unwrap_or_default();
+            self.task = None;
+        }
+        let options = self.options();
+        let selected = self.selected.min(options.len().saturating_sub(1));
+
+        // Prompt or actions
+        let mut l = c.btm();
+        let label = match &self.step {
+            Step::Next => None,
+            Step::Column => Some("column".into()),
+            Step::Operator(idx) => Some(format!("{} operator", self.columns[*idx].0)),
+            Step::Value(idx, op) => Some(format!("{} {} value", self.columns[*idx].0, op.label())),
+        };
+        match label {
+            None => {
+                l.draw(
+                    "a add AND condition, o add OR condition, Enter apply, Esc cancel",
+                    style::separator(),
+                );
+            }
+            Some(label) => {
+                l.draw(format_args!("{label} "), style::separator());
+                if !matches!(self.step, Step::Operator(_)) {
+                    let (str, cursor) = self.prompt.state();
+                    l.draw(&str[..cursor], none());
+                    l.cursor();
+                    l.draw(&str[cursor..], none());
+                }
+            }
+        }
+
+        // Options of the current step
+        let mut l = c.btm();
+        if options.is_empty() && matches!(self.step, Step::Value(..)) && self.task.is_some() {
+            l.draw("loading values…", style::index());
+        }
+        for (i, option) in options.iter().enumerate() {
+            let style = if i == selected {
+                style::selected()
+            } else {
+                style::primary()
+            };
+            l.draw(option, style);
+            l.draw("  ", none());
+        }
+
+        // Conditions built so far
+        let mut l = c.btm();
+        l.draw("filter ", style::separator());
+        l.draw(&self.expr, style::primary());
+        if matches!(self.step, Step::Column) && !self.expr.is_empty() {
+            l.draw(format_args!(" {} …", self.connector), style::index());
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use std::{
     fmt::{Display, Write},
     ops::Range,
+    time::Duration,
 };
 
-use tui::unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use once_cell::sync::Lazy;
+use tui::unicode_width::UnicodeWidthChar;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::Cell;
 
@@ -61,7 +64,7 @@ impl<'a> std::fmt::Write for CellFmtLimit<'a> {
     fn write_str(&mut self, s: &str) -> std::fmt::Result {
         if self.curr < self.max {
             self.buf.push_str(s);
-            self.curr += s.width();
+            self.curr += str_width(s);
             Ok(())
         } else {
             Err(std::fmt::Error)
@@ -106,31 +109,36 @@ impl<'a, 'b> ColBuilder<'a, 'b> {
 
     pub fn add_str(&mut self, str: &'a str) {
         self.col.cells.push(Cell::Str(str));
-        self.col.max_lhs = self.col.max_lhs.max(str.width())
+        self.col.max_lhs = self.col.max_lhs.max(str_width(str))
     }
 
     pub fn add_nb(&mut self, nb: impl lexical_core::ToLexical) {
         let stack = &mut [b'0'; lexical_core::BUFFER_SIZE];
         let slc = lexical_core::write(nb, stack);
         let str = unsafe { std::str::from_utf8_unchecked(slc) };
+        self.add_nb_dsp(str);
+    }
+
+    /// Formatted number, aligned on its decimal point
+    pub(crate) fn add_nb_dsp(&mut self, dsp: impl Display) {
+        let range = self.buff_dsp(dsp);
+        let str = &self.buf.buf[range.clone()];
         let (lhs, rhs) = if let Some((lhs, rhs)) = str.split_once('.') {
-            (lhs.len(), rhs.len() + 1)
+            (str_width(lhs), str_width(rhs) + 1)
         } else {
-            (str.len(), 0)
+            (str_width(str), 0)
         };
         self.col.max_lhs = self.col.max_lhs.max(lhs);
         self.col.max_rhs = self.col.max_rhs.max(rhs);
-        let ty = Cell::Nb {
-            range: self.buff_dsp(str),
-            lhs,
-            rhs,
-        };
-        self.col.cells.push(ty);
+        self.col.cells.push(Cell::Nb { range, lhs, rhs });
     }
 
     pub(crate) fn add_dsp(&mut self, dsp: impl Display) {
         let range = self.buff_dsp(dsp);
-        self.col.max_lhs = self.col.max_lhs.max(self.buf.buf[range.clone()].width());
+        self.col.max_lhs = self
+            .col
+            .max_lhs
+            .max(str_width(&self.buf.buf[range.clone()]));
         self.col.cells.push(Cell::Dsp(range));
     }
 
@@ -146,7 +154,24 @@ pub struct Col<'a> {
     cells: Vec<Cell<'a>>,
 }
 
+/// Kind of content of a column
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColKind {
+    Text,
+    Number,
+    Other,
+}
+
 impl<'a> Col<'a> {
+    /// Kind of the first non null cell
+    pub fn kind(&self) -> ColKind {
+        match self.cells.iter().find(|c| !matches!(c, Cell::Null)) {
+            Some(Cell::Str(_)) => ColKind::Text,
+            Some(Cell::Nb { .. }) => ColKind::Number,
+            _ => ColKind::Other,
+        }
+    }
+
     pub fn align_right(&mut self) {
         self.align_right = true;
     }
@@ -156,6 +181,16 @@ impl<'a> Col<'a> {
     }
 
     pub fn fmt<'b>(&self, grid: &'b mut GridBuffer, idx: usize, budget: usize) -> &'b str {
+        self.fmt_cell(grid, idx, budget).0
+    }
+
+    /// Format a cell and tell if it was truncated to fit the budget
+    pub fn fmt_cell<'b>(
+        &self,
+        grid: &'b mut GridBuffer,
+        idx: usize,
+        budget: usize,
+    ) -> (&'b str, bool) {
         let buf = &mut grid.fmt_buf;
         buf.clear();
         fn pad(buff: &mut String, amount: usize) {
@@ -183,29 +218,253 @@ impl<'a> Col<'a> {
             }
         };
         // Fill remaining budget
-        pad(buf, budget.saturating_sub(buf.width()));
-        // Trim buffer
-        trim_buffer(buf, budget)
+        pad(buf, budget.saturating_sub(str_width(buf)));
+        // Trim buffer in logical order to keep the start of the text
+        let truncated = trim_buffer(buf, budget).1;
+        if !*NATIVE_BIDI {
+            visual(buf);
+        }
+        (buf, truncated)
+    }
+}
+
+/// Display width of a grapheme cluster
+///
+/// Terminals render emoji sequences as a single wide glyph and combining marks
+/// over their base char, so the cluster takes the width of its widest char or
+/// of an emoji.
+pub fn grapheme_width(g: &str) -> usize {
+    let width = g.chars().map(|c| c.width().unwrap_or(0)).max().unwrap_or(0);
+    let emoji = g.chars().nth(1).is_some()
+        && g.chars()
+            .any(|c| matches!(c, '\u{fe0f}' | '\u{200d}' | '\u{1f1e6}'..='\u{1f1ff}'));
+    if emoji {
+        width.max(2)
+    } else {
+        width
     }
 }
 
-fn trim_buffer(buf: &mut String, budget: usize) -> &str {
+/// Display width of a string, by grapheme cluster
+pub fn str_width(str: &str) -> usize {
+    str.graphemes(true).map(grapheme_width).sum()
+}
+
+fn trim_buffer(buf: &mut String, budget: usize) -> (&str, bool) {
     let overflow = buf
-        .char_indices()
-        .scan((0, 0), |(sum, prev), (mut pos, c)| {
+        .grapheme_indices(true)
+        .scan((0, 0), |(sum, prev), (mut pos, g)| {
             std::mem::swap(prev, &mut pos);
-            *sum += c.width().unwrap_or(0);
+            *sum += grapheme_width(g);
             Some((pos, *sum > budget))
         })
         .find_map(|(pos, overflow)| (overflow).then_some(pos));
     if let Some(pos) = overflow {
         buf.replace_range(pos.., "…");
     }
-    buf
+    (buf, overflow.is_some())
 }
 
 pub fn rtrim(it: impl Display, buf: &mut String, budget: usize) -> &str {
     buf.clear();
     write!(buf, "{it}").unwrap();
-    trim_buffer(buf, budget)
+    trim_buffer(buf, budget).0
+}
+
+/// Whether this char belongs to a right-to-left script
+fn is_rtl(c: char) -> bool {
+    matches!(c,
+        '\u{0590}'..='\u{08FF}'
+        | '\u{FB1D}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFF}'
+        | '\u{10800}'..='\u{10FFF}'
+        | '\u{1E800}'..='\u{1EFFF}'
+    )
+}
+
+/// Whether the terminal reorders right-to-left text itself
+static NATIVE_BIDI: Lazy<bool> = Lazy::new(|| {
+    let var = |name| std::env::var(name).unwrap_or_default();
+    var("VTE_VERSION").parse::<u32>().is_ok_and(|v| v >= 5800)
+        || std::env::var_os("KONSOLE_VERSION").is_some()
+        || std::env::var_os("MLTERM").is_some()
+        || var("TERM").contains("mlterm")
+});
+
+/// Reorder right-to-left runs of a formatted cell in visual order
+///
+/// Terminals without bidi support draw chars from left to right, so those
+/// runs are reversed to be readable and keep the column layout intact.
+/// Numbers keep their order and brackets are mirrored inside those runs, a
+/// trailing ellipsis moves with its run to mark the cut end.
+fn visual(buf: &mut String) {
+    if !buf.chars().any(is_rtl) {
+        return;
+    }
+    let units: Vec<&str> = buf.graphemes(true).collect();
+    let is_rtl = |g: &str| g.chars().next().is_some_and(is_rtl);
+    let is_strong = |g: &str| g.chars().next().is_some_and(char::is_alphabetic);
+    let is_digit = |g: &str| g.chars().next().is_some_and(|c| c.is_ascii_digit());
+    let mut out = String::with_capacity(buf.len());
+    let mut i = 0;
+    while i < units.len() {
+        if !is_rtl(units[i]) {
+            out.push_str(units[i]);
+            i += 1;
+            continue;
+        }
+        // Extend the run over neutrals and numbers up to the last rtl unit
+        let mut end = i + 1;
+        let mut j = i + 1;
+        while j < units.len() && (is_rtl(units[j]) || !is_strong(units[j])) {
+            j += 1;
+            if is_rtl(units[j - 1]) || is_digit(units[j - 1]) || units[j - 1] == "…" {
+                end = j;
+            }
+        }
+        // Reverse the run, keeping numbers in order
+        let run = &units[i..end];
+        let mut k = run.len();
+        while k > 0 {
+            let mut start = k - 1;
+            if is_digit(run[start]) {
+                while start > 0 && is_digit(run[start - 1]) {
+                    start -= 1;
+                }
+                run[start..k].iter().for_each(|g| out.push_str(g));
+            } else {
+                match run[start] {
+                    "(" => out.push(')'),
+                    ")" => out.push('('),
+                    "[" => out.push(']'),
+                    "]" => out.push('['),
+                    "{" => out.push('}'),
+                    "}" => out.push('{'),
+                    "<" => out.push('>'),
+                    ">" => out.push('<'),
+                    g => out.push_str(g),
+                }
+            }
+            k = start;
+        }
+        i = end;
+    }
+    *buf = out;
+}
+
+/// Human readable memory size
+pub struct Size(pub usize);
+
+impl Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+        if self.0 < 1024 {
+            return write!(f, "{}B", self.0);
+        }
+        let mut size = self.0 as f64 / 1024.;
+        let mut unit = 0;
+        while size >= 1024. && unit + 1 < UNITS.len() {
+            size /= 1024.;
+            unit += 1;
+        }
+        if size < 10. {
+            write!(f, "{size:.1}{}", UNITS[unit])
+        } else {
+            write!(f, "{size:.0}{}", UNITS[unit])
+        }
+    }
+}
+
+/// Number rounded to at most 3 decimals without trailing zeros, large or
+/// tiny magnitudes in scientific notation, e.g. `1234.5` or `1.235e12`
+pub struct Decimal(pub f64);
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let abs = self.0.abs();
+        if abs != 0. && !(1e-3..1e12).contains(&abs) {
+            return write!(f, "{:.3e}", self.0);
+        }
+        let fixed = format!("{:.3}", self.0);
+        let fixed = fixed.trim_end_matches('0').trim_end_matches('.');
+        f.write_str(if fixed == "-0" { "0" } else { fixed })
+    }
+}
+
+/// Human readable time elapsed since an event, e.g. `5m ago`
+pub struct Age(pub Duration);
+
+impl Display for Age {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.0.as_secs();
+        match secs {
+            0..=59 => write!(f, "<1m ago"),
+            60..=3599 => write!(f, "{}m ago", secs / 60),
+            3600..=86399 => write!(f, "{}h ago", secs / 3600),
+            _ => write!(f, "{}d ago", secs / 86400),
+        }
+    }
+}
+
+/// Interval in the unit it was most likely written in, e.g. `500ms` or `5m`
+pub struct Interval(pub Duration);
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (secs, millis) = (self.0.as_secs(), self.0.subsec_millis());
+        if secs == 0 {
+            write!(f, "{millis}ms")
+        } else if millis != 0 {
+            write!(f, "{}s", Decimal(self.0.as_secs_f64()))
+        } else if secs % 3600 == 0 {
+            write!(f, "{}h", secs / 3600)
+        } else if secs % 60 == 0 {
+            write!(f, "{}m", secs / 60)
+        } else {
+            write!(f, "{secs}s")
+        }
+    }
+}
+
+/// Signed duration in seconds with `digits` fractional digits, e.g. `-1.500s`
+pub struct Seconds {
+    pub value: i64,
+    pub digits: u32,
+}
+
+impl Display for Seconds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.value < 0 { "-" } else { "" };
+        let abs = self.value.unsigned_abs();
+        if self.digits == 0 {
+            return write!(f, "{sign}{abs}s");
+        }
+        let scale = 10u64.pow(self.digits);
+        let width = self.digits as usize;
+        write!(f, "{sign}{}.{:0width$}s", abs / scale, abs % scale)
+    }
+}
+
+/// Split a string into at most `max` lines fitting in `width`
+pub fn wrap(str: &str, width: usize, max: usize) -> Vec<&str> {
+    let mut lines = Vec::new();
+    for line in str.lines() {
+        let mut start = 0;
+        let mut sum = 0;
+        for (pos, g) in line.grapheme_indices(true) {
+            let w = grapheme_width(g);
+            if sum + w > width && pos > start {
+                lines.push(&line[start..pos]);
+                start = pos;
+                sum = 0;
+            }
+            sum += w;
+        }
+        lines.push(&line[start..]);
+    }
+    if lines.is_empty() {
+        lines.push("");
+    }
+    lines.truncate(max);
+    lines
 }
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{ArrayRef, StringArray, UInt64Array},
+    record_batch::RecordBatch,
+};
+
+use crate::{
+    grid::{Grid, Settings},
+    search::{Matches, Search},
+    source::{DataFrame, Source},
+    task::{DuckTask, Runner},
+    view::{View, ViewState},
+};
+
+/// Maximum number of matches listed per tab
+const MAX_HITS: usize = 1000;
+
+/// Search of a pattern in one tab
+struct TabSearch {
+    /// Id of the searched tab
+    tab: usize,
+    name: String,
+    task: Option<DuckTask<(Vec<String>, Matches)>>,
+    /// Column names of the tab and matching cells
+    result: (Vec<String>, Matches),
+    error: Option<String>,
+}
+
+/// Matches of a pattern in every column of every open tab
+pub struct GlobalSearchView {
+    searches: Vec<TabSearch>,
+    /// Tab id, row and column of each listed match
+    hits: Vec<(usize, usize, usize)>,
+    df: DataFrame,
+    error: Option<String>,
+    pub grid: Grid,
+}
+
+impl GlobalSearchView {
+    /// Search the sources of the tabs in the background
+    pub fn new(
+        pattern: &str,
+        sources: Vec<(usize, Arc<Source>)>,
+        runner: &Runner,
+        settings: Settings,
+    ) -> Self {
+        let searches = sources
+            .into_iter()
+            .map(|(tab, source)| {
+                let pattern = pattern.to_string();
+                TabSearch {
+                    tab,
+                    name: source.name().to_string(),
+                    task: Some(runner.duckdb(source, move |source, con| {
+                        let schema = source.schema_on(&con)?;
+                        let names: Vec<_> =
+                            schema.fields().iter().map(|f| f.name().clone()).collect();
+                        let cols: Vec<_> = (0..names.len()).collect();
+                        let sql = Search::sql(&source.load_sql(), &names, &cols, &pattern, false);
+                        let matches = Search::query(&con, &format!("{sql} LIMIT {MAX_HITS}"))?;
+                        Ok((names, matches))
+                    })),
+                    result: (vec![], vec![]),
+                    error: None,
+                }
+            })
+            .collect();
+        let mut view = Self {
+            searches,
+            hits: vec![],
+            df: DataFrame::empty(),
+            error: None,
+            grid: Grid::new(settings),
+        };
+        view.rebuild();
+        view
+    }
+
+    /// Tab id, row and column of the match under the cursor
+    pub fn selected(&self) -> Option<(usize, usize, usize)> {
+        self.hits.get(self.grid.nav.c_row()).copied()
+    }
+
+    fn rebuild(&mut self) {
+        let (mut tabs, mut rows, mut cols) = (vec![], vec![], vec![]);
+        self.hits.clear();
+        for search in &self.searches {
+            let (names, matches) = &search.result;
+            for (row, col) in matches {
+                tabs.push(search.name.clone());
+                rows.push(*row as u64 + 1);
+                cols.push(names[*col].clone());
+                self.hits.push((search.tab, *row, *col));
+            }
+        }
+        let cols: [(&str, ArrayRef); 3] = [
+            ("tab", Arc::new(StringArray::from(tabs))),
+            ("row", Arc::new(UInt64Array::from(rows))),
+            ("column", Arc::new(StringArray::from(cols))),
+        ];
+        self.df = RecordBatch::try_from_iter(cols).unwrap().into();
+        self.error = self
+            .searches
+            .iter()
+            .filter_map(|s| s.error.as_ref().map(|e| format!("{}: {e}", s.name)))
+            .reduce(|a, b| format!("{a}\n{b}"));
+    }
+}
+
+impl View for GlobalSearchView {
+    fn tick(&mut self) -> ViewState {
+        let mut changed = false;
+        for search in &mut self.searches {
+            match search.task.as_mut().and_then(|t| t.tick()) {
+                Some(Ok(result)) => search.result = result,
+                Some(Err(it)) => search.error = Some(it.0),
+                None => continue,
+            }
+            search.task = None;
+            changed = true;
+        }
+        if changed {
+            self.rebuild();
+        }
+
+        ViewState {
+            loading: self
+                .searches
+                .iter()
+                .find_map(|s| s.task.as_ref())
+                .map(|t| ("search".into(), t.progress())),
+            streaming: false,
+            frame: &self.df,
+            grid: &mut self.grid,
+            err: self.error.as_deref(),
+        }
+    }
+}
@@ -1,22 +1,38 @@
+use std::{
+    fmt::Write,
+    ops::Range,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use arrow::{
+    datatypes::{DataType, Field, Schema},
+    record_batch::RecordBatch,
+};
 use tui::{
     crossterm::event::{KeyCode as Key, KeyEvent, KeyModifiers},
     unicode_width::UnicodeWidthStr,
-    Canvas,
+    Canvas, Style,
 };
 
 use crate::{
-    fmt::{rtrim, Col, ColBuilder, GridBuffer},
+    fmt::{rtrim, wrap, Col, ColBuilder, ColKind, GridBuffer},
     source::DataFrame,
-    style,
-    tab::{GridUI, Status},
+    sql,
+    style::{self, Glyphs, Theme},
+    tab::{ColsView, GridUI, Status},
     OnKey,
 };
 
-use self::{nav::Nav, projection::Projection, sizer::Sizer};
+use self::{nav::Nav, projection::Projection, selection::Selection, sort::Sort};
 
 pub mod nav;
 mod projection;
+mod selection;
 mod sizer;
+mod sort;
+
+pub use sizer::{MaxWidth, Sizer};
 
 #[derive(Clone)]
 enum State {
@@ -25,26 +41,225 @@ enum State {
     Projection,
 }
 
+/// How long changed cells stay highlighted after a refresh
+const HIGHLIGHT: Duration = Duration::from_secs(3);
+/// Number of lines of a row in tall mode
+const TALL_LINES: usize = 3;
+/// Maximum number of columns to automatically use tall rows
+const TALL_MAX_COLS: usize = 3;
+
+/// Multi-line rows mode
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tall {
+    /// Tall rows when few columns do not fit the screen
+    Auto,
+    On,
+    Off,
+}
+
+/// Rendering of the separators between columns
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Separator {
+    Line,
+    Space,
+    Dotted,
+    None,
+}
+
+impl Separator {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "line" => Ok(Self::Line),
+            "space" => Ok(Self::Space),
+            "dotted" => Ok(Self::Dotted),
+            "none" => Ok(Self::None),
+            _ => Err(format!(
+                "invalid separator '{s}', expected line, space, dotted or none"
+            )),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Separator::Line => "│",
+            Separator::Space => " ",
+            Separator::Dotted => "┊",
+            Separator::None => "",
+        }
+    }
+
+    /// Crossing of the separator with the header rule
+    fn junction(self) -> &'static str {
+        match self {
+            Separator::Line => "┼",
+            Separator::Space | Separator::Dotted => "─",
+            Separator::None => "",
+        }
+    }
+}
+
+/// Settings of the tabs and their grids, from the explorer options
+#[derive(Clone, Copy, Default)]
+pub struct Settings {
+    /// Maximum width of fitted columns
+    pub max_width: MaxWidth,
+    /// Colors of the grid backgrounds
+    pub theme: Theme,
+    /// Separators between columns and under the header
+    pub borders: Borders,
+    /// Screen reader friendly rendering, without decorative glyphs and
+    /// animations and with the current cell announced on its own line
+    pub accessible: bool,
+    /// Append the applied queries to the history file
+    pub history: bool,
+}
+
+impl Settings {
+    pub fn glyphs(&self) -> &'static Glyphs {
+        if self.accessible {
+            &style::ACCESSIBLE
+        } else {
+            &style::UNICODE
+        }
+    }
+}
+
+/// Lines drawn around the cells
+#[derive(Clone, Copy)]
+pub struct Borders {
+    pub separator: Separator,
+    /// Draw a horizontal rule under the header
+    pub header_rule: bool,
+}
+
+impl Default for Borders {
+    fn default() -> Self {
+        Self {
+            separator: Separator::Line,
+            header_rule: false,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Grid {
     projection: Projection,
     pub nav: Nav,
     sizer: Sizer,
+    pub selection: Selection,
+    pinned: Selection,
+    pub sort: Sort,
     state: State,
+    /// Frame before the last refresh, to highlight changes
+    previous: Option<(DataFrame, Instant)>,
+    pub tall: Tall,
+    /// Shade the cursor row and column
+    crosshair: bool,
+    /// Shade every other row
+    zebra: bool,
+    settings: Settings,
+    /// Layout of the last drawn screen
+    screen: Screen,
+}
+
+/// Visible columns and their sizes, shared by the grids of the same schema
+#[derive(Clone, PartialEq)]
+pub struct Layout {
+    projection: Projection,
+    sizer: Sizer,
+}
+
+/// Rows and columns, with their names and widths, visible on screen
+#[derive(Clone, Default)]
+struct Screen {
+    rows: Range<usize>,
+    cols: Vec<(usize, String, usize)>,
 }
 
 impl Grid {
-    pub fn new() -> Self {
+    pub fn new(settings: Settings) -> Self {
         Self {
             projection: Projection::new(),
             nav: Nav::new(),
-            sizer: Sizer::new(),
+            sizer: Sizer::new(settings.max_width),
+            selection: Selection::new(),
+            pinned: Selection::new(),
+            sort: Sort::new(),
             state: State::Normal,
+            previous: None,
+            tall: Tall::Off,
+            crosshair: false,
+            zebra: settings.theme.zebra,
+            settings,
+            screen: Screen::default(),
+        }
+    }
+
+    /// Cycle the tall rows mode
+    fn toggle_tall(&mut self) {
+        self.tall = match self.tall {
+            Tall::Auto => Tall::On,
+            Tall::On => Tall::Off,
+            Tall::Off => Tall::Auto,
+        }
+    }
+
+    /// Number of lines of each row
+    fn row_height(&self, width: usize) -> usize {
+        let tall = match self.tall {
+            Tall::On => true,
+            Tall::Off => false,
+            Tall::Auto => {
+                // Few columns whose content observed so far do not fit
+                let cols = self.projection.cols();
+                cols.len() <= TALL_MAX_COLS
+                    && cols
+                        .iter()
+                        .map(|idx| self.sizer.content(*idx) + 1)
+                        .sum::<usize>()
+                        > width
+            }
+        };
+        if tall {
+            TALL_LINES
+        } else {
+            1
+        }
+    }
+
+    /// Adapt to a reloaded frame sorted by `sort`, keeping the layout if its
+    /// schema is unchanged
+    pub fn reload(&mut self, same_schema: bool, sort: &[(String, bool)]) {
+        if same_schema {
+            self.nav.top();
+            self.selection.clear();
+            self.pinned.clear();
+        } else {
+            *self = Self::new(self.settings)
         }
+        self.sort_by(sort);
+    }
+
+    /// Show the sort keys applied to the frame
+    pub fn sort_by(&mut self, sort: &[(String, bool)]) {
+        self.sort = Sort::from_keys(sort.to_vec());
+    }
+
+    /// Highlight cells differing from the previous frame for a while
+    pub fn highlight_changes(&mut self, previous: DataFrame) {
+        self.previous = Some((previous, Instant::now()));
+    }
+
+    /// Time until changed cells stop being highlighted
+    pub fn highlight_remaining(&self) -> Option<Duration> {
+        self.previous
+            .as_ref()
+            .map(|(_, start)| HIGHLIGHT.saturating_sub(start.elapsed()))
     }
 
     pub fn on_key(&mut self, event: &KeyEvent) -> OnKey {
         let shift = event.modifiers.contains(KeyModifiers::SHIFT);
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
         let idx = self.nav.c_col();
         let proj_idx = self.projection.project(idx);
         match self.state {
@@ -59,6 +274,19 @@ impl Grid {
                 Key::Down | Key::Char('j') => self.nav.down(),
                 Key::Up | Key::Char('k') => self.nav.up(),
                 Key::Right | Key::Char('l') => self.nav.right(),
+                Key::PageUp => self.nav.win_up(),
+                Key::PageDown => self.nav.win_down(),
+                Key::Home if ctrl => self.nav.top(),
+                Key::End if ctrl => self.nav.btm(),
+                Key::Home => self.nav.start(),
+                Key::End => self.nav.end(),
+                Key::Char(' ') => self.selection.toggle(self.nav.c_row()),
+                Key::Char('u') => self.selection.clear(),
+                Key::Char('P') => self.pinned.toggle(self.nav.c_row()),
+                Key::Char('G') => self.nav.btm(),
+                Key::Char('t') => self.toggle_tall(),
+                Key::Char('z') => self.crosshair = !self.crosshair,
+                Key::Char('Z') => self.zebra = !self.zebra,
                 Key::Char('q') => return OnKey::Quit,
                 _ => return OnKey::Pass,
             },
@@ -110,6 +338,12 @@ impl Grid {
                 Key::Right | Key::Char('l') => {
                     self.sizer.cmd(proj_idx, sizer::Cmd::More);
                 }
+                Key::Char('<') => {
+                    self.sizer.cmd(proj_idx, sizer::Cmd::Narrower);
+                }
+                Key::Char('>') => {
+                    self.sizer.cmd(proj_idx, sizer::Cmd::Wider);
+                }
                 _ => {}
             },
         };
@@ -117,19 +351,123 @@ impl Grid {
         OnKey::Continue
     }
 
+    /// Move the cursor to a cell using its frame column index
+    pub fn focus(&mut self, row: usize, idx: usize) {
+        let col = self.projection.offset(idx).unwrap_or(self.nav.c_col());
+        self.nav.go_to((row, col));
+    }
+
+    /// Rename the column under the cursor
+    pub fn rename(&mut self, name: String) {
+        let idx = self.c_idx();
+        self.projection.rename(idx, name);
+    }
+
+    /// Apply column renames to a batch of the frame
+    pub fn rename_batch(&self, batch: RecordBatch) -> RecordBatch {
+        let schema = batch.schema();
+        let fields: Vec<_> = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(idx, f)| match self.projection.alias(idx) {
+                Some(alias) => Arc::new(Field::clone(f).with_name(alias)),
+                None => f.clone(),
+            })
+            .collect();
+        let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+        RecordBatch::try_new(schema, batch.columns().to_vec()).unwrap()
+    }
+
+    /// Frame index of the visible columns
+    pub fn projected(&self) -> &[usize] {
+        self.projection.cols()
+    }
+
+    pub fn layout(&self) -> Layout {
+        Layout {
+            projection: self.projection.clone(),
+            sizer: self.sizer.clone(),
+        }
+    }
+
+    /// Use the projection and column sizes of another grid, unless they are
+    /// already the same
+    pub fn set_layout(&mut self, layout: &Layout) {
+        if self.projection != layout.projection {
+            self.projection = layout.projection.clone();
+        }
+        if self.sizer != layout.sizer {
+            self.sizer = layout.sizer.clone();
+        }
+    }
+
+    /// SQL select list of the visible columns and their aliases
+    pub fn select_list(&self, df: &dyn Frame) -> String {
+        let cols = self.projection.cols();
+        if cols.iter().copied().eq(0..df.nb_col())
+            && cols.iter().all(|idx| self.projection.alias(*idx).is_none())
+        {
+            return "*".into();
+        }
+        cols.iter()
+            .map(|idx| {
+                let name = sql::ident(&df.col_name(*idx));
+                match self.projection.alias(*idx) {
+                    Some(alias) => format!("{name} AS {}", sql::ident(alias)),
+                    None => name,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Frame index of the column under the cursor
+    pub fn c_idx(&self) -> usize {
+        self.projection.project(self.nav.c_col())
+    }
+
     pub fn draw(&mut self, c: &mut Canvas, buf: &mut GridBuffer, df: &dyn Frame) -> GridUI {
         let nb_col = df.nb_col();
         let nb_row = df.nb_row();
         self.projection.set_nb_cols(nb_col);
         let visible_cols = self.projection.nb_cols();
+        let glyphs = self.settings.glyphs();
 
-        let v_row = c.height() - 1; // header bar
+        // Pinned rows, keeping at least one scrolling row
+        let pinned: Vec<usize> = self
+            .pinned
+            .rows()
+            .filter(|r| *r < nb_row)
+            .take(c.height().saturating_sub(2))
+            .collect();
+        let height = self.row_height(c.width());
+        let Borders {
+            separator,
+            header_rule,
+        } = self.settings.borders;
+        let sep = separator.as_str();
+        let header_height = 1 + header_rule as usize;
+        // header bar and pinned rows
+        let v_row = (c.height().saturating_sub(header_height + pinned.len()) / height).max(1);
         let row_off = self.nav.row_offset(nb_row, v_row);
         // Nb call necessary to print the biggest index
         let mut ids_col = df.idx_iter(buf, row_off, v_row);
         ids_col.align_right();
+        let pinned_ids: Vec<_> = pinned
+            .iter()
+            .map(|row| {
+                let mut col = df.idx_iter(buf, *row, 1);
+                col.align_right();
+                col
+            })
+            .collect();
+        let ids_budget = pinned_ids
+            .iter()
+            .map(Col::budget)
+            .fold(ids_col.budget(), usize::max);
         // Whole canvas minus index col
-        let mut remaining_width = c.width() - ids_col.budget() - 1;
+        let mut remaining_width = c.width() - ids_budget - 1;
         let mut cols = Vec::new();
         let mut coll_off_iter = self.nav.col_iter(visible_cols);
         // Fill canvas with columns
@@ -137,18 +475,34 @@ impl Grid {
             if let Some(off) = coll_off_iter.next() {
                 let idx = self.projection.project(off);
                 let name = df.col_name(idx);
+                let sort = self.sort.get(&name);
+                let name = match self.projection.alias(idx) {
+                    Some(alias) => alias.to_string(),
+                    None => name,
+                };
+                let name = match sort {
+                    Some((pos, desc)) => {
+                        let glyph = if desc { glyphs.desc } else { glyphs.asc };
+                        format!("{name}{glyph}{}", pos + 1)
+                    }
+                    None => name,
+                };
                 let col = df.col_iter(buf, idx, row_off, v_row);
-                let size = self.sizer.fit(idx, col.budget(), name.width());
+                let size = self.sizer.fit(idx, col.budget(), name.width(), col.kind());
                 let allowed = size.min(remaining_width);
-                cols.push((off, name, col, allowed));
-                let separator = if cols.len() == nb_col { 0 } else { 1 }; // Skip last separator
-                remaining_width = remaining_width.saturating_sub(allowed + separator);
+                let pins: Vec<_> = pinned
+                    .iter()
+                    .map(|row| df.col_iter(buf, idx, *row, 1))
+                    .collect();
+                cols.push((off, name, col, pins, allowed));
+                let sep_width = if cols.len() == nb_col { 0 } else { sep.width() }; // Skip last separator
+                remaining_width = remaining_width.saturating_sub(allowed + sep_width);
             } else {
                 break;
             }
         }
         // Redistribute remaining width
-        for (off, _, _, allowed) in &mut cols {
+        for (off, _, _, _, allowed) in &mut cols {
             if remaining_width == 0 {
                 break;
             }
@@ -156,18 +510,32 @@ impl Grid {
             *allowed = self.sizer.fill(idx, &mut remaining_width);
         }
 
-        cols.sort_unstable_by_key(|(i, _, _, _)| *i);
+        cols.sort_unstable_by_key(|(i, _, _, _, _)| *i);
         drop(coll_off_iter);
+        self.screen = Screen {
+            rows: row_off..row_off + v_row.min(nb_row - row_off),
+            cols: cols
+                .iter()
+                .map(|(off, _, _, _, budget)| {
+                    let idx = self.projection.project(*off);
+                    let name = match self.projection.alias(idx) {
+                        Some(alias) => alias.to_string(),
+                        None => df.col_name(idx),
+                    };
+                    (idx, name, *budget)
+                })
+                .collect(),
+        };
 
         // Draw headers
         {
             let line = &mut c.top();
             line.draw(
-                format_args!("{:>1$} ", '#', ids_col.budget()),
+                format_args!("{:>1$} ", '#', ids_budget),
                 style::index().bold(),
             );
 
-            for (off, name, _, budget) in &cols {
+            for (off, name, _, _, budget) in &cols {
                 let style = if *off == self.nav.c_col() {
                     style::selected().bold()
                 } else {
@@ -177,36 +545,225 @@ impl Grid {
                     format_args!("{:<1$}", rtrim(name, buf.fmt_buf(), *budget), budget),
                     style,
                 );
-                line.draw("│", style::separator());
+                line.draw(sep, style::separator());
             }
         }
 
-        // Draw rows
-        for r in 0..v_row.min(nb_row - row_off) {
+        if header_rule {
             let line = &mut c.top();
             line.draw(
-                format_args!("{} ", ids_col.fmt(buf, r, ids_col.budget())),
-                style::index(),
+                format_args!("{:─<1$}", "", ids_budget + 1),
+                style::separator(),
             );
-            for (_, _, col, budget) in &cols {
-                line.draw(
-                    format_args!("{}", col.fmt(buf, r, *budget)),
-                    style::primary(),
-                );
-                line.draw("│", style::separator());
+            for (i, (_, _, _, _, budget)) in cols.iter().enumerate() {
+                line.draw(format_args!("{:─<1$}", "", budget), style::separator());
+                if i + 1 < cols.len() {
+                    line.draw(separator.junction(), style::separator());
+                }
+            }
+        }
+
+        // Draw pinned rows
+        for (i, ids) in pinned_ids.iter().enumerate() {
+            let line = &mut c.top();
+            line.draw(
+                format_args!("{}{}", ids.fmt(buf, 0, ids_budget), glyphs.pinned),
+                style::index().bold(),
+            );
+            for (_, _, _, pins, budget) in &cols {
+                let (cell, truncated) = pins[i].fmt_cell(buf, 0, *budget);
+                let (cell, marker) = split_marker(cell, truncated, glyphs);
+                line.draw(cell, style::primary().bold());
+                line.draw(marker, style::truncated());
+                line.draw(sep, style::separator());
+            }
+        }
+
+        // Draw rows
+        if self.highlight_remaining() == Some(Duration::ZERO) {
+            self.previous = None;
+        }
+        let nb_visible = v_row.min(nb_row - row_off);
+        // Full values of the visible cells, formatted once per column for
+        // wrapped text and changed cells
+        let cells: Vec<_> = cols
+            .iter()
+            .map(|(off, _, col, _, _)| {
+                let wrap = height > 1 && col.kind() == ColKind::Text;
+                if !wrap && self.previous.is_none() {
+                    return (None, vec![]);
+                }
+                let idx = self.projection.project(*off);
+                let values = df.values(idx, row_off, nb_visible);
+                let changed = match &self.previous {
+                    Some((prev, _)) => {
+                        let old = Frame::values(prev, idx, row_off, nb_visible);
+                        (0..values.len())
+                            .map(|r| old.get(r) != Some(&values[r]))
+                            .collect()
+                    }
+                    None => vec![],
+                };
+                (wrap.then_some(values), changed)
+            })
+            .collect();
+        let theme = &self.settings.theme;
+        let mut nb_truncated = 0;
+        for r in 0..nb_visible {
+            let row = row_off + r;
+            let on_row = self.crosshair && row == self.nav.c_row();
+            let stripe = self.zebra && row % 2 == 1;
+            let (mark, style) = if self.selection.contains(row) {
+                (glyphs.marked, style::marked())
+            } else {
+                (" ", style::index())
+            };
+            let style = shade(theme, style, on_row, stripe);
+            // Text cells wrapped on several lines in tall mode
+            let wrapped: Vec<Option<(Vec<&str>, bool)>> = cols
+                .iter()
+                .zip(&cells)
+                .map(|((_, _, _, _, budget), (values, _))| {
+                    let value = values.as_ref()?.get(r)?.as_ref()?;
+                    let mut lines = wrap(value, *budget, height + 1);
+                    let truncated = lines.len() > height;
+                    lines.truncate(height);
+                    Some((lines, truncated))
+                })
+                .collect();
+            for l in 0..height {
+                let line = &mut c.top();
+                if l == 0 {
+                    line.draw(
+                        format_args!("{}{mark}", ids_col.fmt(buf, r, ids_budget)),
+                        style,
+                    );
+                } else {
+                    line.draw(format_args!("{:1$} ", "", ids_budget), style);
+                }
+                for (((off, _, col, _, budget), wrapped), (_, changed)) in
+                    cols.iter().zip(&wrapped).zip(&cells)
+                {
+                    let style = if changed.get(r) == Some(&true) {
+                        style::changed()
+                    } else {
+                        style::primary()
+                    };
+                    let on_cross = on_row || (self.crosshair && *off == self.nav.c_col());
+                    let style = shade(theme, style, on_cross, stripe);
+                    let (marker_style, separator_style) = (
+                        shade(theme, style::truncated(), on_cross, stripe),
+                        shade(theme, style::separator(), on_cross, stripe),
+                    );
+                    let (cell, truncated) = match wrapped {
+                        Some((lines, truncated)) => {
+                            let text = lines.get(l).copied().unwrap_or("");
+                            let last = *truncated && l + 1 == height;
+                            let budget = if last {
+                                budget.saturating_sub(1)
+                            } else {
+                                *budget
+                            };
+                            let cell = rtrim(text, buf.fmt_buf(), budget);
+                            line.draw(format_args!("{cell:<0$}", budget), style);
+                            line.draw(if last { glyphs.truncated } else { "" }, marker_style);
+                            line.draw(sep, separator_style);
+                            if last && *off == self.nav.c_col() {
+                                nb_truncated += 1;
+                            }
+                            continue;
+                        }
+                        None if l == 0 => col.fmt_cell(buf, r, *budget),
+                        None => {
+                            line.draw(format_args!("{:1$}", "", budget), style);
+                            line.draw(sep, separator_style);
+                            continue;
+                        }
+                    };
+                    if truncated && *off == self.nav.c_col() {
+                        nb_truncated += 1;
+                    }
+                    let (cell, marker) = split_marker(cell, truncated, glyphs);
+                    line.draw(cell, style);
+                    line.draw(marker, marker_style);
+                    line.draw(sep, separator_style);
+                }
             }
         }
 
         GridUI {
-            col_name: (self.projection.nb_cols() > 0)
-                .then(|| df.col_name(self.projection.project(self.nav.c_col()))),
+            col_name: (self.projection.nb_cols() > 0).then(|| {
+                let idx = self.c_idx();
+                match self.projection.alias(idx) {
+                    Some(alias) => alias.to_string(),
+                    None => df.col_name(idx),
+                }
+            }),
+            selected: self.selection.len(),
+            truncated: nb_truncated,
             status: match self.state {
                 State::Normal => Status::Normal,
                 State::Size => Status::Size,
                 State::Projection => Status::Projection,
             },
+            cols: ColsView {
+                first: cols.first().map_or(0, |c| c.0),
+                last: cols.last().map_or(0, |c| c.0),
+                total: visible_cols,
+                hidden: nb_col - visible_cols,
+            },
         }
     }
+
+    /// Format the rows and columns visible on screen as a Markdown table
+    pub fn markdown(&self, df: &dyn Frame) -> String {
+        let Screen { rows, cols } = &self.screen;
+        let mut buf = GridBuffer::new();
+        buf.new_frame(cols.iter().map(|(_, _, width)| *width).max().unwrap_or(0));
+        let mut lines = vec![String::from("|"); rows.len() + 2];
+        for (idx, name, width) in cols {
+            // A delimiter cell needs at least three chars
+            let width = (*width).max(3);
+            let col = df.col_iter(&mut buf, *idx, rows.start, rows.len());
+            let header = rtrim(name, buf.fmt_buf(), width);
+            write!(lines[0], " {:<1$} |", escape_pipe(header), width).unwrap();
+            let delimiter = if col.kind() == ColKind::Number {
+                format!("{}:", "-".repeat(width - 1))
+            } else {
+                "-".repeat(width)
+            };
+            write!(lines[1], " {delimiter} |").unwrap();
+            for (r, line) in lines[2..].iter_mut().enumerate() {
+                let (cell, _) = col.fmt_cell(&mut buf, r, width);
+                write!(line, " {} |", escape_pipe(cell)).unwrap();
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+/// Escape pipes that would otherwise end a Markdown table cell
+fn escape_pipe(str: &str) -> String {
+    str.replace('|', "\\|")
+}
+
+/// Background of a cell, the crosshair wins over stripes
+fn shade(theme: &Theme, style: Style, on_cross: bool, stripe: bool) -> Style {
+    if on_cross {
+        theme.crosshair(style)
+    } else if stripe {
+        theme.stripe(style)
+    } else {
+        style
+    }
+}
+
+/// Split the truncation marker from a cell to style it apart from the data
+fn split_marker<'a>(cell: &'a str, truncated: bool, glyphs: &Glyphs) -> (&'a str, &'static str) {
+    match cell.strip_suffix('…').filter(|_| truncated) {
+        Some(head) => (head, glyphs.truncated),
+        None => (cell, ""),
+    }
 }
 
 pub trait Frame {
@@ -215,6 +772,22 @@ pub trait Frame {
     fn idx_iter(&self, buf: &mut GridBuffer, skip: usize, take: usize) -> Col;
     fn col_name(&self, idx: usize) -> String;
     fn col_iter(&self, buf: &mut GridBuffer, idx: usize, skip: usize, take: usize) -> Col;
+    /// Full formatted value of a cell, None if null
+    fn value(&self, row: usize, idx: usize) -> Option<String>;
+    /// Full formatted values of a column over consecutive rows, None if null
+    fn values(&self, idx: usize, skip: usize, take: usize) -> Vec<Option<String>> {
+        (skip..(skip + take).min(self.nb_row()))
+            .map(|row| self.value(row, idx))
+            .collect()
+    }
+    /// Raw bytes of a binary cell
+    fn bytes(&self, _row: usize, _idx: usize) -> Option<Vec<u8>> {
+        None
+    }
+    /// Data type and unformatted value of a cell
+    fn raw(&self, _row: usize, _idx: usize) -> Option<(DataType, String)> {
+        None
+    }
 }
 
 impl Frame for DataFrame {
@@ -241,4 +814,20 @@ impl Frame for DataFrame {
     fn col_iter(&self, buf: &mut GridBuffer, idx: usize, skip: usize, take: usize) -> Col {
         self.iter(buf, idx, skip, take)
     }
+
+    fn value(&self, row: usize, idx: usize) -> Option<String> {
+        DataFrame::value(self, row, idx)
+    }
+
+    fn values(&self, idx: usize, skip: usize, take: usize) -> Vec<Option<String>> {
+        DataFrame::values(self, idx, skip, take)
+    }
+
+    fn bytes(&self, row: usize, idx: usize) -> Option<Vec<u8>> {
+        DataFrame::bytes(self, row, idx)
+    }
+
+    fn raw(&self, row: usize, idx: usize) -> Option<(DataType, String)> {
+        DataFrame::raw(self, row, idx)
+    }
 }
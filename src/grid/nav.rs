@@ -13,6 +13,9 @@ pub struct Nav {
     // View dimensions
     v_row: usize,
     v_col: usize,
+    // Cursor row at the last prefetch and rows to load before the next one
+    p_row: usize,
+    p_goal: usize,
 }
 
 impl Nav {
@@ -26,6 +29,8 @@ impl Nav {
             m_col: 0,
             v_row: 0,
             v_col: 0,
+            p_row: 0,
+            p_goal: 0,
         }
     }
 
@@ -33,10 +38,32 @@ impl Nav {
         self.c_col
     }
 
+    /// Row under the cursor
+    pub fn c_row(&self) -> usize {
+        self.c_row.min(self.m_row)
+    }
+
     pub fn goal(&self) -> usize {
         self.c_row.saturating_add(self.v_row + 1)
     }
 
+    /// Goal extended by `margin` viewports when scrolling down, more when scrolling fast,
+    /// kept until `loaded` rows reach it
+    pub fn prefetch_goal(&mut self, margin: usize, loaded: usize) -> usize {
+        let moved = self.c_row.saturating_sub(self.p_row);
+        self.p_row = self.c_row;
+        if moved > 0 {
+            let goal = self
+                .goal()
+                .saturating_add(self.v_row.max(moved).saturating_mul(margin));
+            self.p_goal = self.p_goal.max(goal);
+        }
+        if loaded >= self.p_goal {
+            self.p_goal = 0;
+        }
+        self.goal().max(self.p_goal)
+    }
+
     pub fn up(&mut self) {
         self.o_row = self.o_row.saturating_sub(1);
         self.c_row = self.o_row;
@@ -74,6 +101,8 @@ impl Nav {
     pub fn top(&mut self) {
         self.o_row = 0;
         self.c_row = self.o_row;
+        self.p_row = 0;
+        self.p_goal = 0;
     }
 
     pub fn btm(&mut self) {
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 #[derive(Clone, Copy)]
 pub enum Cmd {
     Hide,
@@ -6,10 +8,11 @@ pub enum Cmd {
 }
 
 /// Column projection
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Projection {
     cols: Vec<usize>,
     nb_col: usize,
+    aliases: BTreeMap<usize, String>,
 }
 
 impl Projection {
@@ -17,12 +20,14 @@ impl Projection {
         Self {
             cols: vec![],
             nb_col: 0,
+            aliases: BTreeMap::new(),
         }
     }
 
     /// Sync the number of columns
     pub fn set_nb_cols(&mut self, nb_col: usize) {
         self.cols.retain(|n| *n < nb_col);
+        self.aliases.retain(|n, _| *n < nb_col);
         self.cols.extend(self.nb_col..nb_col);
         self.nb_col = nb_col;
     }
@@ -32,11 +37,35 @@ impl Projection {
         self.cols.len()
     }
 
+    /// Visible column idx in display order
+    pub fn cols(&self) -> &[usize] {
+        &self.cols
+    }
+
     /// Get the column idx at this offset
     pub fn project(&self, off: usize) -> usize {
         *self.cols.get(off).unwrap_or(&0)
     }
 
+    /// Get the offset of this column idx if visible
+    pub fn offset(&self, idx: usize) -> Option<usize> {
+        self.cols.iter().position(|n| *n == idx)
+    }
+
+    /// Display name of this column idx if renamed
+    pub fn alias(&self, idx: usize) -> Option<&str> {
+        self.aliases.get(&idx).map(String::as_str)
+    }
+
+    /// Rename this column idx, an empty name restores the original one
+    pub fn rename(&mut self, idx: usize, name: String) {
+        if name.is_empty() {
+            self.aliases.remove(&idx);
+        } else {
+            self.aliases.insert(idx, name);
+        }
+    }
+
     /// Apply command
     pub fn cmd(&mut self, off: usize, cmd: Cmd) {
         if self.cols.is_empty() {
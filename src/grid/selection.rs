@@ -0,0 +1,50 @@
+use std::collections::BTreeSet;
+
+/// Set of marked rows
+#[derive(Clone)]
+pub struct Selection {
+    rows: BTreeSet<usize>,
+    /// Number of changes, to know when derived values are outdated
+    changes: usize,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self {
+            rows: BTreeSet::new(),
+            changes: 0,
+        }
+    }
+
+    /// Mark or unmark a row
+    pub fn toggle(&mut self, row: usize) {
+        if !self.rows.remove(&row) {
+            self.rows.insert(row);
+        }
+        self.changes += 1;
+    }
+
+    pub fn contains(&self, row: usize) -> bool {
+        self.rows.contains(&row)
+    }
+
+    /// Number of marked rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Marked rows in ascending order
+    pub fn rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.rows.iter().copied()
+    }
+
+    /// Unmark all rows
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.changes += 1;
+    }
+
+    pub fn changes(&self) -> usize {
+        self.changes
+    }
+}
@@ -1,43 +1,152 @@
+use serde::Deserialize;
+
+use crate::fmt::ColKind;
+
 #[derive(Clone, Copy)]
 pub enum Cmd {
     Constrain,
     Free,
     Less,
     More,
+    /// Shrink the maximum width of the columns of this kind
+    Narrower,
+    /// Grow the maximum width of the columns of this kind
+    Wider,
 }
 
-#[derive(Clone, Copy)]
+/// Maximum width of fitted columns per kind of content
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(from = "Width")]
+pub struct MaxWidth {
+    pub text: usize,
+    pub number: usize,
+    pub other: usize,
+}
+
+impl MaxWidth {
+    pub const fn new(max: usize) -> Self {
+        Self {
+            text: max,
+            number: max,
+            other: max,
+        }
+    }
+
+    /// Parse `N` for all kinds or a list like `text=40,number=15`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let nb = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid width '{s}'"))
+        };
+        if !s.contains('=') {
+            return Ok(Self::new(nb(s)?));
+        }
+        let mut max = Self::default();
+        for part in s.split(',') {
+            let (kind, width) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected kind=width got '{part}'"))?;
+            match kind.trim() {
+                "text" => max.text = nb(width)?,
+                "number" => max.number = nb(width)?,
+                "other" => max.other = nb(width)?,
+                kind => return Err(format!("unknown column kind '{kind}'")),
+            }
+        }
+        Ok(max)
+    }
+
+    fn get(&self, kind: ColKind) -> usize {
+        match kind {
+            ColKind::Text => self.text,
+            ColKind::Number => self.number,
+            ColKind::Other => self.other,
+        }
+    }
+
+    fn get_mut(&mut self, kind: ColKind) -> &mut usize {
+        match kind {
+            ColKind::Text => &mut self.text,
+            ColKind::Number => &mut self.number,
+            ColKind::Other => &mut self.other,
+        }
+    }
+}
+
+impl Default for MaxWidth {
+    fn default() -> Self {
+        Self::new(25)
+    }
+}
+
+/// Maximum width in the config file, `30` or `{ text = 40, number = 15 }`
+#[derive(Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+enum Width {
+    All(usize),
+    Kinds {
+        text: Option<usize>,
+        number: Option<usize>,
+        other: Option<usize>,
+    },
+}
+
+impl From<Width> for MaxWidth {
+    fn from(width: Width) -> Self {
+        match width {
+            Width::All(max) => Self::new(max),
+            Width::Kinds {
+                text,
+                number,
+                other,
+            } => {
+                let default = Self::default();
+                Self {
+                    text: text.unwrap_or(default.text),
+                    number: number.unwrap_or(default.number),
+                    other: other.unwrap_or(default.other),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
 enum Constraint {
     Fit,
     Fill,
     Fixe(usize),
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 struct SizeStat {
     content: usize,
     header: usize,
     size: usize,
+    kind: ColKind,
 }
 
 /// Size column based on previous observed length and constraints
 /// Prevent column size from flickering on scroll and use all available space
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Sizer {
     cols: Vec<(SizeStat, Constraint)>,
     fit_content: bool,
+    max: MaxWidth,
 }
 
 impl Sizer {
-    pub fn new() -> Self {
+    pub fn new(max: MaxWidth) -> Self {
         Self {
             cols: vec![],
             fit_content: false,
+            max,
         }
     }
 
     /// Size a column taking minimal amount of space
-    pub fn fit(&mut self, idx: usize, len: usize, header_len: usize) -> usize {
+    pub fn fit(&mut self, idx: usize, len: usize, header_len: usize, kind: ColKind) -> usize {
         // Ensure we store info for this column
         if idx >= self.cols.len() {
             self.cols.resize(
@@ -47,6 +156,7 @@ impl Sizer {
                         content: 0,
                         header: 0,
                         size: 0,
+                        kind,
                     },
                     Constraint::Fit,
                 ),
@@ -55,12 +165,18 @@ impl Sizer {
         // Sync max len
         self.cols[idx].0.content = self.cols[idx].0.content.max(len);
         self.cols[idx].0.header = self.cols[idx].0.header.max(header_len);
+        self.cols[idx].0.kind = kind;
 
         let size = self.get_size(idx, false);
         self.cols[idx].0.size = size;
         size
     }
 
+    /// Observed content width of a column
+    pub fn content(&self, idx: usize) -> usize {
+        self.cols.get(idx).map_or(0, |(stat, _)| stat.content)
+    }
+
     /// Size a column taking maximal amount of space
     pub fn fill(&mut self, idx: usize, available: &mut usize) -> usize {
         let fill_size = self.get_size(idx, true);
@@ -77,7 +193,11 @@ impl Sizer {
     /// Size the column based on its constraint
     fn get_size(&self, idx: usize, fill: bool) -> usize {
         let (stat, constraint) = self.cols[idx];
-        let max = if fill { usize::MAX } else { 25 };
+        let max = if fill {
+            usize::MAX
+        } else {
+            self.max.get(stat.kind)
+        };
         match constraint {
             Constraint::Fit if self.fit_content => stat.content.min(max),
             Constraint::Fit => stat.header.max(stat.content).min(max),
@@ -99,12 +219,18 @@ impl Sizer {
             return;
         }
         let stat = self.cols[idx].0;
-        self.cols[idx].1 = match cmd {
-            Cmd::Constrain => Constraint::Fit,
-            Cmd::Free => Constraint::Fill,
-            Cmd::Less => Constraint::Fixe(stat.size.saturating_sub(1).max(self.min_size(idx))),
-            Cmd::More => Constraint::Fixe(stat.size.saturating_add(1).min(stat.content)),
-        };
+        let min_size = self.min_size(idx);
+        let (max, constraint) = (self.max.get_mut(stat.kind), &mut self.cols[idx].1);
+        match cmd {
+            Cmd::Constrain => *constraint = Constraint::Fit,
+            Cmd::Free => *constraint = Constraint::Fill,
+            Cmd::Less => *constraint = Constraint::Fixe(stat.size.saturating_sub(1).max(min_size)),
+            Cmd::More => {
+                *constraint = Constraint::Fixe(stat.size.saturating_add(1).min(stat.content))
+            }
+            Cmd::Narrower => *max = max.saturating_sub(1).max(1),
+            Cmd::Wider => *max = max.saturating_add(1),
+        }
     }
 
     /// Toggle constrain priority
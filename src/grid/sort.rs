@@ -0,0 +1,51 @@
+/// Column sort keys by priority
+#[derive(Clone)]
+pub struct Sort {
+    keys: Vec<(String, bool)>,
+}
+
+impl Sort {
+    pub fn new() -> Self {
+        Self { keys: vec![] }
+    }
+
+    pub fn from_keys(keys: Vec<(String, bool)>) -> Self {
+        Self { keys }
+    }
+
+    /// Cycle a column between ascending, descending and unsorted as the only key
+    pub fn toggle(&mut self, name: &str) {
+        let next = match self.get(name) {
+            None => Some(false),
+            Some((_, false)) => Some(true),
+            Some((_, true)) => None,
+        };
+        self.keys.clear();
+        if let Some(desc) = next {
+            self.keys.push((name.into(), desc));
+        }
+    }
+
+    /// Cycle a column between ascending, descending and unsorted keeping other keys
+    pub fn cycle(&mut self, name: &str) {
+        match self.get(name) {
+            None => self.keys.push((name.into(), false)),
+            Some((pos, false)) => self.keys[pos].1 = true,
+            Some((pos, true)) => {
+                self.keys.remove(pos);
+            }
+        }
+    }
+
+    /// Priority and descending flag of a column
+    pub fn get(&self, name: &str) -> Option<(usize, bool)> {
+        self.keys
+            .iter()
+            .position(|(n, _)| n == name)
+            .map(|pos| (pos, self.keys[pos].1))
+    }
+
+    pub fn keys(&self) -> &[(String, bool)] {
+        &self.keys
+    }
+}
@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use crate::{
+    error::Result,
+    fmt::{Col, GridBuffer},
+    grid::{Frame, Grid, Settings},
+    source::{DataFrame, Source},
+    sql,
+    task::{DuckTask, Runner},
+    view::{View, ViewState},
+};
+
+/// Maximum number of groups listed
+const LIMIT: usize = 1000;
+/// Leading columns used to order and identify rows, hidden from the grid
+const HIDDEN: usize = 3;
+const MEMBER: usize = 0;
+const KEY: usize = 1;
+
+/// Rows nested under the distinct values of a column, each group header
+/// showing its row count and the sum of numeric columns
+pub struct GroupView {
+    source: Arc<Source>,
+    name: String,
+    /// Source columns and whether they are numeric
+    columns: Vec<(String, bool)>,
+    /// Keys of the expanded groups, None standing for null
+    expanded: Vec<Option<String>>,
+    task: Option<DuckTask<DataFrame>>,
+    frame: Grouped,
+    error: Option<String>,
+    pub grid: Grid,
+}
+
+impl GroupView {
+    pub fn new(
+        source: Arc<Source>,
+        name: String,
+        columns: Vec<(String, bool)>,
+        runner: &Runner,
+        settings: Settings,
+    ) -> Self {
+        let mut view = Self {
+            source,
+            name,
+            columns,
+            expanded: vec![],
+            task: None,
+            frame: Grouped(DataFrame::empty()),
+            error: None,
+            grid: Grid::new(settings),
+        };
+        view.load(runner);
+        view
+    }
+
+    fn load(&mut self, runner: &Runner) {
+        let (name, columns, expanded) = (
+            self.name.clone(),
+            self.columns.clone(),
+            self.expanded.clone(),
+        );
+        self.task = Some(runner.duckdb(self.source.clone(), move |source, con| {
+            let sql = query(&source.load_sql(), &name, &columns, &expanded);
+            con.query(&sql)?
+                .map(|d| d.map_err(|e| e.into()))
+                .collect::<Result<DataFrame>>()
+        }));
+    }
+
+    /// Expand or collapse the group of the current row
+    pub fn toggle(&mut self, runner: &Runner) {
+        let df = &self.frame.0;
+        let row = self.grid.nav.c_row();
+        if row >= df.num_rows() {
+            return;
+        }
+        let key = df.value(row, KEY);
+        // Collapsing from a member row moves the cursor back to its header
+        if df.value(row, MEMBER).as_deref() == Some("1") {
+            let header = (0..row)
+                .rev()
+                .find(|r| df.value(*r, MEMBER).as_deref() == Some("0"));
+            self.grid
+                .nav
+                .go_to((header.unwrap_or(0), self.grid.nav.c_col()));
+        }
+        match self.expanded.iter().position(|k| *k == key) {
+            Some(pos) => {
+                self.expanded.remove(pos);
+            }
+            None => self.expanded.push(key),
+        }
+        self.load(runner);
+    }
+}
+
+impl View for GroupView {
+    fn tick(&mut self) -> ViewState {
+        match self.task.as_mut().and_then(|t| t.tick()) {
+            Some(Ok(df)) => {
+                self.frame = Grouped(df);
+                self.task = None;
+            }
+            Some(Err(it)) => {
+                self.error = Some(it.0);
+                self.task = None;
+            }
+            None => {}
+        }
+
+        ViewState {
+            loading: self.task.as_ref().map(|t| ("group".into(), t.progress())),
+            streaming: false,
+            frame: &self.frame,
+            grid: &mut self.grid,
+            err: self.error.as_deref(),
+        }
+    }
+}
+
+/// Query listing group headers followed by the rows of expanded groups
+fn query(
+    load_sql: &str,
+    name: &str,
+    columns: &[(String, bool)],
+    expanded: &[Option<String>],
+) -> String {
+    let key = sql::ident(name);
+    let label = sql::ident(&format!("group by {name}"));
+    let sums: String = columns
+        .iter()
+        .filter(|(col, numeric)| *numeric && col != name)
+        .map(|(col, _)| format!(", sum({0}) AS {0}", sql::ident(col)))
+        .collect();
+    let all: String = columns
+        .iter()
+        .map(|(col, _)| format!(", {}", sql::ident(col)))
+        .collect();
+    let members = if expanded.is_empty() {
+        "false".into()
+    } else {
+        sql::is_in(name, expanded)
+    };
+    format!(
+        "WITH src AS ({load_sql}) \
+        SELECT __member, __key, __pos, {label}{all} FROM ( \
+            (SELECT 0 AS __member, {key} AS __key, 0 AS __pos, \
+            CASE WHEN {members} THEN '▾ ' ELSE '▸ ' END || coalesce({key}::VARCHAR, 'null') || ' (' || count(*) || ')' AS {label}{sums} \
+            FROM src GROUP BY {key} ORDER BY {key} NULLS FIRST LIMIT {LIMIT}) \
+            UNION ALL BY NAME \
+            SELECT 1 AS __member, {key} AS __key, row_number() OVER () AS __pos, * FROM src WHERE {members} \
+        ) ORDER BY __key NULLS FIRST, __member, __pos"
+    )
+}
+
+/// Grouped rows with their ordering columns hidden
+struct Grouped(DataFrame);
+
+impl Frame for Grouped {
+    fn nb_col(&self) -> usize {
+        self.0.num_columns().saturating_sub(HIDDEN)
+    }
+
+    fn nb_row(&self) -> usize {
+        self.0.num_rows()
+    }
+
+    fn idx_iter(&self, buf: &mut GridBuffer, skip: usize, take: usize) -> Col {
+        Frame::idx_iter(&self.0, buf, skip, take)
+    }
+
+    fn col_name(&self, idx: usize) -> String {
+        self.0.schema().all_fields()[idx + HIDDEN].name().clone()
+    }
+
+    fn col_iter(&self, buf: &mut GridBuffer, idx: usize, skip: usize, take: usize) -> Col {
+        self.0.iter(buf, idx + HIDDEN, skip, take)
+    }
+
+    fn value(&self, row: usize, idx: usize) -> Option<String> {
+        self.0.value(row, idx + HIDDEN)
+    }
+
+    fn values(&self, idx: usize, skip: usize, take: usize) -> Vec<Option<String>> {
+        self.0.values(idx + HIDDEN, skip, take)
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use libduckdb_sys::duckdb_query_progress_type;
+use tui::Canvas;
+
+use crate::{
+    chart::{self, Chart},
+    error::Result,
+    source::{DataFrame, Source},
+    style,
+    task::{DuckTask, Runner},
+};
+
+/// Number of buckets
+const BUCKETS: usize = 32;
+const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Distribution of a numeric column
+pub struct HistogramView {
+    name: String,
+    task: Option<DuckTask<Vec<Vec<Option<f64>>>>>,
+    counts: Vec<f64>,
+    range: (f64, f64),
+    error: Option<String>,
+}
+
+impl HistogramView {
+    /// Bucket a column given as a numeric expression
+    pub fn new(source: Arc<Source>, name: String, expr: String, runner: &Runner) -> Self {
+        Self {
+            task: Some(runner.duckdb(source, move |source, con| {
+                let sql = format!(
+                    "WITH t AS (SELECT {expr} AS v FROM ({})), \
+                    b AS (SELECT min(v) AS lo, max(v) AS hi FROM t) \
+                    SELECT coalesce(least(floor((v - lo) / nullif(hi - lo, 0) * {BUCKETS}), {BUCKETS} - 1), 0)::INTEGER AS bucket, \
+                    count(*) AS count, any_value(lo) AS lo, any_value(hi) AS hi \
+                    FROM t, b WHERE v IS NOT NULL GROUP BY bucket ORDER BY bucket",
+                    source.load_sql()
+                );
+                let df = con
+                    .query(&sql)?
+                    .map(|d| d.map_err(|e| e.into()))
+                    .collect::<Result<DataFrame>>()?;
+                chart::columns(&df)
+            })),
+            name,
+            counts: vec![],
+            range: (0., 1.),
+            error: None,
+        }
+    }
+}
+
+impl Chart for HistogramView {
+    fn label(&self) -> &'static str {
+        "HIST"
+    }
+
+    fn tick(&mut self) -> Option<(&'static str, duckdb_query_progress_type)> {
+        match self.task.as_mut().and_then(|t| t.tick()) {
+            Some(Ok(cols)) => {
+                let [buckets, counts, lo, hi] = [0, 1, 2, 3].map(|idx| &cols[idx]);
+                self.counts = vec![0.; BUCKETS];
+                for (bucket, count) in buckets.iter().zip(counts) {
+                    if let (Some(bucket), Some(count)) = (bucket, count) {
+                        if let Some(slot) = self.counts.get_mut(*bucket as usize) {
+                            *slot = *count;
+                        }
+                    }
+                }
+                if let (Some(Some(lo)), Some(Some(hi))) = (lo.first(), hi.first()) {
+                    self.range = (*lo, *hi);
+                }
+                self.task = None;
+            }
+            Some(Err(it)) => {
+                self.error = Some(it.0);
+                self.task = None;
+            }
+            None => {}
+        }
+        self.task.as_ref().map(|t| ("histogram", t.progress()))
+    }
+
+    fn err(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn draw(&self, c: &mut Canvas) {
+        let total: f64 = self.counts.iter().sum();
+        c.top().draw(
+            format_args!("{} - {total} values", self.name),
+            style::index(),
+        );
+        if self.counts.is_empty() {
+            return;
+        }
+
+        let max = self.counts.iter().copied().fold(1., f64::max);
+        let height = c.height().saturating_sub(1);
+        let width = c.width().saturating_sub(chart::gutter((0., max)) + 1);
+        let bar = (width / BUCKETS).max(1);
+        let gutter = chart::draw_y_axis(c, (0., max), height, |r, buf| {
+            let floor = (height - 1 - r) * 8;
+            for count in &self.counts {
+                let level = (count / max * (height * 8) as f64).round() as usize;
+                let block = BLOCKS[level.saturating_sub(floor).min(8)];
+                for i in 0..bar {
+                    let gap = bar > 1 && i == bar - 1;
+                    buf.push((if gap { ' ' } else { block }, style::series(0)));
+                }
+            }
+        });
+        chart::draw_x_axis(c, gutter, self.range);
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use arrow::{
+    array::{ArrayRef, StringArray},
+    record_batch::RecordBatch,
+};
+use once_cell::sync::Lazy;
+use tui::Canvas;
+
+use crate::{
+    fmt::{wrap, Age},
+    grid::{Grid, Settings},
+    source::{DataFrame, Source},
+    style,
+    view::{View, ViewState},
+};
+
+/// Number of entries kept from the persisted history
+const MAX_ENTRIES: usize = 500;
+/// Maximum number of lines of the query preview
+const PREVIEW_LINES: usize = 5;
+
+/// Applied query with how long it took and how many rows it returned
+struct Entry {
+    sql: String,
+    at: SystemTime,
+    took: Duration,
+    rows: usize,
+    /// Whether all the rows were loaded
+    complete: bool,
+}
+
+static HISTORY: Lazy<Mutex<Vec<Entry>>> = Lazy::new(|| Mutex::new(load()));
+
+/// History file, in `$XDG_STATE_HOME` or `~/.local/state`
+pub fn path() -> Option<PathBuf> {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .map(|dir| dir.join("dtex").join("history"))
+}
+
+/// Read the persisted history, one tab separated entry per line
+///
+/// Entries are only appended while running, the file is compacted to the
+/// kept entries here so it does not grow forever.
+fn load() -> Vec<Entry> {
+    let Some(path) = path() else {
+        return vec![];
+    };
+    let Ok(content) = fs::read_to_string(&path) else {
+        return vec![];
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    let lines = &lines[lines.len().saturating_sub(MAX_ENTRIES)..];
+    if lines.len() < content.lines().count() {
+        // Best effort, replace the file at once to not lose it half written
+        let tmp = path.with_extension("tmp");
+        let _ = fs::write(&tmp, lines.join("\n") + "\n").and_then(|_| fs::rename(&tmp, &path));
+    }
+    lines
+        .iter()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            let at = UNIX_EPOCH + Duration::from_secs(fields.next()?.parse().ok()?);
+            let took = Duration::from_millis(fields.next()?.parse().ok()?);
+            let rows = fields.next()?;
+            let (rows, complete) = match rows.strip_suffix('+') {
+                Some(rows) => (rows, false),
+                None => (rows, true),
+            };
+            Some(Entry {
+                sql: unescape(fields.next()?),
+                at,
+                took,
+                rows: rows.parse().ok()?,
+                complete,
+            })
+        })
+        .collect()
+}
+
+/// Record an applied query, persisting it is best effort
+pub fn push(sql: &str, took: Duration, rows: usize, complete: bool, persist: bool) {
+    let entry = Entry {
+        sql: sql.into(),
+        at: SystemTime::now(),
+        took,
+        rows,
+        complete,
+    };
+    if let Some(path) = path().filter(|_| persist) {
+        let line = format!(
+            "{}\t{}\t{}{}\t{}\n",
+            entry
+                .at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            entry.took.as_millis(),
+            entry.rows,
+            if entry.complete { "" } else { "+" },
+            escape(&entry.sql)
+        );
+        let _ = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| OpenOptions::new().create(true).append(true).open(&path))
+            .and_then(|mut file| file.write_all(line.as_bytes()));
+    }
+    HISTORY.lock().unwrap().push(entry);
+}
+
+fn escape(sql: &str) -> String {
+    sql.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape(line: &str) -> String {
+    let mut sql = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            sql.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => sql.push('\n'),
+            Some('t') => sql.push('\t'),
+            Some(c) => sql.push(c),
+            None => sql.push('\\'),
+        }
+    }
+    sql
+}
+
+/// Past queries, most recent first
+pub struct HistoryView {
+    queries: Vec<String>,
+    df: DataFrame,
+    pub grid: Grid,
+}
+
+impl HistoryView {
+    /// List the history, marking the queries whose result is cached for this
+    /// source
+    pub fn new(source: &Source, settings: Settings) -> Self {
+        let history = HISTORY.lock().unwrap();
+        let mut cols: [Vec<String>; 5] = Default::default();
+        let mut queries = vec![];
+        for entry in history.iter().rev() {
+            let cached = source.query(entry.sql.clone()).is_cached();
+            for (col, value) in cols.iter_mut().zip([
+                entry.sql.split_whitespace().collect::<Vec<_>>().join(" "),
+                format!("{}{}", entry.rows, if entry.complete { "" } else { "+" }),
+                format!("{:.2?}", entry.took),
+                Age(entry.at.elapsed().unwrap_or_default()).to_string(),
+                if cached { "cached" } else { "" }.into(),
+            ]) {
+                col.push(value);
+            }
+            queries.push(entry.sql.clone());
+        }
+        let cols = ["query", "rows", "took", "when", "result"]
+            .into_iter()
+            .zip(cols)
+            .map(|(name, values)| (name, Arc::new(StringArray::from(values)) as ArrayRef));
+        Self {
+            queries,
+            df: RecordBatch::try_from_iter(cols).unwrap().into(),
+            grid: Grid::new(settings),
+        }
+    }
+
+    /// Query under the cursor
+    pub fn selected(&self) -> Option<&str> {
+        self.queries.get(self.grid.nav.c_row()).map(String::as_str)
+    }
+
+    /// Number of lines of the preview of the selected query
+    pub fn height(&self, width: usize) -> usize {
+        self.selected()
+            .map_or(0, |sql| wrap(sql, width, PREVIEW_LINES).len())
+    }
+
+    /// Preview the full selected query
+    pub fn draw(&self, c: &mut Canvas) {
+        if let Some(sql) = self.selected() {
+            for line in wrap(sql, c.width(), PREVIEW_LINES).iter().rev() {
+                c.btm().draw(line, style::primary());
+            }
+        }
+    }
+}
+
+impl View for HistoryView {
+    fn tick(&mut self) -> ViewState {
+        ViewState {
+            loading: None,
+            streaming: false,
+            frame: &self.df,
+            grid: &mut self.grid,
+            err: None,
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::io::{self, Write};
+
+use image::{imageops::FilterType, DynamicImage, RgbaImage};
+use tui::crossterm::terminal;
+
+use crate::clipboard::base64;
+
+/// Maximum payload size of a kitty graphics escape sequence
+const CHUNK: usize = 4096;
+/// Size of a cell in pixels when the terminal does not report it
+const CELL_PIXELS: (u32, u32) = (10, 20);
+/// Levels of each channel of the sixel palette
+const LEVELS: u32 = 6;
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    Png,
+    Jpeg,
+}
+
+impl Format {
+    /// Detect an image format from its magic bytes
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Format::Png)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Format::Jpeg)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Jpeg => "jpeg",
+        }
+    }
+
+    /// Whether the terminal can display images
+    pub fn displayable(&self) -> bool {
+        Protocol::detect().is_some()
+    }
+}
+
+/// Graphics protocol implemented by the terminal
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// Kitty graphics protocol, also implemented by WezTerm and Ghostty
+    Kitty,
+    /// DEC sixel graphics
+    Sixel,
+}
+
+impl Protocol {
+    /// Detect the graphics protocol of the terminal from its environment
+    pub fn detect() -> Option<Self> {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        let (term, program) = (var("TERM"), var("TERM_PROGRAM"));
+        if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || matches!(program.as_str(), "WezTerm" | "ghostty")
+        {
+            Some(Self::Kitty)
+        } else if ["sixel", "foot", "mlterm", "yaft", "contour"]
+            .iter()
+            .any(|name| term.contains(name))
+            || matches!(program.as_str(), "iTerm.app" | "mintty")
+        {
+            Some(Self::Sixel)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Protocol::Kitty => "kitty",
+            Protocol::Sixel => "sixel",
+        }
+    }
+}
+
+/// Display an image at a screen position over `rows` lines
+pub fn show(bytes: &[u8], row: u16, col: u16, rows: u16) -> io::Result<()> {
+    let Some(protocol) = Protocol::detect() else {
+        return Ok(());
+    };
+    let mut out = io::stdout().lock();
+    // Save cursor, move to position and restore after
+    write!(out, "\x1b7\x1b[{};{}H", row + 1, col + 1)?;
+    match protocol {
+        // The kitty protocol decodes PNG itself
+        Protocol::Kitty if matches!(Format::detect(bytes), Some(Format::Png)) => {
+            kitty(&mut out, &format!("f=100,r={rows}"), bytes)?
+        }
+        Protocol::Kitty => {
+            let image = decode(bytes, rows)?;
+            let (width, height) = image.dimensions();
+            let params = format!("f=32,s={width},v={height},r={rows}");
+            kitty(&mut out, &params, image.as_raw())?
+        }
+        Protocol::Sixel => sixel(&mut out, &decode(bytes, rows)?)?,
+    }
+    write!(out, "\x1b8")?;
+    out.flush()
+}
+
+/// Remove all displayed images, returning whether the screen must be drawn
+/// again as sixel images are only erased by overwriting their cells
+pub fn clear() -> io::Result<bool> {
+    match Protocol::detect() {
+        Some(Protocol::Kitty) => {
+            let mut out = io::stdout().lock();
+            write!(out, "\x1b_Ga=d,q=2\x1b\\")?;
+            out.flush()?;
+            Ok(false)
+        }
+        Some(Protocol::Sixel) => Ok(true),
+        None => Ok(false),
+    }
+}
+
+/// Transmit and display pixels with the kitty graphics protocol
+fn kitty(out: &mut impl Write, params: &str, payload: &[u8]) -> io::Result<()> {
+    let data = base64(payload);
+    let mut chunks = data.as_bytes().chunks(CHUNK).peekable();
+    let mut first = true;
+    while let Some(chunk) = chunks.next() {
+        let more = chunks.peek().is_some() as u8;
+        if first {
+            write!(out, "\x1b_Ga=T,q=2,C=1,{params},m={more};")?;
+            first = false;
+        } else {
+            write!(out, "\x1b_Gm={more};")?;
+        }
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// Decode an image scaled down to fit in `rows` lines of the screen
+fn decode(bytes: &[u8], rows: u16) -> io::Result<RgbaImage> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let (cell_width, cell_height) = match terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.rows > 0 && size.columns > 0 => (
+            (size.width / size.columns) as u32,
+            (size.height / size.rows) as u32,
+        ),
+        _ => CELL_PIXELS,
+    };
+    let columns = terminal::size().map_or(80, |(columns, _)| columns) as u32;
+    let (width, height) = (columns * cell_width, rows as u32 * cell_height);
+    let image = if image.width() > width || image.height() > height {
+        image.resize(width, height, FilterType::Triangle)
+    } else {
+        image
+    };
+    Ok(DynamicImage::to_rgba8(&image))
+}
+
+/// Encode pixels as sixels using a palette of 216 colors, transparent pixels
+/// are left untouched
+fn sixel(out: &mut impl Write, image: &RgbaImage) -> io::Result<()> {
+    let (width, height) = image.dimensions();
+    let level = |c: u8| (c as u32 * (LEVELS - 1) + 127) / 255;
+    let color = |x: u32, y: u32| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        (a >= 128).then(|| ((level(r) * LEVELS + level(g)) * LEVELS + level(b)) as usize)
+    };
+    // Raster attributes then the palette, in percent of each channel
+    write!(out, "\x1bPq\"1;1;{width};{height}")?;
+    for i in 0..LEVELS.pow(3) {
+        let [r, g, b] = [i / LEVELS / LEVELS, i / LEVELS % LEVELS, i % LEVELS]
+            .map(|level| level * 100 / (LEVELS - 1));
+        write!(out, "#{i};2;{r};{g};{b}")?;
+    }
+    let mut bits = vec![0u8; width as usize];
+    for band in (0..height).step_by(6) {
+        let mut colors: Vec<usize> = (band..(band + 6).min(height))
+            .flat_map(|y| (0..width).filter_map(move |x| color(x, y)))
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+        for color_idx in colors {
+            for (x, bit) in bits.iter_mut().enumerate() {
+                *bit = (0..6)
+                    .filter(|dy| {
+                        band + dy < height && color(x as u32, band + dy) == Some(color_idx)
+                    })
+                    .fold(0, |acc, dy| acc | 1 << dy);
+            }
+            // Overlay each color on the same band, run length encoded
+            write!(out, "#{color_idx}")?;
+            let mut runs: Vec<(u8, usize)> = vec![];
+            for bit in &bits {
+                match runs.last_mut() {
+                    Some((prev, len)) if prev == bit => *len += 1,
+                    _ => runs.push((*bit, 1)),
+                }
+            }
+            // Trailing empty sixels can be skipped
+            if runs.last().is_some_and(|(bit, _)| *bit == 0) {
+                runs.pop();
+            }
+            for (bit, len) in runs {
+                let char = (63 + bit) as char;
+                match len {
+                    1..=3 => (0..len).try_for_each(|_| write!(out, "{char}"))?,
+                    len => write!(out, "!{len}{char}")?,
+                }
+            }
+            write!(out, "$")?;
+        }
+        write!(out, "-")?;
+    }
+    write!(out, "\x1b\\")
+}
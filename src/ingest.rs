@@ -0,0 +1,237 @@
+use std::{
+    io::{BufRead, BufReader, Cursor, Read},
+    sync::{Arc, Mutex},
+};
+
+use arrow::{
+    csv, ipc,
+    json::{self, reader::infer_json_schema},
+    record_batch::RecordBatch,
+};
+
+use crate::{avro, duckdb::Connection, error::Result, records, source::DataFrame};
+
+/// Number of rows used to infer the schema of text formats
+const INFER_ROWS: usize = 1000;
+
+/// Encoding of data read from a stream
+#[derive(Clone, Copy)]
+pub enum Format {
+    /// Comma separated values with a header line
+    Csv,
+    /// Newline delimited json objects
+    Json,
+    /// Arrow IPC stream
+    Arrow,
+    /// Concatenated MessagePack records
+    MsgPack,
+    /// Concatenated BSON documents
+    Bson,
+    /// Avro object container file
+    Avro,
+}
+
+impl Format {
+    /// Record stream formats duckdb cannot read, by file extension
+    pub fn from_path(path: &str) -> Option<Self> {
+        [
+            (".msgpack", Self::MsgPack),
+            (".mpk", Self::MsgPack),
+            (".bson", Self::Bson),
+            (".avro", Self::Avro),
+        ]
+        .into_iter()
+        .find_map(|(ext, format)| path.ends_with(ext).then_some(format))
+    }
+}
+
+/// Batches fed by the host program, accumulated by a background thread
+#[derive(Clone)]
+pub struct Ingest {
+    state: Arc<Mutex<State>>,
+    stored: Arc<Mutex<Stored>>,
+}
+
+#[derive(Default)]
+struct State {
+    df: DataFrame,
+    /// Number of batches received, including the ones dropped
+    batches: usize,
+    /// Number of oldest batches dropped beyond the retention
+    dropped: usize,
+    /// Number of most recent rows kept
+    retain: Option<usize>,
+    done: bool,
+    err: Option<String>,
+}
+
+impl State {
+    /// Append a batch, dropping the oldest ones beyond the retention
+    fn push(&mut self, batch: RecordBatch) {
+        self.df.extend(std::iter::once(batch));
+        self.batches += 1;
+        let Some(retain) = self.retain else {
+            return;
+        };
+        let mut rows = self.df.num_rows();
+        let batches = &self.df.0.batchs;
+        let expired = batches
+            .iter()
+            .take_while(|b| {
+                let expired = rows - b.num_rows() >= retain;
+                if expired {
+                    rows -= b.num_rows();
+                }
+                expired
+            })
+            .count();
+        if expired > 0 {
+            self.df = batches[expired..].iter().cloned().collect();
+            self.dropped += expired;
+        }
+    }
+}
+
+/// Batches copied into the current table, by position in the whole stream
+#[derive(Default)]
+struct Stored {
+    start: usize,
+    end: usize,
+}
+
+impl Ingest {
+    /// Collect the batches of an iterator opened in a background thread,
+    /// keeping only the `retain` most recent rows when set
+    pub fn spawn<I>(
+        open: impl FnOnce() -> Result<I> + Send + 'static,
+        retain: Option<usize>,
+    ) -> Self
+    where
+        I: Iterator<Item = Result<RecordBatch>>,
+    {
+        let ingest = Self {
+            state: Arc::new(Mutex::new(State {
+                retain,
+                ..State::default()
+            })),
+            stored: Arc::default(),
+        };
+        let state = ingest.state.clone();
+        std::thread::spawn(move || {
+            let result = open().and_then(|iter| {
+                for batch in iter {
+                    let batch = batch?;
+                    let mut state = state.lock().unwrap();
+                    if state.batches > 0 && *state.df.schema() != batch.schema() {
+                        return Err("Ingested batches must share the same schema".into());
+                    }
+                    state.push(batch);
+                }
+                Ok(())
+            });
+            let mut state = state.lock().unwrap();
+            state.done = true;
+            state.err = result.err().map(|e| e.0);
+        });
+        ingest
+    }
+
+    /// Decode a stream in a background thread
+    pub fn read(reader: impl Read + Send + 'static, format: Format) -> Self {
+        Self::spawn(move || decode(reader, format), None)
+    }
+
+    /// Rows ingested so far
+    pub fn snapshot(&self) -> DataFrame {
+        self.state.lock().unwrap().df.clone()
+    }
+
+    /// Number of batches ingested so far
+    pub fn batches(&self) -> usize {
+        self.state.lock().unwrap().batches
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state.lock().unwrap().done
+    }
+
+    /// Why ingestion stopped early, reported once
+    pub fn take_err(&self) -> Option<String> {
+        self.state.lock().unwrap().err.take()
+    }
+
+    /// Copy the rows ingested since the last call into the current table of
+    /// the database, the whole table is copied again once rows expired
+    pub fn store(&self, con: &Connection) -> Result<()> {
+        let mut stored = self.stored.lock().unwrap();
+        let (df, dropped, batches) = {
+            let state = self.state.lock().unwrap();
+            (state.df.clone(), state.dropped, state.batches)
+        };
+        if df.num_columns() == 0 || (stored.start == dropped && stored.end == batches) {
+            return Ok(());
+        }
+        if stored.end > 0 && stored.start == dropped {
+            let new: DataFrame = df.0.batchs[stored.end - dropped..]
+                .iter()
+                .cloned()
+                .collect();
+            con.append("current", &new)?;
+        } else {
+            con.store("current", &df)?;
+        }
+        *stored = Stored {
+            start: dropped,
+            end: batches,
+        };
+        Ok(())
+    }
+}
+
+/// Batches of a stream, the schema of text formats is inferred from their
+/// first rows
+pub fn decode(
+    reader: impl Read + 'static,
+    format: Format,
+) -> Result<Box<dyn Iterator<Item = Result<RecordBatch>>>> {
+    Ok(match format {
+        Format::Csv => {
+            let (bytes, rest) = prefix(reader)?;
+            let (schema, _) = csv::reader::Format::default()
+                .with_header(true)
+                .infer_schema(Cursor::new(&bytes), Some(INFER_ROWS))?;
+            let reader = csv::ReaderBuilder::new(Arc::new(schema))
+                .with_header(true)
+                .build(Cursor::new(bytes).chain(rest))?;
+            Box::new(reader.map(|b| b.map_err(|e| e.into())))
+        }
+        Format::Json => {
+            let (bytes, rest) = prefix(reader)?;
+            let (schema, _) = infer_json_schema(Cursor::new(&bytes), Some(INFER_ROWS))?;
+            let reader =
+                json::ReaderBuilder::new(Arc::new(schema)).build(Cursor::new(bytes).chain(rest))?;
+            Box::new(reader.map(|b| b.map_err(|e| e.into())))
+        }
+        Format::Arrow => {
+            let reader = ipc::reader::StreamReader::try_new(BufReader::new(reader), None)?;
+            Box::new(reader.map(|b| b.map_err(|e| e.into())))
+        }
+        Format::MsgPack => Box::new(records::msgpack(BufReader::new(reader))?),
+        Format::Bson => Box::new(records::bson(BufReader::new(reader))?),
+        Format::Avro => Box::new(avro::batches(BufReader::new(reader))?),
+    })
+}
+
+/// Lines holding the first rows of a text stream, and the rest of the stream
+/// to read progressively
+fn prefix<R: Read>(reader: R) -> Result<(Vec<u8>, BufReader<R>)> {
+    let mut reader = BufReader::new(reader);
+    let mut bytes = Vec::new();
+    // One more line for the header of csv
+    for _ in 0..=INFER_ROWS {
+        if reader.read_until(b'\n', &mut bytes)? == 0 {
+            break;
+        }
+    }
+    Ok((bytes, reader))
+}
@@ -0,0 +1,53 @@
+use tui::{crossterm::event::KeyCode as Key, Canvas};
+
+use crate::{clipboard, fmt::wrap, style, OnKey};
+
+/// Maximum number of lines of the pane
+const MAX_LINES: usize = 12;
+
+/// Read-only pane showing the effective SQL of a tab
+pub struct Inspect {
+    sql: String,
+    /// First displayed line
+    offset: usize,
+}
+
+impl Inspect {
+    pub fn new(sql: String) -> Self {
+        Self { sql, offset: 0 }
+    }
+
+    /// Number of lines of the pane, title included
+    pub fn height(&self, width: usize) -> usize {
+        wrap(&self.sql, width, usize::MAX).len().min(MAX_LINES) + 1
+    }
+
+    pub fn on_key(&mut self, code: Key) -> (OnKey, Option<std::io::Result<()>>) {
+        match code {
+            Key::Esc | Key::Char('q' | 'I') => return (OnKey::Quit, None),
+            Key::Char('y') => return (OnKey::Continue, Some(clipboard::copy(&self.sql))),
+            Key::Down | Key::Char('j') => self.offset += 1,
+            Key::Up | Key::Char('k') => self.offset = self.offset.saturating_sub(1),
+            _ => return (OnKey::Pass, None),
+        }
+        (OnKey::Continue, None)
+    }
+
+    pub fn draw(&mut self, c: &mut Canvas) {
+        let lines = wrap(&self.sql, c.width(), usize::MAX);
+        let visible = MAX_LINES.min(lines.len());
+        self.offset = self.offset.min(lines.len() - visible);
+        for line in lines[self.offset..self.offset + visible].iter().rev() {
+            c.btm().draw(line, style::primary());
+        }
+        c.btm().draw(
+            format_args!(
+                "Effective SQL {}-{}/{} (y copy, esc close)",
+                self.offset + 1,
+                self.offset + visible,
+                lines.len()
+            ),
+            style::index(),
+        );
+    }
+}
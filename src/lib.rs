@@ -1,63 +1,261 @@
-use std::{io, ops::Range, sync::mpsc::RecvTimeoutError, time::Duration};
+use std::{
+    fmt::Display,
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::mpsc::RecvTimeoutError,
+    time::{Duration, Instant},
+};
 
 use arrow::{
-    array::{ArrayRef, AsArray, Decimal128Array},
+    array::{Array, ArrayRef, AsArray},
     datatypes::{
-        DataType, Float16Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
-        UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+        DataType, Decimal128Type, Decimal256Type, DurationMicrosecondType, DurationMillisecondType,
+        DurationNanosecondType, DurationSecondType, Float16Type, Float32Type, Float64Type,
+        Int16Type, Int32Type, Int64Type, Int8Type, TimeUnit, UInt16Type, UInt32Type, UInt64Type,
+        UInt8Type,
     },
     util::display::{ArrayFormatter, FormatOptions},
 };
+use embed::Hooks;
 use event::event_listener;
-use fmt::{rtrim, ColBuilder, GridBuffer};
-use grid::nav::Nav;
+use fmt::{rtrim, ColBuilder, GridBuffer, Seconds, Size};
+use global_search::GlobalSearchView;
+use grid::{nav::Nav, Settings};
 use notify::{RecommendedWatcher, Watcher};
 use notify_debouncer_full::FileIdMap;
 use tab::Tab;
 use tui::{
-    crossterm::event::{Event, KeyCode, KeyEventKind},
+    crossterm::{
+        event::{Event, KeyEventKind},
+        terminal,
+    },
     unicode_width::UnicodeWidthStr,
     Canvas, Terminal,
 };
 
 pub use arrow;
+pub use cmd::UNION_BY_NAME;
+pub use diagnose::diagnose;
+pub use embed::{Activation, App, Selection};
 pub use error::{Result, StrError};
+pub use grid::{Borders, MaxWidth, Separator};
+pub use ingest::Format;
+#[cfg(feature = "live")]
+pub use live::RETAIN_ROWS;
+pub use print::print;
+pub use provider::{Provider, Pushdown};
 pub use source::{DataFrame, Source};
+pub use stats::{stats, Stats};
+pub use style::Theme;
+pub use tui::crossterm::event::{KeyCode, KeyModifiers};
 
+mod archive;
+mod avro;
+mod cache;
+mod catalog;
+mod chart;
+mod clipboard;
+mod cmd;
+mod config;
+mod delta;
 mod describe;
+mod diagnose;
+mod distinct;
 mod duckdb;
+mod embed;
+mod encoding;
 mod error;
 mod event;
+mod excel;
+mod export;
+mod files;
+mod filter;
 mod fmt;
+mod global_search;
 mod grid;
+mod group;
+mod histogram;
+mod history;
+mod image;
+mod ingest;
+mod inspect;
+#[cfg(feature = "live")]
+mod live;
+mod missing;
 mod navigator;
+mod ops;
+mod plot;
+mod print;
+mod progress;
+mod provider;
+mod records;
+mod report;
+mod schema_diff;
+mod search;
 mod shell;
 mod source;
 mod spinner;
+mod sql;
+mod stats;
 mod style;
 mod tab;
 mod task;
+mod temp;
+mod validate;
 mod view;
 
+/// Explorer options
+pub struct Options {
+    /// Re-execute the queries periodically
+    pub watch: Option<Duration>,
+    /// Number of viewports loaded ahead when scrolling down
+    pub prefetch: usize,
+    /// Keep loading rows in the background up to this memory size in bytes
+    pub idle_load: Option<usize>,
+    /// Maximum number of queries executing at once
+    pub max_tasks: usize,
+    /// Start loading all tabs at once instead of when first focused
+    pub preload: bool,
+    /// Drop the loaded data of tabs not focused for this long
+    pub evict_after: Option<Duration>,
+    /// Maximum width of fitted columns
+    pub max_width: MaxWidth,
+    /// Colors of the grid backgrounds
+    pub theme: Theme,
+    /// Separators between columns and under the header
+    pub borders: Borders,
+    /// Share column projection and sizes between tabs with the same schema
+    pub share_layout: bool,
+    /// Screen reader friendly rendering, see `style::ACCESSIBLE`
+    pub accessible: bool,
+    /// Append the queries applied from the shell to the history file, see
+    /// `history::path`
+    pub history: bool,
+    /// Command opening the file of a tab, defaults to `$VISUAL`, `$EDITOR`
+    /// then the system opener
+    pub opener: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            watch: None,
+            prefetch: tab::PREFETCH,
+            idle_load: None,
+            max_tasks: task::MAX_TASKS,
+            preload: false,
+            evict_after: None,
+            max_width: MaxWidth::default(),
+            theme: Theme::default(),
+            borders: Borders::default(),
+            share_layout: false,
+            accessible: false,
+            history: true,
+            opener: None,
+        }
+    }
+}
+
+/// Version of the linked duckdb library, failing when it is not supported
+pub fn duckdb_version() -> Result<String> {
+    Ok(duckdb::library_version()?)
+}
+
 pub fn run(sources: impl Iterator<Item = Source>) {
+    run_with(sources, Options::default())
+}
+
+pub fn run_with(sources: impl Iterator<Item = Source>, options: Options) {
+    run_hooked(sources, options, Hooks::default())
+}
+
+fn run_hooked(sources: impl Iterator<Item = Source>, options: Options, hooks: Hooks) {
+    task::set_max_tasks(options.max_tasks);
+    let settings = Settings {
+        max_width: options.max_width,
+        theme: options.theme,
+        borders: if options.accessible {
+            // Box drawing characters are read aloud
+            Borders {
+                separator: Separator::Space,
+                header_rule: false,
+            }
+        } else {
+            options.borders
+        },
+        accessible: options.accessible,
+        history: options.history,
+    };
     let (receiver, watcher, runner) = event_listener();
-    let mut app = App::new(watcher);
+    let mut app = Explorer::new(watcher, hooks);
+    app.share_layout = options.share_layout;
+    let opener = options
+        .opener
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .unwrap_or_else(|| SYSTEM_OPENER.into());
     for source in sources {
-        app.add_tab(Tab::open(runner.clone(), source));
+        let mut tab = if options.preload || app.tabs.is_empty() {
+            Tab::open(runner.clone(), source, settings)
+        } else {
+            Tab::open_deferred(runner.clone(), source, settings)
+        };
+        tab.view.prefetch = options.prefetch;
+        tab.view.idle_load = options.idle_load;
+        if let Some(interval) = options.watch {
+            tab.watch(interval);
+        }
+        app.add_tab(tab);
     }
     if app.tabs.is_empty() {
-        app.add_tab(Tab::open(runner, Source::empty("#".into())));
+        match Source::empty("#".into()) {
+            Ok(source) => app.add_tab(Tab::open(runner, source, settings)),
+            Err(err) => {
+                eprintln!("{}", err.0);
+                return;
+            }
+        }
     }
     let mut terminal = Terminal::new(io::stdout()).unwrap();
+    let mut shown: Option<(Vec<u8>, u16)> = None;
     loop {
+        app.record_stats();
         let mut is_loading = false;
         terminal
             .draw(|c| {
                 is_loading = app.draw(c);
             })
             .unwrap();
-        let mut event = if is_loading {
-            match receiver.recv_timeout(Duration::from_millis(100)) {
+        // Images are drawn over the terminal grid after each frame
+        if shown.as_ref().map(|(b, o)| (b.as_slice(), *o)) != app.image() {
+            if shown.is_some() && image::clear().unwrap_or(false) {
+                // Erase images that can't be deleted by drawing everything again
+                terminal = Terminal::new(io::stdout()).unwrap();
+                terminal.draw(|c| is_loading = app.draw(c)).unwrap();
+            }
+            shown = app.image().map(|(bytes, offset)| (bytes.to_vec(), offset));
+            if let (Some((bytes, offset)), Ok((_, height))) = (&shown, terminal::size()) {
+                image::show(
+                    bytes,
+                    height.saturating_sub(*offset),
+                    0,
+                    tab::PREVIEW_LINES as u16,
+                )
+                .ok();
+            }
+        }
+        if let Some(after) = options.evict_after {
+            app.evict(after);
+        }
+        let timeout = if is_loading {
+            let frame = frame_interval(app.input.elapsed());
+            Some(app.next_wake().map_or(frame, |wake| wake.min(frame)))
+        } else {
+            app.next_wake()
+        };
+        let mut event = if let Some(timeout) = timeout {
+            match receiver.recv_timeout(timeout) {
                 Ok(e) => Some(e),
                 Err(err) => match err {
                     RecvTimeoutError::Timeout => None,
@@ -70,42 +268,173 @@ pub fn run(sources: impl Iterator<Item = Source>) {
             Some(receiver.recv().unwrap())
         };
         while let Some(e) = event {
+            let resize = matches!(e, event::Event::Term(Event::Resize(..)));
             if app.on_event(e) {
+                if let Some(quit) = app.hooks.quit.take() {
+                    let selection = app.tabs.get(app.nav.c_col()).map(Tab::selection);
+                    quit(selection.or(app.closed.take()));
+                }
+                // Embedders polling the stats should not see the last run
+                stats::record(0, 0, 0);
                 return;
             }
-            // Ingest more event before drawing if we can
-            event = receiver.try_recv().ok()
+            // Ingest more event before drawing if we can, resizes come in bursts
+            // so wait for the last one instead of reflowing at each step
+            event = if resize {
+                receiver.recv_timeout(RESIZE_DEBOUNCE).ok()
+            } else {
+                receiver.try_recv().ok()
+            }
+        }
+        if std::mem::take(&mut app.suspend) {
+            // Restore the terminal while suspended and redraw everything on resume
+            drop(terminal);
+            suspend();
+            terminal = Terminal::new(io::stdout()).unwrap();
+            shown = None;
+        }
+        if let Some(path) = app.launch.take() {
+            // The program owns the terminal until it exits
+            drop(terminal);
+            let result = launch(&opener, &path);
+            terminal = Terminal::new(io::stdout()).unwrap();
+            shown = None;
+            if let Err(err) = result {
+                if let Some(tab) = app.tabs.get_mut(app.nav.c_col()) {
+                    tab.notice = Some(Err(err));
+                }
+            }
         }
     }
 }
 
-struct App {
+/// Number of other tables of a database opened in their own tab
+const MAX_TABLE_TABS: usize = 8;
+
+/// Delay without resize event before redrawing
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Redraw interval while loading, faster when the user is scrolling through
+/// incoming rows and slower when nobody is watching
+fn frame_interval(since_input: Duration) -> Duration {
+    if since_input < Duration::from_secs(1) {
+        Duration::from_millis(30)
+    } else if since_input < Duration::from_secs(10) {
+        Duration::from_millis(100)
+    } else {
+        Duration::from_millis(500)
+    }
+}
+
+/// Program opening files with their default application
+#[cfg(target_os = "macos")]
+const SYSTEM_OPENER: &str = "open";
+#[cfg(not(target_os = "macos"))]
+const SYSTEM_OPENER: &str = "xdg-open";
+
+/// Run a command on a file and wait for it to exit
+fn launch(command: &str, path: &Path) -> Result<()> {
+    let mut args = command.split_whitespace();
+    let program = args.next().ok_or("Empty open command")?;
+    let status = std::process::Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .map_err(|e| format!("{program}: {e}"))?;
+    if !status.success() {
+        return Err(format!("{program} exited with {status}").into());
+    }
+    Ok(())
+}
+
+/// Stop the process like a shell job, return once resumed with `fg`
+#[cfg(unix)]
+fn suspend() {
+    unsafe {
+        libc::raise(libc::SIGTSTP);
+    }
+}
+
+#[cfg(not(unix))]
+fn suspend() {}
+
+struct Explorer {
     tabs: Vec<Tab>,
     nav: Nav,
     debouncer: notify_debouncer_full::Debouncer<RecommendedWatcher, FileIdMap>,
     buf: GridBuffer,
+    share_layout: bool,
+    /// Suspend to the shell before the next draw
+    suspend: bool,
+    /// Last user input, drives the redraw cadence
+    input: Instant,
+    /// File to open in an external program before the next draw
+    launch: Option<PathBuf>,
+    hooks: Hooks,
+    /// Rows picked in the last closed tab
+    closed: Option<Selection>,
+    /// Id of the next opened tab
+    next_id: usize,
 }
-impl App {
-    pub fn new(debouncer: notify_debouncer_full::Debouncer<RecommendedWatcher, FileIdMap>) -> Self {
+impl Explorer {
+    pub fn new(
+        debouncer: notify_debouncer_full::Debouncer<RecommendedWatcher, FileIdMap>,
+        hooks: Hooks,
+    ) -> Self {
         Self {
             tabs: vec![],
             nav: Nav::new(),
             buf: GridBuffer::new(),
             debouncer,
+            share_layout: false,
+            suspend: false,
+            input: Instant::now(),
+            launch: None,
+            hooks,
+            closed: None,
+            next_id: 0,
         }
     }
 
-    pub fn add_tab(&mut self, tab: Tab) {
-        if let Some(path) = tab.view.source.path() {
-            self.debouncer
-                .watcher()
-                .watch(path, notify::RecursiveMode::NonRecursive)
-                .ok();
+    pub fn add_tab(&mut self, mut tab: Tab) {
+        if let Some((path, recursive)) = tab.view.source.watched() {
+            let mode = if recursive {
+                notify::RecursiveMode::Recursive
+            } else {
+                notify::RecursiveMode::NonRecursive
+            };
+            self.debouncer.watcher().watch(&path, mode).ok();
+        }
+        // A database opens on its first table, the others load when focused
+        // or are picked from its catalog when there are too many
+        let source = tab.view.source.clone();
+        let tables = source.other_tables();
+        if tables.len() > MAX_TABLE_TABS {
+            tab.show_catalog();
+            self.push(tab);
+            return;
         }
+        let (runner, settings) = (tab.runner.clone(), tab.settings);
+        self.push(tab);
+        for table in tables {
+            self.push(Tab::open_deferred(
+                runner.clone(),
+                source.table(table),
+                settings,
+            ));
+        }
+    }
+
+    fn push(&mut self, mut tab: Tab) {
+        tab.id = self.next_id;
+        self.next_id += 1;
         self.tabs.push(tab);
     }
 
     pub fn draw(&mut self, c: &mut Canvas) -> bool {
+        for tab in &mut self.tabs {
+            tab.tick_watch();
+        }
         self.buf.new_frame(c.width());
         let mut coll_off_iter = self.nav.col_iter(self.tabs.len());
         if self.tabs.len() == 1 {
@@ -117,9 +446,16 @@ impl App {
             while remaining_width > cols.len() {
                 if let Some(off) = coll_off_iter.next() {
                     let tab = &self.tabs[off];
-                    remaining_width =
-                        remaining_width.saturating_sub(tab.view.source.name().width());
-                    cols.push((off, tab.view.source.name()));
+                    let mut name = match tab.mem_size() {
+                        0 => tab.view.source.name().to_string(),
+                        size => format!("{} {}", tab.view.source.name(), Size(size)),
+                    };
+                    if tab.linked {
+                        name.push(' ');
+                        name.push_str(tab.settings.glyphs().linked);
+                    }
+                    remaining_width = remaining_width.saturating_sub(name.width());
+                    cols.push((off, name));
                 } else {
                     break;
                 }
@@ -151,6 +487,112 @@ impl App {
         }
     }
 
+    /// Publish the resource usage of the open tabs
+    fn record_stats(&self) {
+        stats::record(
+            self.tabs.len(),
+            self.tabs.iter().map(Tab::num_rows).sum(),
+            self.tabs.iter().map(Tab::mem_size).sum(),
+        );
+    }
+
+    /// Drop the loaded data of the tabs not focused for a while
+    pub fn evict(&mut self, after: Duration) {
+        let current = self.nav.c_col();
+        for (i, tab) in self.tabs.iter_mut().enumerate() {
+            if i != current {
+                tab.evict(after);
+            }
+        }
+    }
+
+    /// Time until the current tab must be drawn again or any tab refreshed
+    pub fn next_wake(&self) -> Option<Duration> {
+        let current = self.tabs.get(self.nav.c_col()).and_then(Tab::next_wake);
+        self.tabs
+            .iter()
+            .filter_map(Tab::watch_remaining)
+            .chain(current)
+            .min()
+    }
+
+    /// Open the schema changes from the current tab to the next one in a new tab
+    fn schema_diff(&mut self) {
+        if self.tabs.len() < 2 {
+            return;
+        }
+        let (old, new) = (self.nav.c_col(), (self.nav.c_col() + 1) % self.tabs.len());
+        let source = Source::schema_diff(
+            self.tabs[old].view.source.clone(),
+            self.tabs[new].view.source.clone(),
+        );
+        match source {
+            Ok(source) => {
+                let runner = self.tabs[old].runner.clone();
+                self.add_tab(Tab::open(runner, source, self.tabs[old].settings));
+            }
+            Err(err) => self.tabs[old].notice = Some(Err(err)),
+        }
+    }
+
+    /// Search a pattern in every tab, listing the matches in the current one
+    fn search_all(&mut self, pattern: &str) {
+        let current = self.nav.c_col();
+        let sources = self
+            .tabs
+            .iter()
+            .filter(|tab| !tab.view.source.init_sql().is_empty())
+            .map(|tab| (tab.id, tab.view.source.clone()))
+            .collect();
+        let tab = &mut self.tabs[current];
+        let view = GlobalSearchView::new(pattern, sources, &tab.runner, tab.settings);
+        tab.show_search_all(view);
+    }
+
+    /// Focus a cell of a tab, the tab may have been closed or moved since
+    /// the cell was found
+    fn jump(&mut self, (id, row, col): (usize, usize, usize)) {
+        if let Some(pos) = self.tabs.iter().position(|tab| tab.id == id) {
+            self.tabs[pos].view.grid.focus(row, col);
+            self.nav.go_to((self.nav.c_row(), pos));
+        }
+    }
+
+    /// Move the cursor and scroll of the other linked tabs like a linked tab
+    fn follow(&mut self, from: usize) {
+        let Some(tab) = self.tabs.get(from).filter(|t| t.linked) else {
+            return;
+        };
+        let nav = tab.view.grid.nav.clone();
+        for (i, tab) in self.tabs.iter_mut().enumerate() {
+            if i != from && tab.linked {
+                tab.view.grid.nav = nav.clone();
+            }
+        }
+    }
+
+    /// Copy the layout of a tab to the other tabs with the same schema
+    fn share_layout(&mut self, from: usize) {
+        let Some(tab) = self.tabs.get(from).filter(|_| self.share_layout) else {
+            return;
+        };
+        let schema = tab.view.schema().clone();
+        if schema.fields().is_empty() {
+            return;
+        }
+        let layout = tab.view.grid.layout();
+        for (i, tab) in self.tabs.iter_mut().enumerate() {
+            if i != from && tab.view.schema().fields() == schema.fields() {
+                tab.view.grid.set_layout(&layout);
+            }
+        }
+    }
+
+    /// Image to display in the current tab with its offset from the bottom of the screen
+    pub fn image(&self) -> Option<(&[u8], u16)> {
+        self.tabs.get(self.nav.c_col()).and_then(Tab::image)
+    }
+
     pub fn on_event(&mut self, event: event::Event) -> bool {
         match event {
             event::Event::Term(event) => {
@@ -158,16 +600,85 @@ impl App {
                     if event.kind != KeyEventKind::Press {
                         return false;
                     }
+                    self.input = Instant::now();
                     let mut pass = true;
+                    if let Some(tab) = self.tabs.get_mut(self.nav.c_col()) {
+                        if let Some(handler) = self
+                            .hooks
+                            .key(event.code, event.modifiers)
+                            .filter(|_| tab.is_normal())
+                        {
+                            tab.notice = handler(&tab.selection()).map(Ok);
+                            return false;
+                        }
+                    }
                     match event.code {
                         KeyCode::Tab => {
+                            self.share_layout(self.nav.c_col());
                             self.nav.right_roll();
                             pass = false;
                         }
                         KeyCode::BackTab => {
+                            self.share_layout(self.nav.c_col());
                             self.nav.left_roll();
                             pass = false;
                         }
+                        KeyCode::Char('x')
+                            if event
+                                .modifiers
+                                .contains(tui::crossterm::event::KeyModifiers::CONTROL) =>
+                        {
+                            self.schema_diff();
+                            pass = false;
+                        }
+                        KeyCode::Char('z')
+                            if event
+                                .modifiers
+                                .contains(tui::crossterm::event::KeyModifiers::CONTROL) =>
+                        {
+                            self.suspend = true;
+                            pass = false;
+                        }
+                        KeyCode::Char('o')
+                            if event
+                                .modifiers
+                                .contains(tui::crossterm::event::KeyModifiers::CONTROL) =>
+                        {
+                            if let Some(tab) = self.tabs.get_mut(self.nav.c_col()) {
+                                match tab.view.source.path() {
+                                    Some(path) => self.launch = Some(path.to_path_buf()),
+                                    None => tab.notice = Some(Err("No file for this tab".into())),
+                                }
+                            }
+                            pass = false;
+                        }
+                        KeyCode::Char('g')
+                            if event
+                                .modifiers
+                                .contains(tui::crossterm::event::KeyModifiers::CONTROL) =>
+                        {
+                            if let Some(tab) = self.tabs.get_mut(self.nav.c_col()) {
+                                tab.prompt_search_all();
+                            }
+                            pass = false;
+                        }
+                        KeyCode::Char('l')
+                            if event
+                                .modifiers
+                                .contains(tui::crossterm::event::KeyModifiers::CONTROL) =>
+                        {
+                            if let Some(tab) = self.tabs.get_mut(self.nav.c_col()) {
+                                tab.linked = !tab.linked;
+                                let msg = if tab.linked {
+                                    "Scrolling linked with the other linked tabs"
+                                } else {
+                                    "Scrolling unlinked"
+                                };
+                                tab.notice = Some(Ok(msg.into()));
+                            }
+                            self.follow(self.nav.c_col());
+                            pass = false;
+                        }
                         KeyCode::Char('c' | 'd')
                             if event
                                 .modifiers
@@ -181,10 +692,27 @@ impl App {
                     if pass {
                         if let Some(tab) = self.tabs.get_mut(self.nav.c_col()) {
                             if tab.on_key(&event) {
-                                if let Some(path) = tab.view.source.path() {
-                                    self.debouncer.watcher().unwatch(path).unwrap();
+                                if self.hooks.quit.is_some() {
+                                    self.closed = Some(tab.selection());
+                                }
+                                if let Some((path, _)) = tab.view.source.watched() {
+                                    self.debouncer.watcher().unwatch(&path).ok();
                                 }
                                 self.tabs.remove(self.nav.c_col());
+                            } else if let Some(source) = tab.open.take() {
+                                let (runner, settings) = (tab.runner.clone(), tab.settings);
+                                self.add_tab(Tab::open(runner, source, settings));
+                            } else if let Some(pattern) = tab.search_all.take() {
+                                self.search_all(&pattern);
+                            } else if let Some(hit) = tab.jump.take() {
+                                self.jump(hit);
+                            } else if let Some(activation) = tab.activated.take() {
+                                if let Some(handler) = &mut self.hooks.row_activate {
+                                    handler(&activation);
+                                }
+                            } else {
+                                self.share_layout(self.nav.c_col());
+                                self.follow(self.nav.c_col());
                             }
                         }
                     }
@@ -196,16 +724,15 @@ impl App {
                         for e in events {
                             // TODO handle more event
                             // TODO perf with many tabs
-                            if e.kind.is_modify() {
+                            // Files created in a watched directory can match a pattern
+                            if e.kind.is_modify() || e.kind.is_create() {
                                 for path in &e.paths {
-                                    if let Some(_tab) = self
-                                        .tabs
+                                    // A file can be watched by its own tab and
+                                    // the tab of its directory
+                                    self.tabs
                                         .iter_mut()
-                                        .find(|t| t.view.source.path() == Some(path.as_path()))
-                                    {
-
-                                        //tab.set_source(Source::from_path(path)) TODO
-                                    }
+                                        .filter(|t| t.view.source.watches(path))
+                                        .for_each(Tab::file_changed);
                                 }
                             }
                         }
@@ -256,6 +783,23 @@ macro_rules! prim {
     };
 }
 
+/// Numbers formatted by `fmt`, aligned on their decimal point
+fn nb_dsp<D: Display>(
+    array: &dyn Array,
+    bd: &mut ColBuilder,
+    skip: usize,
+    take: usize,
+    fmt: impl Fn(usize) -> D,
+) {
+    for i in (0..array.len()).skip(skip).take(take) {
+        if array.is_null(i) {
+            bd.add_null()
+        } else {
+            bd.add_nb_dsp(fmt(i))
+        }
+    }
+}
+
 pub fn array_to_iter<'a>(
     array: &'a ArrayRef,
     bd: &mut ColBuilder<'a, '_>,
@@ -296,8 +840,36 @@ pub fn array_to_iter<'a>(
         }
         DataType::LargeUtf8 => iter!(array.as_string::<i64>(), bd, skip, take, add_str),
         DataType::Decimal128(_, _) => {
-            let array: &Decimal128Array = array.as_any().downcast_ref().unwrap();
-            iter!(array, bd, skip, take, add_nb)
+            let array = array.as_primitive::<Decimal128Type>();
+            nb_dsp(array, bd, skip, take, |i| array.value_as_string(i))
+        }
+        DataType::Decimal256(_, _) => {
+            let array = array.as_primitive::<Decimal256Type>();
+            nb_dsp(array, bd, skip, take, |i| array.value_as_string(i))
+        }
+        DataType::Duration(unit) => {
+            let (values, digits) = match unit {
+                TimeUnit::Second => (array.as_primitive::<DurationSecondType>().values(), 0),
+                TimeUnit::Millisecond => {
+                    (array.as_primitive::<DurationMillisecondType>().values(), 3)
+                }
+                TimeUnit::Microsecond => {
+                    (array.as_primitive::<DurationMicrosecondType>().values(), 6)
+                }
+                TimeUnit::Nanosecond => {
+                    (array.as_primitive::<DurationNanosecondType>().values(), 9)
+                }
+            };
+            nb_dsp(array.as_ref(), bd, skip, take, |i| Seconds {
+                value: values[i],
+                digits,
+            })
+        }
+        DataType::Interval(_) => {
+            let fmt =
+                ArrayFormatter::try_new(array, &FormatOptions::default().with_display_error(false))
+                    .unwrap();
+            nb_dsp(array.as_ref(), bd, skip, take, |i| fmt.value(i))
         }
         _ => {
             let fmt =
@@ -0,0 +1,81 @@
+use std::{
+    io::{BufRead, BufReader, Cursor},
+    sync::Arc,
+};
+
+use arrow::{
+    datatypes::SchemaRef,
+    json::{reader::infer_json_schema, ReaderBuilder},
+    record_batch::RecordBatch,
+};
+use tungstenite::Message;
+
+use crate::error::Result;
+
+/// Default number of most recent rows kept from a live endpoint
+pub const RETAIN_ROWS: usize = 100_000;
+
+/// Subscribe to a live endpoint, each of its messages holds json records
+/// decoded into a batch with the schema inferred from the first message,
+/// messages that do not match it are skipped
+pub fn subscribe(url: &str) -> Result<impl Iterator<Item = Result<RecordBatch>>> {
+    let mut messages = messages(url)?;
+    let first = messages
+        .next()
+        .ok_or("Endpoint closed without sending any record")??;
+    let (schema, _) = infer_json_schema(Cursor::new(&first), None)?;
+    let schema = Arc::new(schema);
+    Ok(std::iter::once(Ok(first))
+        .chain(messages)
+        .filter_map(move |msg| match msg {
+            Ok(msg) => decode(&schema, &msg).ok().flatten().map(Ok),
+            Err(err) => Some(Err(err)),
+        }))
+}
+
+/// Records of a message, a fresh decoder keeps a bad message from leaving
+/// partial rows behind
+fn decode(schema: &SchemaRef, msg: &str) -> Result<Option<RecordBatch>> {
+    let mut decoder = ReaderBuilder::new(schema.clone()).build_decoder()?;
+    decoder.decode(msg.as_bytes())?;
+    Ok(decoder.flush()?)
+}
+
+/// Text of the messages of a WebSocket, or of the events of an http stream
+fn messages(url: &str) -> Result<Box<dyn Iterator<Item = Result<String>>>> {
+    if url.starts_with("ws://") || url.starts_with("wss://") {
+        let (mut socket, _) = tungstenite::connect(url)?;
+        Ok(Box::new(std::iter::from_fn(move || loop {
+            return match socket.read() {
+                Ok(Message::Text(text)) => Some(Ok(text)),
+                Ok(Message::Binary(bytes)) => Some(String::from_utf8(bytes).map_err(|e| e.into())),
+                Ok(Message::Close(_)) => None,
+                Ok(_) => continue,
+                Err(tungstenite::Error::ConnectionClosed) => None,
+                Err(err) => Some(Err(err.into())),
+            };
+        })))
+    } else {
+        let response = ureq::get(url).set("Accept", "text/event-stream").call()?;
+        let mut lines = BufReader::new(response.into_reader()).lines();
+        Ok(Box::new(std::iter::from_fn(move || {
+            // An event spans its data lines up to a blank line
+            let mut data = String::new();
+            loop {
+                match lines.next() {
+                    Some(Ok(line)) if line.is_empty() && !data.is_empty() => return Some(Ok(data)),
+                    Some(Ok(line)) => {
+                        if let Some(value) = line.strip_prefix("data:") {
+                            if !data.is_empty() {
+                                data.push('\n');
+                            }
+                            data.push_str(value.strip_prefix(' ').unwrap_or(value));
+                        }
+                    }
+                    Some(Err(err)) => return Some(Err(err.into())),
+                    None => return (!data.is_empty()).then_some(Ok(data)),
+                }
+            }
+        })))
+    }
+}
@@ -1,4 +1,9 @@
-use std::path::PathBuf;
+use std::{
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use clap::Parser;
 
@@ -10,14 +15,265 @@ pub struct Args {
     pub files: Vec<PathBuf>,
     #[arg(long)]
     pub sql: Option<String>,
+    /// Re-execute queries periodically (e.g. 500ms, 30s, 5m, 1h)
+    #[arg(long, value_parser = parse_duration)]
+    pub watch: Option<Duration>,
+    /// Number of viewports loaded ahead when scrolling down
+    #[arg(long, default_value_t = 5)]
+    pub prefetch: usize,
+    /// Keep loading rows in the background up to this memory size in MB
+    #[arg(long, value_name = "MB")]
+    pub idle_load: Option<usize>,
+    /// Maximum number of queries executing at once
+    #[arg(long, default_value_t = 4)]
+    pub jobs: usize,
+    /// Load all files at startup instead of when their tab is first focused
+    #[arg(long)]
+    pub preload: bool,
+    /// Drop the data of tabs not focused for this long (e.g. 10m, 1h)
+    #[arg(long, value_parser = parse_duration)]
+    pub evict: Option<Duration>,
+    /// Maximum width of columns, for all (e.g. 30) or per kind (e.g. text=40,number=15) [default: 25]
+    #[arg(long, value_parser = dtex::MaxWidth::parse)]
+    pub max_width: Option<dtex::MaxWidth>,
+    /// Separator between columns: line, space, dotted or none
+    #[arg(long, value_parser = dtex::Separator::parse, default_value = "line")]
+    pub separator: dtex::Separator,
+    /// Draw a horizontal rule under the header
+    #[arg(long)]
+    pub header_rule: bool,
+    /// Share column projection and sizes between tabs with the same schema
+    #[arg(long)]
+    pub share_layout: bool,
+    /// Screen reader friendly rendering, announcing the current cell on its own line
+    #[arg(long)]
+    pub accessible: bool,
+    /// Keep the applied queries in memory only, without writing the history file
+    #[arg(long)]
+    pub no_history: bool,
+    /// Command opening the file of a tab with Ctrl+O (default: $VISUAL, $EDITOR or the system opener)
+    #[arg(long, value_name = "CMD")]
+    pub open_with: Option<String>,
+    /// Print CSV instead of a table when stdout is not a terminal
+    #[arg(long)]
+    pub csv: bool,
+    /// Options of the file readers (e.g. "delim=';', header=true")
+    #[arg(long, value_name = "OPTIONS")]
+    pub read_options: Option<String>,
+    /// Match the columns of multiple files by name instead of position
+    #[arg(long)]
+    pub union_by_name: bool,
+    /// Skip the malformed rows of csv files instead of failing, list them with #
+    #[arg(long)]
+    pub ignore_errors: bool,
+    /// Delimiter of csv files instead of the detected one (e.g. ';' or '\t')
+    #[arg(long, value_name = "CHAR")]
+    pub delim: Option<String>,
+    /// Quote character of csv files instead of the detected one
+    #[arg(long, value_name = "CHAR")]
+    pub quote: Option<String>,
+    /// Read the first line of csv files as data instead of column names
+    #[arg(long)]
+    pub no_header: bool,
+    /// Number of lines skipped at the start of csv files
+    #[arg(long, value_name = "ROWS")]
+    pub skip: Option<usize>,
+    /// Encoding of csv and json files instead of the detected one (e.g. latin-1, windows-1252, shift-jis)
+    #[arg(long, value_name = "LABEL")]
+    pub encoding: Option<String>,
+    /// Open a sample of files larger than this on network storage, and of remote objects, 0 to disable
+    #[arg(long, value_name = "MB", default_value_t = 1024)]
+    pub sample_above: u64,
+    /// Follow the json records of a WebSocket (ws://, wss://) or server-sent events (http://, https://) endpoint
+    #[cfg(feature = "live")]
+    #[arg(long, value_name = "URL")]
+    pub live: Vec<String>,
+    /// Number of most recent rows kept from live endpoints
+    #[cfg(feature = "live")]
+    #[arg(long, value_name = "ROWS", default_value_t = dtex::RETAIN_ROWS)]
+    pub retain: usize,
+    /// Print versions, extensions, terminal capabilities and paths as json
+    #[arg(long)]
+    pub diagnose: bool,
+    /// Compare the schema of two files
+    #[arg(long, num_args = 2, value_names = ["OLD", "NEW"])]
+    pub schema_diff: Option<Vec<PathBuf>>,
 }
 
 fn main() {
     let args = Args::parse();
-    dtex::run(
-        args.files
-            .into_iter()
-            .map(|p| dtex::Source::from_path(&p))
-            .chain(args.sql.map(|s| dtex::Source::empty("shell".into()).query(s))),
+    if args.diagnose {
+        println!("{}", dtex::diagnose());
+        return;
+    }
+    if let Err(err) = dtex::duckdb_version() {
+        eprintln!("{}", err.0);
+        std::process::exit(1)
+    }
+    let config = dtex::Options::from_config().unwrap_or_else(|err| {
+        eprintln!("{}", err.0);
+        std::process::exit(1)
+    });
+    if let Some(paths) = args.schema_diff {
+        let [old, new] = [&paths[0], &paths[1]].map(|p| or_exit(p, dtex::Source::from_path(p)));
+        let diff = dtex::Source::schema_diff(Arc::new(old), Arc::new(new));
+        dtex::run(std::iter::once(or_exit("schema diff", diff)));
+        return;
+    }
+    let read_options = match (args.read_options, args.union_by_name) {
+        (Some(options), true) => Some(format!("{options}, {}", dtex::UNION_BY_NAME)),
+        (None, true) => Some(dtex::UNION_BY_NAME.into()),
+        (options, false) => options,
+    };
+    // Dialect of csv files, auto-detection is often fooled by european files
+    let csv_options: Vec<String> = [
+        args.delim.map(|c| format!("delim={}", quote(&c))),
+        args.quote.map(|c| format!("quote={}", quote(&c))),
+        args.no_header.then(|| "header=false".into()),
+        args.skip.map(|rows| format!("skip={rows}")),
+        args.ignore_errors.then(|| "ignore_errors=true".into()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let text_options: Vec<String> = args
+        .encoding
+        .map(|label| format!("encoding={}", quote(&label)))
+        .into_iter()
+        .collect();
+    let sources = args
+        .files
+        .into_iter()
+        .map(|p| {
+            let source = open(&p, read_options.as_deref(), &csv_options, &text_options);
+            or_exit(&p, source)
+        })
+        .map(|s| match args.sample_above {
+            0 => s,
+            mb => s.guarded(mb.saturating_mul(1024 * 1024)),
+        });
+    #[cfg(feature = "live")]
+    let sources = sources.chain(
+        args.live
+            .iter()
+            .map(|url| or_exit(url, dtex::Source::live(url, args.retain))),
+    );
+    let sources = sources.chain(args.sql.map(|s| {
+        let source = or_exit("shell", dtex::Source::empty("shell".into()));
+        source.query(s)
+    }));
+    if !io::stdout().is_terminal() {
+        // Escape codes would only garble a pipe or a file, print the data instead
+        if let Err(err) = dtex::print(sources, args.csv) {
+            eprintln!("{}", err.0);
+            std::process::exit(1)
+        }
+        return;
+    }
+    dtex::run_with(
+        sources,
+        dtex::Options {
+            watch: args.watch,
+            prefetch: args.prefetch,
+            idle_load: args.idle_load.map(|mb| mb.saturating_mul(1024 * 1024)),
+            max_tasks: args.jobs,
+            preload: args.preload,
+            evict_after: args.evict,
+            max_width: args.max_width.unwrap_or(config.max_width),
+            theme: config.theme,
+            borders: dtex::Borders {
+                separator: args.separator,
+                header_rule: args.header_rule,
+            },
+            share_layout: args.share_layout,
+            accessible: args.accessible,
+            history: !args.no_history,
+            opener: args.open_with,
+        },
     );
 }
+
+/// Open a file source with the options applying to its format
+fn open(
+    path: &Path,
+    read_options: Option<&str>,
+    csv_options: &[String],
+    text_options: &[String],
+) -> dtex::Result<dtex::Source> {
+    let source = dtex::Source::from_path(path)?;
+    let name = path.to_string_lossy();
+    let plain = name.trim_end_matches(".gz").trim_end_matches(".zst");
+    let csv = plain.ends_with(".csv") || plain.ends_with(".tsv");
+    let json = [".json", ".jsonl", ".ndjson"]
+        .iter()
+        .any(|ext| plain.ends_with(ext));
+    let mut options: Vec<String> = read_options.map(String::from).into_iter().collect();
+    if csv {
+        options.extend_from_slice(csv_options);
+    }
+    // Compressed files are not transcoded
+    if (csv || json) && plain.len() == name.len() {
+        options.extend_from_slice(text_options);
+    }
+    if options.is_empty() {
+        return Ok(source);
+    }
+    source.with_read_options(options.join(", "))
+}
+
+/// Value of a result, exit with its error about `what` otherwise
+fn or_exit<T>(what: impl AsRef<Path>, result: dtex::Result<T>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("{}: {}", what.as_ref().display(), err.0);
+        std::process::exit(1)
+    })
+}
+
+/// Sql string literal of a character option
+fn quote(c: &str) -> String {
+    format!("'{}'", c.replace('\'', "''"))
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let pos = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (nb, unit) = s.split_at(pos);
+    let nb: u64 = nb.parse().map_err(|_| format!("invalid duration '{s}'"))?;
+    let secs = |factor: u64| {
+        nb.checked_mul(factor)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("duration too long '{s}'"))
+    };
+    match unit {
+        "ms" => Ok(Duration::from_millis(nb)),
+        "s" | "" => secs(1),
+        "m" => secs(60),
+        "h" => secs(3600),
+        _ => Err(format!("invalid duration unit '{unit}'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("5s"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_duration("5"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("0s"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn invalid_durations() {
+        for s in [
+            "", "s", "ms", "-1s", "1.5s", " 1s", "1 s", "1d", "1S", "1sec", "1hm",
+        ] {
+            assert!(parse_duration(s).is_err(), "{s}");
+        }
+        assert!(parse_duration("99999999999999999999s").is_err());
+        assert!(parse_duration(&format!("{}h", u64::MAX)).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use libduckdb_sys::duckdb_query_progress_type;
+use tui::{unicode_width::UnicodeWidthStr, Canvas};
+
+use crate::{
+    chart::{self, Chart},
+    error::Result,
+    fmt::rtrim,
+    source::{DataFrame, Source},
+    sql, style,
+    task::{DuckTask, Runner},
+};
+
+/// Number of row ranges of the heatmap
+const SEGMENTS: usize = 64;
+/// Width of the null percentage bars
+const BAR: usize = 20;
+const MAX_NAME: usize = 24;
+const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+/// Per column null percentage and their distribution over row ranges
+pub struct MissingView {
+    names: Vec<String>,
+    task: Option<DuckTask<Vec<Vec<Option<f64>>>>>,
+    /// Number of rows and non null count per column for each segment
+    segments: Vec<(f64, Vec<f64>)>,
+    error: Option<String>,
+}
+
+impl MissingView {
+    pub fn new(source: Arc<Source>, names: Vec<String>, runner: &Runner) -> Self {
+        let counts: Vec<_> = std::iter::once("count(*)".into())
+            .chain(
+                names
+                    .iter()
+                    .map(|name| format!("count({})", sql::ident(name))),
+            )
+            .collect();
+        let counts = counts.join(", ");
+        Self {
+            task: Some(runner.duckdb(source, move |source, con| {
+                let sql = format!(
+                    "SELECT {counts} FROM (SELECT ntile({SEGMENTS}) OVER () AS __seg, * FROM ({})) GROUP BY __seg ORDER BY __seg",
+                    source.load_sql()
+                );
+                let df = con
+                    .query(&sql)?
+                    .map(|d| d.map_err(|e| e.into()))
+                    .collect::<Result<DataFrame>>()?;
+                chart::columns(&df)
+            })),
+            names,
+            segments: vec![],
+            error: None,
+        }
+    }
+
+    /// Fraction of null of a column over a range of segments
+    fn null_ratio(&self, idx: usize, segments: &[(f64, Vec<f64>)]) -> f64 {
+        let (rows, values) = segments.iter().fold((0., 0.), |(r, v), (rows, values)| {
+            (r + rows, v + values[idx])
+        });
+        if rows == 0. {
+            0.
+        } else {
+            1. - values / rows
+        }
+    }
+}
+
+impl Chart for MissingView {
+    fn label(&self) -> &'static str {
+        "NULL"
+    }
+
+    fn tick(&mut self) -> Option<(&'static str, duckdb_query_progress_type)> {
+        match self.task.as_mut().and_then(|t| t.tick()) {
+            Some(Ok(cols)) => {
+                self.segments = (0..cols[0].len())
+                    .map(|row| {
+                        let rows = cols[0][row].unwrap_or(0.);
+                        let values = cols[1..].iter().map(|c| c[row].unwrap_or(0.)).collect();
+                        (rows, values)
+                    })
+                    .collect();
+                self.task = None;
+            }
+            Some(Err(it)) => {
+                self.error = Some(it.0);
+                self.task = None;
+            }
+            None => {}
+        }
+        self.task.as_ref().map(|t| ("nulls", t.progress()))
+    }
+
+    fn err(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn draw(&self, c: &mut Canvas) {
+        if self.segments.is_empty() {
+            return;
+        }
+        let name_width = self
+            .names
+            .iter()
+            .map(|n| n.width())
+            .max()
+            .unwrap_or(0)
+            .min(MAX_NAME);
+        let heatmap = c
+            .width()
+            .saturating_sub(name_width + BAR + 10)
+            .min(self.segments.len());
+        let mut buf = String::new();
+        for (idx, name) in self.names.iter().enumerate().take(c.height()) {
+            let ratio = self.null_ratio(idx, &self.segments);
+            let mut l = c.top();
+            l.draw(
+                format_args!("{:<name_width$} ", rtrim(name, &mut buf, name_width)),
+                style::primary(),
+            );
+            let filled = (ratio * BAR as f64).ceil() as usize;
+            l.draw("█".repeat(filled), style::error());
+            l.draw("░".repeat(BAR - filled), style::separator());
+            l.draw(format_args!(" {:>5.1}% ", ratio * 100.), style::index());
+            // Downsample segments to the available width
+            for i in 0..heatmap {
+                let range = i * self.segments.len() / heatmap
+                    ..((i + 1) * self.segments.len() / heatmap).max(i + 1);
+                let ratio = self.null_ratio(idx, &self.segments[range]);
+                let shade = if ratio == 0. {
+                    0
+                } else {
+                    1 + ((ratio * 4.) as usize).min(3)
+                };
+                l.draw(SHADES[shade], style::error());
+            }
+        }
+    }
+}
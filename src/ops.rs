@@ -0,0 +1,81 @@
+use std::fmt::Write;
+
+use crate::sql;
+
+/// Transformations applied on top of a source query
+#[derive(Clone, Default)]
+pub struct Ops {
+    /// Prepend a column with the position of each row in the source
+    pub row_id: bool,
+    /// Column replacement expressions by column name
+    pub casts: Vec<(String, String)>,
+    /// Computed columns appended to the source ones
+    pub columns: Vec<String>,
+    /// Predicates rows must all satisfy
+    pub filters: Vec<String>,
+    /// Columns rows must have a value in
+    pub not_null: Vec<String>,
+    /// Sort keys by priority as column name and descending flag
+    pub sort: Vec<(String, bool)>,
+}
+
+impl Ops {
+    pub fn is_empty(&self) -> bool {
+        !self.row_id
+            && self.casts.is_empty()
+            && self.columns.is_empty()
+            && self.filters.is_empty()
+            && self.not_null.is_empty()
+            && self.sort.is_empty()
+    }
+
+    /// Wrap a query with the transformations
+    pub fn apply(&self, query: &str) -> String {
+        self.select(query, "*")
+    }
+
+    /// Wrap a query with the transformations, only selecting `columns` at
+    /// the end
+    pub fn select(&self, query: &str, columns: &str) -> String {
+        if query.is_empty() || (self.is_empty() && columns == "*") {
+            return query.into();
+        }
+        // Each layer can reference the columns of the previous one
+        let mut sql = query.to_string();
+        if self.row_id {
+            sql = format!("SELECT row_number() OVER () - 1 AS row_id, * FROM ({sql})");
+        }
+        if !self.casts.is_empty() {
+            let replace: Vec<_> = self
+                .casts
+                .iter()
+                .map(|(name, expr)| format!("{expr} AS {}", sql::ident(name)))
+                .collect();
+            sql = format!("SELECT * REPLACE ({}) FROM ({sql})", replace.join(", "));
+        }
+        if !self.columns.is_empty() {
+            sql = format!("SELECT *, {} FROM ({sql})", self.columns.join(", "));
+        }
+        if !self.filters.is_empty()
+            || !self.not_null.is_empty()
+            || !self.sort.is_empty()
+            || columns != "*"
+        {
+            sql = format!("SELECT {columns} FROM ({sql})");
+        }
+        let not_null = self
+            .not_null
+            .iter()
+            .map(|name| format!("{} IS NOT NULL", sql::ident(name)));
+        for (i, filter) in self.filters.iter().cloned().chain(not_null).enumerate() {
+            let sep = if i == 0 { " WHERE" } else { " AND" };
+            write!(sql, "{sep} ({filter})").unwrap();
+        }
+        for (i, (name, desc)) in self.sort.iter().enumerate() {
+            let sep = if i == 0 { " ORDER BY" } else { "," };
+            let order = if *desc { "DESC" } else { "ASC" };
+            write!(sql, "{sep} {} {order}", sql::ident(name)).unwrap();
+        }
+        sql
+    }
+}
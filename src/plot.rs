@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use libduckdb_sys::duckdb_query_progress_type;
+use tui::Canvas;
+
+use crate::{
+    chart::{self, bounds, Braille, Chart},
+    error::Result,
+    source::{DataFrame, Source},
+    style,
+    task::{DuckTask, Runner},
+};
+
+/// Maximum number of points sampled
+const SAMPLE: usize = 10_000;
+
+/// Scatter plot of one or more columns against another
+pub struct PlotView {
+    x: String,
+    ys: Vec<String>,
+    task: Option<DuckTask<Vec<Vec<Option<f64>>>>>,
+    points: Vec<Vec<Option<f64>>>,
+    error: Option<String>,
+}
+
+impl PlotView {
+    /// Plot columns given as (name, numeric expression), sampled by duckdb
+    pub fn new(
+        source: Arc<Source>,
+        x: (String, String),
+        ys: Vec<(String, String)>,
+        runner: &Runner,
+    ) -> Self {
+        let exprs: Vec<_> = std::iter::once(&x)
+            .chain(&ys)
+            .map(|(_, expr)| expr.as_str())
+            .collect();
+        let exprs = exprs.join(", ");
+        Self {
+            task: Some(runner.duckdb(source, move |source, con| {
+                let sql = format!(
+                    "SELECT {exprs} FROM ({}) USING SAMPLE reservoir({SAMPLE} ROWS)",
+                    source.load_sql()
+                );
+                let df = con
+                    .query(&sql)?
+                    .map(|d| d.map_err(|e| e.into()))
+                    .collect::<Result<DataFrame>>()?;
+                chart::columns(&df)
+            })),
+            x: x.0,
+            ys: ys.into_iter().map(|(name, _)| name).collect(),
+            points: vec![],
+            error: None,
+        }
+    }
+}
+
+impl Chart for PlotView {
+    fn label(&self) -> &'static str {
+        "PLOT"
+    }
+
+    fn tick(&mut self) -> Option<(&'static str, duckdb_query_progress_type)> {
+        match self.task.as_mut().and_then(|t| t.tick()) {
+            Some(Ok(points)) => {
+                self.points = points;
+                self.task = None;
+            }
+            Some(Err(it)) => {
+                self.error = Some(it.0);
+                self.task = None;
+            }
+            None => {}
+        }
+        self.task.as_ref().map(|t| ("plot", t.progress()))
+    }
+
+    fn err(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn draw(&self, c: &mut Canvas) {
+        // Legend
+        {
+            let mut l = c.top();
+            l.draw(format_args!("{} →", self.x), style::index());
+            for (i, name) in self.ys.iter().enumerate() {
+                l.draw(format_args!(" ● {name}"), style::series(i));
+            }
+        }
+
+        let Some((xs, ys)) = self.points.split_first() else {
+            return;
+        };
+        let x_bounds = bounds(xs.iter().flatten().copied());
+        let y_bounds = bounds(ys.iter().flatten().flatten().copied());
+        let height = c.height().saturating_sub(1);
+        let width = c.width().saturating_sub(chart::gutter(y_bounds) + 1);
+        let mut braille = Braille::new(width, height);
+        let norm = |v: f64, (min, max): (f64, f64)| (v - min) / (max - min);
+        for (series, y) in ys.iter().enumerate() {
+            for (x, y) in xs.iter().zip(y) {
+                if let (Some(x), Some(y)) = (x, y) {
+                    braille.dot(norm(*x, x_bounds), norm(*y, y_bounds), series);
+                }
+            }
+        }
+        let gutter = chart::draw_y_axis(c, y_bounds, height, |r, buf| braille.row(r, buf));
+        chart::draw_x_axis(c, gutter, x_bounds);
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    fmt::Write as _,
+    io::{self, BufWriter, Write},
+};
+
+use crate::{
+    error::Result,
+    export,
+    fmt::{rtrim, str_width, GridBuffer},
+    source::{DataFrame, Source},
+};
+
+/// Maximum width of formatted cells, columns are narrower anyway
+const CELL_WIDTH: usize = 256;
+
+/// Print the result of each source as plain text, used instead of the
+/// terminal UI when stdout is redirected
+pub fn print(sources: impl Iterator<Item = Source>, csv: bool) -> Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    for (i, source) in sources.enumerate() {
+        if source.init_sql().is_empty() {
+            continue;
+        }
+        if i > 0 && !write(&mut out, b"\n")? {
+            return Ok(());
+        }
+        let chunks = source.load(source.conn()?)?;
+        // Column sizes are fixed by the first batch so rows stay aligned while streaming
+        let mut widths: Option<Vec<usize>> = None;
+        let mut buf = GridBuffer::new();
+        let mut text = String::new();
+        for (n, batch) in chunks.enumerate() {
+            let batch = batch?;
+            text.clear();
+            if csv {
+                export::csv(&batch, n == 0, &mut text)?;
+            } else {
+                let df = DataFrame::from(batch);
+                let widths = widths.get_or_insert_with(|| header(&df, &mut buf, &mut text));
+                table(&df, widths, &mut buf, &mut text);
+            }
+            if !write(&mut out, text.as_bytes())? {
+                return Ok(());
+            }
+        }
+    }
+    match out.flush() {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        res => Ok(res?),
+    }
+}
+
+/// Write to the output, false if the reader went away (e.g. `| head`)
+fn write(out: &mut impl Write, bytes: &[u8]) -> Result<bool> {
+    match out.write_all(bytes) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Size columns from the first rows and write the header line, values are
+/// never truncated unlike in the grid
+fn header(df: &DataFrame, buf: &mut GridBuffer, text: &mut String) -> Vec<usize> {
+    buf.new_frame(CELL_WIDTH);
+    let mut line = String::new();
+    let widths: Vec<_> = df
+        .schema()
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            let col = df.iter(buf, idx, 0, df.num_rows());
+            let width = col.budget().max(str_width(field.name()));
+            write!(
+                line,
+                "{:<1$}  ",
+                rtrim(field.name(), buf.fmt_buf(), width),
+                width
+            )
+            .unwrap();
+            width
+        })
+        .collect();
+    text.push_str(line.trim_end());
+    text.push('\n');
+    widths
+}
+
+/// Write rows aligned on the header columns, wider values of later rows
+/// overflow their column instead of being truncated
+fn table(df: &DataFrame, widths: &[usize], buf: &mut GridBuffer, text: &mut String) {
+    buf.new_frame(CELL_WIDTH);
+    let mut lines = vec![String::new(); df.num_rows()];
+    for (idx, width) in widths.iter().enumerate() {
+        let col = df.iter(buf, idx, 0, df.num_rows());
+        let width = col.budget().max(*width);
+        for (row, line) in lines.iter_mut().enumerate() {
+            line.push_str(col.fmt(buf, row, width));
+            line.push_str("  ");
+        }
+    }
+    for line in lines {
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+}
@@ -0,0 +1,65 @@
+use std::{fmt::Display, time::Duration};
+
+use libduckdb_sys::duckdb_query_progress_type;
+
+use crate::style::Glyphs;
+
+/// Width of the bar in the status line
+pub const STATUS_BAR: usize = 10;
+/// Width of the bar shown in place of an empty frame
+pub const SCREEN_BAR: usize = 30;
+/// Completed ratio before estimating the remaining time, earlier estimates
+/// swing too much to be useful
+const ETA_MIN_RATIO: f64 = 0.02;
+
+/// Determinate progress of a query with an estimate of the remaining time
+pub struct Progress {
+    ratio: f64,
+    eta: Option<Duration>,
+}
+
+impl Progress {
+    /// None while duckdb cannot tell the query progress
+    pub fn new(progress: &duckdb_query_progress_type, elapsed: Option<Duration>) -> Option<Self> {
+        (progress.percentage > 0.).then(|| {
+            let ratio = (progress.percentage / 100.).min(1.);
+            let eta = elapsed
+                .filter(|_| ratio >= ETA_MIN_RATIO)
+                .map(|elapsed| elapsed.mul_f64((1. - ratio) / ratio));
+            Self { ratio, eta }
+        })
+    }
+
+    /// Bar of `width` cells filled with the completed ratio
+    pub fn bar(&self, width: usize, glyphs: &Glyphs) -> String {
+        let (done, todo) = glyphs.bar;
+        let filled = ((self.ratio * width as f64).round() as usize).min(width);
+        done.repeat(filled) + &todo.repeat(width - filled)
+    }
+}
+
+impl Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:>3.0}%", self.ratio * 100.)?;
+        if let Some(eta) = self.eta {
+            write!(f, " ETA {}", Eta(eta))?;
+        }
+        Ok(())
+    }
+}
+
+/// Remaining time rounded to its two most significant units
+struct Eta(Duration);
+
+impl Display for Eta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.0.as_secs();
+        if secs < 60 {
+            write!(f, "{secs}s")
+        } else if secs < 3600 {
+            write!(f, "{}m{:02}s", secs / 60, secs % 60)
+        } else {
+            write!(f, "{}h{:02}m", secs / 3600, secs % 3600 / 60)
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{error::Result, source::DataFrame};
+
+/// Custom data provider, such as an internal REST API or a proprietary
+/// binary format, that dtex renders and queries like any other tab
+pub trait Provider: Send + Sync + 'static {
+    /// Every row of the data, queried by duckdb as the `current` view and
+    /// scanned again when the tab is refreshed
+    fn scan(&self) -> Result<DataFrame>;
+
+    /// Evaluate transformations closer to the data instead of letting duckdb
+    /// run them over the scanned rows, None when unsupported
+    fn pushdown(&self, _pushdown: &Pushdown) -> Option<Result<DataFrame>> {
+        None
+    }
+}
+
+/// Transformations applied on top of the data of a provider, only offered
+/// when rows are not filtered by any other predicate
+pub struct Pushdown<'a> {
+    /// Columns rows must have a value in
+    pub not_null: &'a [String],
+    /// Sort keys by priority as column name and descending flag
+    pub sort: &'a [(String, bool)],
+}
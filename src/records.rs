@@ -0,0 +1,67 @@
+use std::{
+    io::{ErrorKind, Read},
+    sync::Arc,
+};
+
+use arrow::{
+    json::{reader::infer_json_schema_from_iterator, ReaderBuilder},
+    record_batch::RecordBatch,
+};
+use serde_json::Value;
+
+use crate::error::Result;
+
+/// Number of records decoded per batch, the schema is inferred from the first
+/// batch
+const BATCH_ROWS: usize = 1024;
+
+/// Batches of a stream of concatenated MessagePack records
+pub fn msgpack(mut reader: impl Read) -> Result<impl Iterator<Item = Result<RecordBatch>>> {
+    batches(move || match rmpv::decode::read_value(&mut reader) {
+        Ok(value) => Ok(Some(serde_json::to_value(value)?)),
+        Err(rmpv::decode::Error::InvalidMarkerRead(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+            Ok(None)
+        }
+        Err(err) => Err(err.into()),
+    })
+}
+
+/// Batches of a stream of concatenated BSON documents
+pub fn bson(mut reader: impl Read) -> Result<impl Iterator<Item = Result<RecordBatch>>> {
+    batches(move || match bson::Document::from_reader(&mut reader) {
+        Ok(doc) => Ok(Some(bson::Bson::Document(doc).into_relaxed_extjson())),
+        Err(bson::de::Error::Io(e)) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+        Err(err) => Err(err.into()),
+    })
+}
+
+/// Decode records in batches until `next` returns None
+fn batches(
+    mut next: impl FnMut() -> Result<Option<Value>>,
+) -> Result<impl Iterator<Item = Result<RecordBatch>>> {
+    let first = chunk(&mut next)?;
+    let schema = infer_json_schema_from_iterator(first.iter().map(Ok))?;
+    let mut decoder = ReaderBuilder::new(Arc::new(schema)).build_decoder()?;
+    let mut pending = Some(Ok(first));
+    Ok(std::iter::from_fn(move || {
+        let values = match pending.take().unwrap_or_else(|| chunk(&mut next)) {
+            Ok(values) if values.is_empty() => return None,
+            Ok(values) => values,
+            Err(err) => return Some(Err(err)),
+        };
+        let batch = decoder.serialize(&values).and_then(|_| decoder.flush());
+        batch.map_err(|e| e.into()).transpose()
+    }))
+}
+
+/// Next records, fewer than `BATCH_ROWS` at the end of the stream
+fn chunk(next: &mut impl FnMut() -> Result<Option<Value>>) -> Result<Vec<Value>> {
+    let mut values = Vec::new();
+    while values.len() < BATCH_ROWS {
+        match next()? {
+            Some(value) => values.push(value),
+            None => break,
+        }
+    }
+    Ok(values)
+}
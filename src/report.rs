@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use crate::{
+    error::Result,
+    grid::{Grid, Settings},
+    source::{DataFrame, Source},
+    task::{DuckTask, Runner},
+    view::{View, ViewState},
+};
+
+/// Result of an analysis query over a source
+pub struct ReportView {
+    label: &'static str,
+    task: Option<DuckTask<DataFrame>>,
+    df: DataFrame,
+    error: Option<String>,
+    pub grid: Grid,
+    /// Filter selecting the source rows of each report row
+    pub filters: Vec<String>,
+}
+
+impl ReportView {
+    /// Run the query built from the source load query
+    pub fn new(
+        label: &'static str,
+        source: Arc<Source>,
+        query: impl FnOnce(&str) -> String + Send + 'static,
+        runner: &Runner,
+        settings: Settings,
+    ) -> Self {
+        Self {
+            label,
+            task: Some(runner.duckdb(source, move |source, con| {
+                con.query(&query(&source.load_sql()))?
+                    .map(|d| d.map_err(|e| e.into()))
+                    .collect::<Result<DataFrame>>()
+            })),
+            df: DataFrame::empty(),
+            error: None,
+            grid: Grid::new(settings),
+            filters: vec![],
+        }
+    }
+
+    /// Show the result of a custom task
+    pub fn from_task(label: &'static str, task: DuckTask<DataFrame>, settings: Settings) -> Self {
+        Self {
+            label,
+            task: Some(task),
+            df: DataFrame::empty(),
+            error: None,
+            grid: Grid::new(settings),
+            filters: vec![],
+        }
+    }
+
+    pub fn with_filters(mut self, filters: Vec<String>) -> Self {
+        self.filters = filters;
+        self
+    }
+}
+
+impl View for ReportView {
+    fn tick(&mut self) -> ViewState {
+        match self.task.as_mut().and_then(|t| t.tick()) {
+            Some(Ok(df)) => {
+                self.df = df;
+                self.task = None;
+            }
+            Some(Err(it)) => {
+                self.error = Some(it.0);
+                self.task = None;
+            }
+            None => {}
+        }
+
+        ViewState {
+            loading: self
+                .task
+                .as_ref()
+                .map(|t| (self.label.into(), t.progress())),
+            streaming: false,
+            frame: &self.df,
+            grid: &mut self.grid,
+            err: self.error.as_deref(),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::StringArray,
+    datatypes::{DataType, Field, Schema},
+    record_batch::RecordBatch,
+};
+
+use crate::{error::Result, Source};
+
+#[derive(Debug, PartialEq)]
+enum Change<'a> {
+    Same(&'a Field),
+    Retyped(&'a Field, &'a Field),
+    Renamed(&'a Field, &'a Field),
+    Removed(&'a Field),
+    Added(&'a Field),
+}
+
+/// Compare two schemas, a removed and an added column of the same type at the same position
+/// are considered renamed
+fn diff<'a>(old: &'a Schema, new: &'a Schema) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    let mut added: Vec<_> = new
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| old.field_with_name(f.name()).is_err())
+        .collect();
+    for (pos, field) in old.fields().iter().enumerate() {
+        let change = match new.field_with_name(field.name()) {
+            Ok(new) if new.data_type() == field.data_type() => Change::Same(field),
+            Ok(new) => Change::Retyped(field, new),
+            Err(_) => match added
+                .iter()
+                .position(|(p, f)| *p == pos && f.data_type() == field.data_type())
+            {
+                Some(i) => Change::Renamed(field, added.remove(i).1),
+                None => Change::Removed(field),
+            },
+        };
+        changes.push(change);
+    }
+    changes.extend(added.into_iter().map(|(_, f)| Change::Added(f)));
+    changes
+}
+
+/// Schema changes between two sources
+pub fn batch(old: &Source, new: &Source) -> Result<RecordBatch> {
+    let (old_schema, new_schema) = (old.schema()?, new.schema()?);
+    let changes = diff(&old_schema, &new_schema);
+    let ty = |f: &Field| Some(f.data_type().to_string());
+    let rows: Vec<_> = changes
+        .iter()
+        .map(|c| match c {
+            Change::Same(f) => ("same", f.name().clone(), ty(f), ty(f)),
+            Change::Retyped(o, n) => ("retyped", o.name().clone(), ty(o), ty(n)),
+            Change::Renamed(o, n) => (
+                "renamed",
+                format!("{} → {}", o.name(), n.name()),
+                ty(o),
+                ty(n),
+            ),
+            Change::Removed(f) => ("removed", f.name().clone(), ty(f), None),
+            Change::Added(f) => ("added", f.name().clone(), None, ty(f)),
+        })
+        .collect();
+    let schema = Arc::new(Schema::new(
+        ["change", "column", "old_type", "new_type"]
+            .map(|n| Field::new(n, DataType::Utf8, true))
+            .to_vec(),
+    ));
+    Ok(RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.0))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| &r.1))),
+            Arc::new(StringArray::from_iter(rows.iter().map(|r| r.2.as_ref()))),
+            Arc::new(StringArray::from_iter(rows.iter().map(|r| r.3.as_ref()))),
+        ],
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(fields: &[(&str, DataType)]) -> Schema {
+        Schema::new(
+            fields
+                .iter()
+                .map(|(name, ty)| Field::new(*name, ty.clone(), true))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn same() {
+        let old = schema(&[("a", DataType::Int64), ("b", DataType::Utf8)]);
+        let changes = diff(&old, &old);
+        assert_eq!(
+            changes,
+            [Change::Same(old.field(0)), Change::Same(old.field(1))]
+        );
+    }
+
+    #[test]
+    fn added_and_removed() {
+        let old = schema(&[("a", DataType::Int64), ("b", DataType::Utf8)]);
+        let new = schema(&[("a", DataType::Int64), ("c", DataType::Float64)]);
+        // Different type at the same position is not a rename
+        assert_eq!(
+            diff(&old, &new),
+            [
+                Change::Same(old.field(0)),
+                Change::Removed(old.field(1)),
+                Change::Added(new.field(1)),
+            ]
+        );
+        let new = schema(&[
+            ("a", DataType::Int64),
+            ("b", DataType::Utf8),
+            ("c", DataType::Int64),
+        ]);
+        assert_eq!(
+            diff(&new, &old).last(),
+            Some(&Change::Removed(new.field(2)))
+        );
+        assert_eq!(diff(&old, &new).last(), Some(&Change::Added(new.field(2))));
+    }
+
+    #[test]
+    fn retyped() {
+        let old = schema(&[("a", DataType::Int32), ("b", DataType::Utf8)]);
+        let new = schema(&[("a", DataType::Int64), ("b", DataType::Utf8)]);
+        assert_eq!(
+            diff(&old, &new),
+            [
+                Change::Retyped(old.field(0), new.field(0)),
+                Change::Same(old.field(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn renamed() {
+        let old = schema(&[("a", DataType::Int64), ("b", DataType::Utf8)]);
+        let new = schema(&[("a", DataType::Int64), ("name", DataType::Utf8)]);
+        assert_eq!(
+            diff(&old, &new),
+            [
+                Change::Same(old.field(0)),
+                Change::Renamed(old.field(1), new.field(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn reordered() {
+        let old = schema(&[
+            ("a", DataType::Int64),
+            ("b", DataType::Utf8),
+            ("c", DataType::Date32),
+        ]);
+        let new = schema(&[
+            ("c", DataType::Date32),
+            ("a", DataType::Int64),
+            ("b", DataType::Utf8),
+        ]);
+        // Columns are matched by name whatever their position
+        assert_eq!(
+            diff(&old, &new),
+            [
+                Change::Same(old.field(0)),
+                Change::Same(old.field(1)),
+                Change::Same(old.field(2))
+            ]
+        );
+        // Moved columns of the same type are not renames
+        let new = schema(&[("b", DataType::Utf8), ("d", DataType::Int64)]);
+        assert_eq!(
+            diff(&old, &new),
+            [
+                Change::Removed(old.field(0)),
+                Change::Same(old.field(1)),
+                Change::Removed(old.field(2)),
+                Change::Added(new.field(1)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::{fmt::Write, sync::Arc};
+
+use arrow::{
+    array::AsArray,
+    datatypes::{Int32Type, Int64Type},
+    util::display::{ArrayFormatter, FormatOptions},
+};
+use libduckdb_sys::duckdb_query_progress_type;
+
+use crate::{
+    duckdb::Connection,
+    error::Result,
+    source::{DataFrame, Source},
+    sql,
+    task::{DuckTask, Runner, Task},
+};
+
+/// Matching cells as (row, col) in row order
+pub type Matches = Vec<(usize, usize)>;
+
+enum Pending {
+    Duck(DuckTask<Matches>),
+    Mem(Task<(), Option<Matches>>),
+}
+
+/// Background search of a pattern in some columns of every row
+pub struct Search {
+    pub pattern: String,
+    pending: Option<Pending>,
+    matches: Matches,
+    error: Option<String>,
+}
+
+impl Search {
+    /// Search loaded rows in memory or push down to duckdb if the frame is still streaming
+    /// Regex are always pushed down
+    pub fn new(
+        pattern: String,
+        regex: bool,
+        cols: Vec<usize>,
+        source: Arc<Source>,
+        df: DataFrame,
+        streaming: bool,
+        runner: &Runner,
+    ) -> Self {
+        let pending = if streaming || regex {
+            let names: Vec<_> = cols
+                .iter()
+                .map(|i| df.schema().field(*i).name().clone())
+                .collect();
+            let pattern = pattern.clone();
+            Pending::Duck(runner.duckdb(source, move |source, con| {
+                let sql = Self::sql(&source.load_sql(), &names, &cols, &pattern, regex);
+                Self::query(&con, &sql)
+            }))
+        } else {
+            let pattern = pattern.to_lowercase();
+            Pending::Mem(runner.task((), None, move |ctx| {
+                let matches = Self::scan(&df, &cols, &pattern, || ctx.canceled());
+                ctx.lock(|r| *r = Some(matches));
+            }))
+        };
+        Self {
+            pattern,
+            pending: Some(pending),
+            matches: vec![],
+            error: None,
+        }
+    }
+
+    /// Matching cells returned by a search query
+    pub fn query(con: &Connection, sql: &str) -> Result<Matches> {
+        let mut matches = Vec::new();
+        for batch in con.query(sql)? {
+            let batch = batch?;
+            let rows = batch.column(0).as_primitive::<Int64Type>();
+            let cols = batch.column(1).as_primitive::<Int32Type>();
+            matches.extend(
+                rows.values()
+                    .iter()
+                    .zip(cols.values())
+                    .map(|(r, c)| (*r as usize, *c as usize)),
+            );
+        }
+        Ok(matches)
+    }
+
+    /// Multi-column query returning the first matching column of each row
+    pub fn sql(
+        query: &str,
+        names: &[String],
+        cols: &[usize],
+        pattern: &str,
+        regex: bool,
+    ) -> String {
+        let mut case = String::from("CASE");
+        for (name, idx) in names.iter().zip(cols) {
+            write!(
+                case,
+                " WHEN {} THEN {idx}",
+                sql::matches(name, pattern, regex)
+            )
+            .unwrap();
+        }
+        case.push_str(" END");
+        format!(
+            "SELECT __row, __col FROM (SELECT row_number() OVER () - 1 AS __row, {case}::INTEGER AS __col FROM ({query})) WHERE __col IS NOT NULL ORDER BY __row"
+        )
+    }
+
+    /// Case insensitive scan of formatted values
+    fn scan(df: &DataFrame, cols: &[usize], pattern: &str, canceled: impl Fn() -> bool) -> Matches {
+        let options = FormatOptions::default().with_display_error(false);
+        let mut buf = String::new();
+        let mut matches = Vec::new();
+        let mut offset = 0;
+        for batch in &df.0.batchs {
+            if canceled() {
+                break;
+            }
+            let fmts: Vec<_> = cols
+                .iter()
+                .map(|i| {
+                    (
+                        *i,
+                        ArrayFormatter::try_new(batch.column(*i), &options).unwrap(),
+                    )
+                })
+                .collect();
+            for row in 0..batch.num_rows() {
+                for (idx, fmt) in &fmts {
+                    buf.clear();
+                    write!(buf, "{}", fmt.value(row)).unwrap();
+                    if buf.to_lowercase().contains(pattern) {
+                        matches.push((offset + row, *idx));
+                        break;
+                    }
+                }
+            }
+            offset += batch.num_rows();
+        }
+        matches
+    }
+
+    /// Poll background task, return true when the results just arrived
+    pub fn tick(&mut self) -> bool {
+        let result: Option<Result<Matches>> = match &mut self.pending {
+            Some(Pending::Duck(task)) => task.tick(),
+            Some(Pending::Mem(task)) => task.lock(|r| r.take()).map(Ok),
+            None => None,
+        };
+        match result {
+            Some(Ok(matches)) => {
+                self.matches = matches;
+                self.pending = None;
+                true
+            }
+            Some(Err(e)) => {
+                self.error = Some(e.0);
+                self.pending = None;
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_loading(&self) -> Option<duckdb_query_progress_type> {
+        match &self.pending {
+            Some(Pending::Duck(task)) => Some(task.progress()),
+            Some(Pending::Mem(_)) => Some(duckdb_query_progress_type {
+                percentage: -1.,
+                rows_processed: 0,
+                total_rows_to_process: 0,
+            }),
+            None => None,
+        }
+    }
+
+    pub fn err(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn nb_match(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// First match at or after this row, wrapping around
+    pub fn first_from(&self, row: usize) -> Option<(usize, usize)> {
+        let pos = self.matches.partition_point(|(r, _)| *r < row);
+        self.matches
+            .get(pos)
+            .or_else(|| self.matches.first())
+            .copied()
+    }
+
+    /// Last match before this row, wrapping around
+    pub fn prev(&self, row: usize) -> Option<(usize, usize)> {
+        let pos = self.matches.partition_point(|(r, _)| *r < row);
+        pos.checked_sub(1)
+            .and_then(|pos| self.matches.get(pos))
+            .or_else(|| self.matches.last())
+            .copied()
+    }
+}
@@ -1,37 +1,99 @@
+use std::{ops::Range, sync::Arc};
+
 use tui::{
-    crossterm::event::{KeyCode, KeyEvent},
-    unicode_width::UnicodeWidthChar,
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
     Canvas,
 };
+use unicode_segmentation::UnicodeSegmentation;
 
-use crate::{style, OnKey};
+use crate::{fmt::grapheme_width, source::Source, sql, style, task::Runner, OnKey};
 
 use self::{
     highlighter::Highlighter,
     prompt::{Prompt, PromptCmd},
+    signature::Signatures,
+    suggest::{Relations, Suggestion},
 };
 
 mod highlighter;
+mod lint;
 pub mod prompt;
+mod signature;
+mod suggest;
+
+/// Maximum number of prompt lines drawn at once
+const MAX_LINES: usize = 10;
 
 pub struct Shell {
     prompt: Prompt<15>,
     offset: usize,
     pending: bool,
+    /// Query the warnings were computed for
+    linted: String,
+    warnings: Vec<String>,
+    signatures: Signatures,
+    /// Signature of the function call around the cursor
+    hint: Option<String>,
+    relations: Relations,
+    /// Fix of the unknown name of the last error
+    suggestion: Option<Suggestion>,
 }
 
 impl Shell {
     pub fn new(init: &str) -> Self {
         Self {
-            prompt: Prompt::new(&init),
+            prompt: Prompt::new(init),
             offset: 0,
             pending: false,
+            linted: String::new(),
+            warnings: vec![],
+            signatures: Signatures::default(),
+            hint: None,
+            relations: Relations::default(),
+            suggestion: None,
         }
     }
 
+    /// Replace the prompt content
+    pub fn set(&mut self, sql: &str) {
+        self.prompt.set(sql);
+        self.offset = 0;
+    }
+
+    /// Number of lines taken by the prompt
+    pub fn height(&self) -> usize {
+        let (str, _) = self.prompt.state();
+        str.split('\n').count().min(MAX_LINES)
+    }
+
+    /// Write text at the cursor
+    pub fn insert(&mut self, str: &str) {
+        for c in str.chars() {
+            self.prompt.exec(PromptCmd::Write(c));
+        }
+        self.pending = true;
+    }
+
     pub fn on_key(&mut self, event: &KeyEvent) -> (OnKey, Option<&str>, bool) {
         match event.code {
             KeyCode::Esc => return (OnKey::Quit, None, false),
+            KeyCode::Char('f') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                let (str, _) = self.prompt.state();
+                match sql::format(str) {
+                    Ok(formatted) => self.set(&formatted),
+                    Err(e) => self.warnings = vec![format!("cannot format: {}", e.0)],
+                }
+            }
+            KeyCode::Char('y') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                let (str, _) = self.prompt.state();
+                if let Some(fixed) = self
+                    .suggestion
+                    .as_ref()
+                    .and_then(|s| suggest::apply(str, s))
+                {
+                    self.set(&fixed);
+                }
+            }
             KeyCode::Char(c) => {
                 self.prompt.exec(PromptCmd::Write(c));
             }
@@ -55,80 +117,93 @@ impl Shell {
         (OnKey::Continue, None, false)
     }
 
+    /// Check the current query against the source columns, return warnings
+    /// to show under the prompt
+    pub fn lint(&mut self, columns: &[String]) -> &[String] {
+        let (str, _) = self.prompt.state();
+        if str != self.linted {
+            self.warnings = lint::lint(str, columns);
+            self.linted = str.into();
+        }
+        &self.warnings
+    }
+
+    /// Load the function signatures used for hints
+    pub fn load_signatures(&mut self, source: Arc<Source>, runner: &Runner) {
+        self.signatures.load(source, runner)
+    }
+
+    /// Signature of the function call around the cursor, to show under the
+    /// prompt
+    pub fn hint(&mut self) -> Option<&str> {
+        let (str, cursor) = self.prompt.state();
+        self.hint = self.signatures.hint(str, cursor);
+        self.hint.as_deref()
+    }
+
+    /// Load the table and view names used for suggestions
+    pub fn load_relations(&mut self, source: Arc<Source>, runner: &Runner) {
+        self.relations.load(source, runner)
+    }
+
+    /// Known name close to the one the query error could not find, to show
+    /// under the prompt
+    pub fn suggest(&mut self, err: Option<&str>, columns: &[String]) -> bool {
+        self.suggestion = err.and_then(|err| self.relations.suggest(err, columns));
+        self.suggestion.is_some()
+    }
+
     pub fn draw(&mut self, c: &mut Canvas, loading: bool, err: bool) {
-        let mut l = c.btm();
-        l.draw(
-            "$ ",
-            if self.pending {
-                style::selected()
-            } else if loading {
-                style::progress()
-            } else if err {
-                style::error()
-            } else {
-                style::separator()
-            },
-        );
+        if let Some(suggestion) = &self.suggestion {
+            c.btm().draw(
+                format_args!("did you mean `{}`? Ctrl+Y to apply", suggestion.known),
+                style::index(),
+            );
+        }
+        for warning in self.warnings.iter().rev() {
+            c.btm().draw(format_args!("! {warning}"), style::changed());
+        }
+        if let Some(hint) = &self.hint {
+            c.btm().draw(hint, style::index());
+        }
+        let style = if self.pending {
+            style::selected()
+        } else if loading {
+            style::progress()
+        } else if err {
+            style::error()
+        } else {
+            style::separator()
+        };
         let (str, cursor) = self.prompt.state();
         let mut highlighter = Highlighter::load(str);
-        let mut pending_cursor = true;
-
-        let mut w = l.width();
-        self.offset = self.offset.min(cursor);
-
-        let mut before = str[..cursor].chars().rev();
-        let mut start = cursor;
-        let after = str[cursor..].chars();
-        let mut end = cursor;
-        // Read left until goal
-        loop {
-            if start == self.offset {
-                break;
-            }
-            if let Some(c) = before.next() {
-                let c_width = c.width().unwrap_or(0);
-                if c_width > 0 && w <= c_width {
+        // Lines around the cursor, drawn from the bottom
+        let lines = lines(str);
+        let current = lines.iter().position(|l| cursor <= l.end).unwrap_or(0);
+        let first = current.saturating_sub(MAX_LINES - 1);
+        for (n, line) in lines.iter().enumerate().skip(first).take(MAX_LINES).rev() {
+            let mut l = c.btm();
+            l.draw(if n == 0 { "$ " } else { "  " }, style);
+            let (start, end) = if n == current {
+                scroll(str, line.clone(), cursor, &mut self.offset, l.width())
+            } else {
+                (line.start, line.end)
+            };
+            let mut pending_cursor = n == current;
+            for (i, g) in str[start..end].grapheme_indices(true) {
+                let i = start + i;
+                if l.width() == 1 {
                     break;
                 }
-                w -= c_width;
-                start -= c.len_utf8();
-            } else {
-                break;
-            }
-        }
-        self.offset = start;
-        // Read right until eof
-        for c in after {
-            let c_width = c.width().unwrap_or(0);
-            if c_width > 0 && w <= c_width {
-                break;
-            }
-            w -= c_width;
-            end += c.len_utf8();
-        }
-        // Read left until eof
-        for c in before {
-            let c_width = c.width().unwrap_or(0);
-            if c_width > 0 && w <= c_width {
-                break;
-            }
-            w -= c_width;
-            start -= c.len_utf8();
-        }
-
-        for (i, c) in str[start..end].char_indices() {
-            let i = start + i;
-            if l.width() == 1 {
-                break;
+                if pending_cursor && cursor <= i {
+                    l.cursor();
+                    pending_cursor = false
+                }
+                l.draw(g, highlighter.style(i as u64));
             }
-            if pending_cursor && cursor <= i {
+            if pending_cursor {
                 l.cursor();
-                pending_cursor = false
             }
-            l.draw(c, highlighter.style(i as u64));
-        }
-        if pending_cursor {
-            l.cursor();
         }
         // Draw error message
         /*if let Some((range, msg)) = &self.err {
@@ -175,3 +250,67 @@ impl Shell {
         }*/
     }
 }
+
+/// Byte ranges of the lines of a text
+fn lines(str: &str) -> Vec<Range<usize>> {
+    let mut start = 0;
+    let mut lines = vec![];
+    for (i, _) in str.match_indices('\n') {
+        lines.push(start..i);
+        start = i + 1;
+    }
+    lines.push(start..str.len());
+    lines
+}
+
+/// Visible range of a line scrolled horizontally to keep the cursor in view
+fn scroll(
+    str: &str,
+    line: Range<usize>,
+    cursor: usize,
+    offset: &mut usize,
+    mut w: usize,
+) -> (usize, usize) {
+    *offset = (*offset).clamp(line.start, cursor);
+
+    let mut before = str[line.start..cursor].graphemes(true).rev();
+    let mut start = cursor;
+    let after = str[cursor..line.end].graphemes(true);
+    let mut end = cursor;
+    // Read left until goal
+    loop {
+        if start == *offset {
+            break;
+        }
+        if let Some(g) = before.next() {
+            let g_width = grapheme_width(g);
+            if g_width > 0 && w <= g_width {
+                break;
+            }
+            w -= g_width;
+            start -= g.len();
+        } else {
+            break;
+        }
+    }
+    *offset = start;
+    // Read right until eof
+    for g in after {
+        let g_width = grapheme_width(g);
+        if g_width > 0 && w <= g_width {
+            break;
+        }
+        w -= g_width;
+        end += g.len();
+    }
+    // Read left until eof
+    for g in before {
+        let g_width = grapheme_width(g);
+        if g_width > 0 && w <= g_width {
+            break;
+        }
+        w -= g_width;
+        start -= g.len();
+    }
+    (start, end)
+}
@@ -1,6 +1,8 @@
-use sqlparser::{dialect::DuckDbDialect, tokenizer::Token};
+use sqlparser::tokenizer::Token;
 use tui::{none, Color, Style};
 
+use crate::sql;
+
 /// SQL highlighter
 pub struct Highlighter {
     styles: Vec<(u64, Style)>,
@@ -14,13 +16,10 @@ impl Highlighter {
             styles: vec![(0, tui::none())],
             idx: 0,
         };
-        for token in sqlparser::tokenizer::Tokenizer::new(&DuckDbDialect, query)
-            .tokenize_with_location()
-            .unwrap_or_default()
-        {
+        for (offset, token) in sql::tokens(query).unwrap_or_default() {
             tmp.styles.push((
-                token.location.column - 1,
-                match token.token {
+                offset as u64,
+                match token {
                     Token::Mul
                     | Token::Plus
                     | Token::Minus
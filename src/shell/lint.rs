@@ -0,0 +1,144 @@
+use sqlparser::{
+    ast::{
+        Expr, FunctionArg, FunctionArgExpr, Ident, JoinOperator, Query, SelectItem, SetExpr,
+        Statement, TableFactor,
+    },
+    dialect::DuckDbDialect,
+    parser::Parser,
+};
+
+/// Number of columns from which selecting all of them is reported
+const WIDE: usize = 50;
+/// Maximum number of reported warnings
+const MAX_WARNINGS: usize = 3;
+
+/// Lightweight static checks of a query against the source columns
+pub fn lint(query: &str, columns: &[String]) -> Vec<String> {
+    let Ok(statements) = Parser::parse_sql(&DuckDbDialect {}, query) else {
+        return vec![];
+    };
+    let mut warnings = vec![];
+    for statement in &statements {
+        if let Statement::Query(query) = statement {
+            lint_query(query, columns, &mut warnings);
+        }
+    }
+    warnings.dedup();
+    warnings.truncate(MAX_WARNINGS);
+    warnings
+}
+
+fn lint_query(query: &Query, columns: &[String], warnings: &mut Vec<String>) {
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return;
+    };
+    // Columns are only known when reading from the current view
+    let current = match select.from.as_slice() {
+        [] => true,
+        [table] => {
+            table.joins.is_empty()
+                && matches!(&table.relation, TableFactor::Table { name, args: None, .. }
+                    if name.0.len() == 1 && name.0[0].value.eq_ignore_ascii_case("current"))
+        }
+        _ => false,
+    };
+
+    if !columns.is_empty() && current && query.with.is_none() {
+        let aliases: Vec<_> = select
+            .projection
+            .iter()
+            .filter_map(|item| match item {
+                SelectItem::ExprWithAlias { alias, .. } => Some(alias.value.as_str()),
+                _ => None,
+            })
+            .collect();
+        let mut idents = vec![];
+        for item in &select.projection {
+            if let SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } = item {
+                collect_idents(expr, &mut idents);
+            }
+        }
+        if let Some(expr) = &select.selection {
+            collect_idents(expr, &mut idents);
+        }
+        for order in &query.order_by {
+            collect_idents(&order.expr, &mut idents);
+        }
+        for ident in idents {
+            let known = columns
+                .iter()
+                .map(String::as_str)
+                .chain(aliases.iter().copied())
+                .any(|name| name.eq_ignore_ascii_case(&ident.value));
+            if !known {
+                warnings.push(format!("Unknown column {}", ident.value));
+            }
+        }
+    }
+
+    let wildcard = select
+        .projection
+        .iter()
+        .any(|item| matches!(item, SelectItem::Wildcard(_)));
+    if wildcard && current && columns.len() > WIDE {
+        warnings.push(format!("SELECT * on {} columns", columns.len()));
+    }
+
+    let cross = select.from.len() > 1
+        || select
+            .from
+            .iter()
+            .flat_map(|t| &t.joins)
+            .any(|j| matches!(j.join_operator, JoinOperator::CrossJoin));
+    if cross && query.limit.is_none() {
+        warnings.push("Cross join without LIMIT".into());
+    }
+}
+
+/// Collect column references, qualified ones are skipped as they can also be
+/// struct fields
+fn collect_idents<'a>(expr: &'a Expr, idents: &mut Vec<&'a Ident>) {
+    match expr {
+        Expr::Identifier(ident) => idents.push(ident),
+        Expr::BinaryOp { left, right, .. } => {
+            collect_idents(left, idents);
+            collect_idents(right, idents);
+        }
+        Expr::UnaryOp { expr, .. }
+        | Expr::Nested(expr)
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::Cast { expr, .. }
+        | Expr::TryCast { expr, .. } => collect_idents(expr, idents),
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            for expr in [expr, low, high] {
+                collect_idents(expr, idents);
+            }
+        }
+        Expr::InList { expr, list, .. } => {
+            collect_idents(expr, idents);
+            for expr in list {
+                collect_idents(expr, idents);
+            }
+        }
+        Expr::Like { expr, pattern, .. } | Expr::ILike { expr, pattern, .. } => {
+            collect_idents(expr, idents);
+            collect_idents(pattern, idents);
+        }
+        Expr::Function(function) => {
+            for arg in &function.args {
+                if let FunctionArg::Named {
+                    arg: FunctionArgExpr::Expr(expr),
+                    ..
+                }
+                | FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) = arg
+                {
+                    collect_idents(expr, idents);
+                }
+            }
+        }
+        _ => {}
+    }
+}
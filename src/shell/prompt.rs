@@ -49,6 +49,12 @@ impl<const H: usize> Prompt<H> {
         }
     }
 
+    /// Replace the buffer content, keeping the history
+    pub fn set(&mut self, str: &str) {
+        self.pos = None;
+        self.buffer = LineBuffer::from(str);
+    }
+
     /// Ensure buffer contains the right data
     fn solidify(&mut self) {
         if let Some(pos) = self.pos.take() {
@@ -0,0 +1,90 @@
+use std::{collections::HashMap, sync::Arc};
+
+use sqlparser::{
+    dialect::DuckDbDialect,
+    tokenizer::{Token, Tokenizer},
+};
+
+use crate::{
+    error::Result,
+    source::{DataFrame, Source},
+    task::{DuckTask, Runner},
+};
+
+const FUNCTIONS: &str = "\
+SELECT DISTINCT lower(function_name), \
+function_name || '(' || array_to_string(parameters, ', ') || ')' || coalesce(' -> ' || return_type, '') \
+FROM duckdb_functions() ORDER BY 1, 2";
+
+/// Signatures of the duckdb functions by lowercase name
+#[derive(Default)]
+pub struct Signatures {
+    requested: bool,
+    task: Option<DuckTask<HashMap<String, Vec<String>>>>,
+    map: HashMap<String, Vec<String>>,
+}
+
+impl Signatures {
+    /// Load the signatures in the background, only once
+    pub fn load(&mut self, source: Arc<Source>, runner: &Runner) {
+        if self.requested {
+            return;
+        }
+        self.requested = true;
+        self.task = Some(runner.duckdb(source, |_, con| {
+            let df = con
+                .query(FUNCTIONS)?
+                .map(|d| d.map_err(|e| e.into()))
+                .collect::<Result<DataFrame>>()?;
+            let mut map: HashMap<String, Vec<String>> = HashMap::new();
+            for row in 0..df.num_rows() {
+                if let (Some(name), Some(signature)) = (df.value(row, 0), df.value(row, 1)) {
+                    map.entry(name).or_default().push(signature);
+                }
+            }
+            Ok(map)
+        }));
+    }
+
+    /// Signature of the innermost known function call around the cursor
+    pub fn hint(&mut self, query: &str, cursor: usize) -> Option<String> {
+        if let Some(result) = self.task.as_mut().and_then(|t| t.tick()) {
+            // Hints are optional, errors are ignored
+            self.map = result.unwrap_or_default();
+            self.task = None;
+        }
+        let overloads = open_calls(&query[..cursor])
+            .into_iter()
+            .rev()
+            .find_map(|name| self.map.get(&name.to_lowercase()))?;
+        Some(match overloads.as_slice() {
+            [signature] => signature.clone(),
+            [signature, others @ ..] => format!("{signature} (+{} overloads)", others.len()),
+            [] => return None,
+        })
+    }
+}
+
+/// Names of the calls whose parenthesis is still open, outermost first
+fn open_calls(prefix: &str) -> Vec<String> {
+    let Ok(tokens) = Tokenizer::new(&DuckDbDialect, prefix).tokenize() else {
+        return vec![];
+    };
+    let mut stack = vec![];
+    let mut prev = None;
+    for token in tokens {
+        match &token {
+            Token::Whitespace(_) => continue,
+            Token::LParen => stack.push(match &prev {
+                Some(Token::Word(word)) => Some(word.value.clone()),
+                _ => None,
+            }),
+            Token::RParen => {
+                stack.pop();
+            }
+            _ => {}
+        }
+        prev = Some(token);
+    }
+    stack.into_iter().flatten().collect()
+}
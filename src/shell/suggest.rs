@@ -0,0 +1,244 @@
+use std::sync::Arc;
+
+use sqlparser::{
+    dialect::DuckDbDialect,
+    tokenizer::{Location, Token, Tokenizer},
+};
+
+use crate::{
+    error::Result,
+    source::{DataFrame, Source},
+    sql,
+    task::{DuckTask, Runner},
+};
+
+const RELATIONS: &str = "\
+SELECT table_name FROM duckdb_tables() \
+UNION ALL SELECT view_name FROM duckdb_views() WHERE NOT internal";
+
+/// Replacement of an unknown name reported by duckdb
+pub struct Suggestion {
+    pub unknown: String,
+    pub known: String,
+}
+
+/// Names of the tables and views of the database
+#[derive(Default)]
+pub struct Relations {
+    requested: bool,
+    task: Option<DuckTask<Vec<String>>>,
+    names: Vec<String>,
+}
+
+impl Relations {
+    /// Load the names in the background, only once
+    pub fn load(&mut self, source: Arc<Source>, runner: &Runner) {
+        if self.requested {
+            return;
+        }
+        self.requested = true;
+        self.task = Some(runner.duckdb(source, |_, con| {
+            let df = con
+                .query(RELATIONS)?
+                .map(|d| d.map_err(|e| e.into()))
+                .collect::<Result<DataFrame>>()?;
+            Ok((0..df.num_rows())
+                .filter_map(|row| df.value(row, 0))
+                .collect())
+        }));
+    }
+
+    /// Closest known name to the one duckdb could not find in its error
+    pub fn suggest(&mut self, err: &str, columns: &[String]) -> Option<Suggestion> {
+        if let Some(result) = self.task.as_mut().and_then(|t| t.tick()) {
+            // Suggestions are optional, errors are ignored
+            self.names = result.unwrap_or_default();
+            self.task = None;
+        }
+        let (unknown, candidates) = if let Some(name) = between(err, "column \"", "\" not found") {
+            (name, columns)
+        } else if let Some(name) = between(err, "Table with name ", " does not exist") {
+            (name, self.names.as_slice())
+        } else {
+            return None;
+        };
+        let known = closest(unknown, candidates)?;
+        Some(Suggestion {
+            unknown: unknown.into(),
+            known: known.into(),
+        })
+    }
+}
+
+/// Text between two markers
+fn between<'a>(str: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let (_, rest) = str.split_once(start)?;
+    let (name, _) = rest.split_once(end)?;
+    Some(name)
+}
+
+/// Closest candidate within a few edits, case is ignored
+fn closest<'a>(name: &str, candidates: &'a [String]) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    let max = (name.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|c| (distance(&name, &c.to_lowercase()), c))
+        .filter(|(d, _)| *d > 0 && *d <= max)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c.as_str())
+}
+
+/// Levenshtein distance between two strings
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = (ca != *cb) as usize;
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+/// Replace every occurrence of the unknown identifier in the query, None if
+/// the query cannot be tokenized
+pub fn apply(query: &str, suggestion: &Suggestion) -> Option<String> {
+    let tokens = Tokenizer::new(&DuckDbDialect, query)
+        .tokenize_with_location()
+        .ok()?;
+    let mut fixed = String::with_capacity(query.len());
+    let mut copied = 0;
+    for (token, next) in tokens
+        .iter()
+        .zip(tokens.iter().skip(1).map(Some).chain([None]))
+    {
+        let Token::Word(word) = &token.token else {
+            continue;
+        };
+        if !word.value.eq_ignore_ascii_case(&suggestion.unknown) {
+            continue;
+        }
+        let start = offset(query, &token.location);
+        let end = next.map_or(query.len(), |next| offset(query, &next.location));
+        fixed.push_str(&query[copied..start]);
+        let simple = suggestion
+            .known
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if word.quote_style.is_none() && simple {
+            fixed.push_str(&suggestion.known);
+        } else {
+            fixed.push_str(&sql::ident(&suggestion.known));
+        }
+        copied = end;
+    }
+    fixed.push_str(&query[copied..]);
+    Some(fixed)
+}
+
+/// Byte offset of a tokenizer location
+fn offset(query: &str, location: &Location) -> usize {
+    let line_start: usize = query
+        .split_inclusive('\n')
+        .take(location.line as usize - 1)
+        .map(str::len)
+        .sum();
+    query[line_start..]
+        .char_indices()
+        .nth(location.column as usize - 1)
+        .map_or(query.len(), |(i, _)| line_start + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggestion(unknown: &str, known: &str) -> Suggestion {
+        Suggestion {
+            unknown: unknown.into(),
+            known: known.into(),
+        }
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("abc", ""), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("customer_id", "customer_id"), 0);
+        assert_eq!(distance("custmer_id", "customer_id"), 1);
+        assert_eq!(distance("ab", "ba"), 2);
+        assert_eq!(distance("été", "ete"), 2);
+    }
+
+    #[test]
+    fn closest_candidate() {
+        let candidates = ["customer_id", "customer", "Amount"].map(String::from);
+        assert_eq!(closest("custmer_id", &candidates), Some("customer_id"));
+        assert_eq!(closest("amout", &candidates), Some("Amount"));
+        // Exact matches and far away names are not suggested
+        assert_eq!(closest("AMOUNT", &candidates), None);
+        assert_eq!(closest("price", &candidates), None);
+        assert_eq!(closest("x", &[]), None);
+    }
+
+    #[test]
+    fn unknown_name() {
+        let err = "Binder Error: Referenced column \"custmer_id\" not found in FROM clause!";
+        assert_eq!(
+            between(err, "column \"", "\" not found"),
+            Some("custmer_id")
+        );
+        let err = "Catalog Error: Table with name order does not exist!";
+        assert_eq!(
+            between(err, "Table with name ", " does not exist"),
+            Some("order")
+        );
+        assert_eq!(between("Parser Error", "column \"", "\" not found"), None);
+    }
+
+    #[test]
+    fn apply_every_occurrence() {
+        let fix = suggestion("custmer_id", "customer_id");
+        assert_eq!(
+            apply("SELECT custmer_id FROM t\nWHERE CUSTMER_ID > 1", &fix).unwrap(),
+            "SELECT customer_id FROM t\nWHERE customer_id > 1"
+        );
+    }
+
+    #[test]
+    fn apply_keeps_literals_and_other_words() {
+        let fix = suggestion("nme", "name");
+        assert_eq!(
+            apply("SELECT nme, nmes, 'nme' FROM t -- nme", &fix).unwrap(),
+            "SELECT name, nmes, 'nme' FROM t -- nme"
+        );
+    }
+
+    #[test]
+    fn apply_quotes_when_needed() {
+        // Quoted identifiers and names that would change meaning unquoted
+        assert_eq!(
+            apply("SELECT \"nme\" FROM t", &suggestion("nme", "name")).unwrap(),
+            "SELECT \"name\" FROM t"
+        );
+        assert_eq!(
+            apply("SELECT amout FROM t", &suggestion("amout", "Amount")).unwrap(),
+            "SELECT \"Amount\" FROM t"
+        );
+        assert_eq!(
+            apply("SELECT ét FROM t", &suggestion("ét", "first name")).unwrap(),
+            "SELECT \"first name\" FROM t"
+        );
+    }
+
+    #[test]
+    fn apply_invalid_query() {
+        assert!(apply("SELECT 'unterminated", &suggestion("a", "b")).is_none());
+    }
+}
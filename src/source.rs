@@ -1,27 +1,52 @@
 use std::{
+    fmt::Display,
+    fs::File,
+    io::Read,
     path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
+    time::SystemTime,
 };
 
 use arrow::{
-    datatypes::{Schema, SchemaRef},
+    array::{Array, AsArray},
+    compute::{cast, concat_batches},
+    datatypes::{DataType, Float32Type, Float64Type, Schema, SchemaRef},
     record_batch::RecordBatch,
+    util::display::{ArrayFormatter, FormatOptions},
 };
 use libduckdb_sys::duckdb_query_progress_type;
 
 use crate::{
-    array_to_iter,
+    archive, array_to_iter,
+    cache::Cache,
+    cmd::{self, UNION_BY_NAME},
+    delta,
     duckdb::{Chunks, Connection, DuckDb},
+    encoding::{self, Copy},
     error::Result,
-    fmt::{Col, ColBuilder, GridBuffer},
-    task::{Ctx, DuckTask, Runner, Task},
+    excel,
+    fmt::{Age, Col, ColBuilder, GridBuffer, Size},
+    ingest::{self, Format, Ingest},
+    ops::Ops,
+    provider::{Provider, Pushdown},
+    sql,
+    task::{Ctx, DuckTask, Permit, Runner, Task, TaskHandle},
+    temp::TempFile,
 };
 
+/// Minimum number of rows of compacted batches
+const COMPACT_ROWS: usize = 64 * 1024;
+
+/// Maximum number of rows waiting in the pending queue before the streaming
+/// worker pauses until the UI drains it
+const MAX_PENDING_ROWS: usize = 100_000;
+
 pub struct Pending {
     batches: Vec<RecordBatch>,
+    rows: usize,
     full: bool,
     error: Option<String>,
 }
@@ -36,6 +61,10 @@ pub enum StreamingFrame {
         task: Task<AtomicUsize, Pending>,
         df: DataFrame,
         is_loading: bool,
+        /// Cumulated number of rows at the end of each parquet row group
+        row_groups: Vec<usize>,
+        /// Source of the streamed rows, caching them once complete
+        source: Arc<Source>,
     },
 }
 
@@ -48,12 +77,19 @@ impl StreamingFrame {
         Self::Loaded(full)
     }
 
-    pub fn streaming(preloaded: DataFrame, chunks: Chunks, runner: Runner) -> Self {
+    pub fn streaming(
+        source: Arc<Source>,
+        preloaded: DataFrame,
+        chunks: Chunks,
+        runner: Runner,
+        row_groups: Vec<usize>,
+    ) -> Self {
         let loaded = preloaded.num_rows();
         let task = runner.task(
             AtomicUsize::new(0),
             Pending {
                 batches: vec![],
+                rows: 0,
                 full: false,
                 error: None,
             },
@@ -63,6 +99,8 @@ impl StreamingFrame {
             task,
             df: preloaded,
             is_loading: true,
+            row_groups,
+            source,
         }
     }
 
@@ -75,14 +113,20 @@ impl StreamingFrame {
             task,
             df,
             is_loading,
+            source,
             ..
         } = self
         {
-            let (full, error) = task.lock(|p| {
+            let (drained, full, error) = task.lock(|p| {
                 df.extend(p.batches.drain(..));
-                (p.full, p.error.take())
+                (std::mem::take(&mut p.rows), p.full, p.error.take())
             });
+            // Wake worker if it was waiting for the queue to be drained
+            if drained >= MAX_PENDING_ROWS {
+                task.wake();
+            }
             if full {
+                source.cache.insert(source.load_sql(), df.clone());
                 *self = StreamingFrame::Loaded(std::mem::take(df))
             } else if let Some(error) = error {
                 *self = StreamingFrame::Error {
@@ -111,6 +155,19 @@ impl StreamingFrame {
         }
     }
 
+    /// Replace the data frame by its compacted version if it did not change
+    pub fn compacted(&mut self, from: &DataFrame, compacted: DataFrame) {
+        match self {
+            StreamingFrame::Loaded(df)
+            | StreamingFrame::Error { df, .. }
+            | StreamingFrame::Streaming { df, .. } => {
+                if Arc::ptr_eq(&df.0, &from.0) {
+                    *df = compacted
+                }
+            }
+        }
+    }
+
     pub fn df(&self) -> &DataFrame {
         match self {
             StreamingFrame::Loaded(df)
@@ -138,17 +195,40 @@ impl StreamingFrame {
         }
     }
 
-    /// Background streaming task
+    /// Row group being streamed and the number of row groups, when streaming
+    /// a parquet file as is
+    pub fn row_group(&self) -> Option<(usize, usize, usize)> {
+        let StreamingFrame::Streaming { df, row_groups, .. } = self else {
+            return None;
+        };
+        let total = *row_groups.last()?;
+        let current = row_groups.partition_point(|end| *end <= df.num_rows());
+        Some(((current + 1).min(row_groups.len()), row_groups.len(), total))
+    }
+
+    /// Background streaming task, holding an execution slot only while
+    /// fetching rows
     fn streaming_task(ctx: Ctx<AtomicUsize, Pending>, mut loaded: usize, mut chunks: Chunks) {
+        let mut permit = None;
         loop {
             while loaded < ctx.state().load(Ordering::Relaxed) {
                 if ctx.canceled() {
                     return;
                 }
+                // Wait for the UI to drain the pending queue
+                if ctx.lock(|p| p.rows) >= MAX_PENDING_ROWS {
+                    permit = None;
+                    ctx.wait();
+                    continue;
+                }
+                permit.get_or_insert_with(Permit::acquire);
                 match chunks.next() {
                     Some(Ok(batch)) => {
                         loaded += batch.num_rows();
-                        ctx.lock(|p| p.batches.push(batch))
+                        ctx.lock(|p| {
+                            p.rows += batch.num_rows();
+                            p.batches.push(batch)
+                        })
                     }
                     Some(Err(err)) => {
                         ctx.lock(|p| p.error = Some(err.to_string()));
@@ -163,6 +243,7 @@ impl StreamingFrame {
             if ctx.canceled() {
                 return;
             }
+            permit = None;
             ctx.wait();
         }
     }
@@ -175,17 +256,26 @@ pub enum FrameLoader {
 
 impl FrameLoader {
     pub fn load(source: Arc<Source>, runner: &Runner) -> Self {
-        if let Some(df) = source.sync_full() {
+        if let Some(df) = source
+            .sync_full()
+            .or_else(|| source.cache.get(&source.load_sql()))
+        {
             Self::Finished(Some(StreamingFrame::full(df)))
         } else {
             let _runner = runner.clone();
             Self::Pending(runner.duckdb(source, move |source, con| {
+                if let Some(df) = source.pushdown() {
+                    return Ok(StreamingFrame::full(df?));
+                }
+                let row_groups = source.row_groups(&con);
                 let mut chunks = source.load(con)?;
                 let preload = chunks
                     .next()
                     .map(|r| r.map(|r| r.into()))
                     .unwrap_or_else(|| Ok(DataFrame::default()))?;
-                Ok(StreamingFrame::streaming(preload, chunks, _runner))
+                Ok(StreamingFrame::streaming(
+                    source, preload, chunks, _runner, row_groups,
+                ))
             }))
         }
     }
@@ -213,162 +303,1155 @@ impl FrameLoader {
             FrameLoader::Pending(task) => Some(task.progress()),
         }
     }
+
+    pub fn handle(&self) -> Option<TaskHandle> {
+        match self {
+            FrameLoader::Finished(_) => None,
+            FrameLoader::Pending(task) => Some(task.handle()),
+        }
+    }
+}
+
+/// File size, modification time and format of a file source
+pub struct FileMeta {
+    size: u64,
+    modified: Option<SystemTime>,
+    format: String,
+    /// Number of files matching a pattern
+    files: usize,
+}
+
+impl Display for FileMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format)?;
+        if self.files > 1 {
+            write!(f, " {} files", self.files)?;
+        }
+        write!(f, " {}", Size(self.size as usize))?;
+        if let Some(age) = self.modified.and_then(|m| m.elapsed().ok()) {
+            write!(f, " {}", Age(age))?;
+        }
+        Ok(())
+    }
 }
 
+/// Number of rows of the sample opened instead of a large file
+pub const SAMPLE_ROWS: usize = 10_000;
+
+/// Temporary table collecting the malformed rows of a csv file
+const REJECTS: &str = "dtex_rejects";
+
+/// Initial query of sources exposing a current view
+const DEFAULT_SQL: &str = "FROM current SELECT *";
+
+/// Duckdb reader of each data file extension
+const READERS: [(&str, &str); 7] = [
+    (".parquet", "read_parquet"),
+    (".csv", "read_csv"),
+    (".tsv", "read_csv"),
+    (".json", "read_json"),
+    (".jsonl", "read_json"),
+    (".ndjson", "read_json"),
+    (".xlsx", "st_read"),
+];
+
+/// Tables then views of an attached database
+const DB_TABLES: &str = "\
+SELECT table_schema, table_name FROM information_schema.tables WHERE table_catalog = 'db' \
+ORDER BY table_type, table_schema <> 'main', table_schema, table_name";
+
 #[derive(Clone)]
 enum Kind {
     Empty,
     Eager(DataFrame),
-    File { path: PathBuf, display_path: String },
+    /// Data scanned from a custom provider
+    Virtual(Arc<dyn Provider>, DataFrame),
+    /// Batches fed progressively by the host program
+    Ingest(Ingest),
+    File {
+        path: PathBuf,
+        display_path: String,
+    },
+    /// Database file attached read only
+    Database {
+        path: PathBuf,
+        display_path: String,
+    },
+    /// Directory or archive whose files are picked from a listing
+    Directory(PathBuf),
 }
 
+#[derive(Clone)]
 pub struct Source {
     name: String,
     kind: Kind,
     sql: String,
+    ops: Ops,
     db: DuckDb,
+    /// Statements that created the current view
+    setup: Vec<String>,
+    /// Tables and views of an attached database
+    tables: Vec<String>,
+    read_options: String,
+    /// Maximum number of rows read from the file
+    sample: Option<usize>,
+    /// Utf-8 copy of a file in another encoding, read instead of it and made
+    /// by the first connection as copying a large file takes a while
+    transcoded: Option<Arc<Copy>>,
+    /// Whether the setup waits for the file to be copied
+    pending: Arc<AtomicBool>,
+    /// Member extracted from an archive, deleted with the last source using it
+    extracted: Option<Arc<TempFile>>,
+    cache: Cache,
 }
 
 impl Source {
     fn new(name: String, kind: Kind, sql: String) -> Result<Self> {
         let db = DuckDb::mem()?;
-        let conn = db.conn()?;
-        conn.execute("SET enable_progress_bar=true; SET enable_progress_bar_print=false;")?;
-
-        match &kind {
-            Kind::Empty => {}
-            Kind::Eager(df) => {
-                conn.bind(df.clone())?;
+        let conn = db.conn(Self::init_conn)?;
+        // In memory data frames are copied once for every connection to see
+        if let Kind::Eager(df) | Kind::Virtual(_, df) = &kind {
+            if df.num_columns() > 0 {
+                conn.store("current", df)?;
             }
+        }
+
+        let (setup, transcoded, tables) = match &kind {
+            Kind::Empty
+            | Kind::Eager(_)
+            | Kind::Virtual(..)
+            | Kind::Ingest(_)
+            | Kind::Directory(_) => (vec![], None, vec![]),
             Kind::File { display_path, .. } => {
-                if display_path.ends_with(".sql") {
-                    let content = std::fs::read_to_string(display_path)?;
-                    let queries = content
-                        .split(';')
-                        .filter(|s| !s.trim().is_empty())
-                        .collect::<Vec<_>>();
-
-                    match queries.as_slice() {
-                        [] => {
-                            conn.execute("CREATE TABLE current (i INTEGER)")?; // TODO what to do as default ?
-                        }
-                        [content @ .., tail] => {
-                            for q in content {
-                                conn.execute(q)?;
-                            }
-                            conn.execute(&format!("CREATE VIEW current AS {tail}"))?;
-                        }
-                    }
-                } else {
-                    let path = display_path
-                        .trim_end_matches(".gz")
-                        .trim_end_matches(".zst");
-                    if [".parquet", ".csv", ".tsv", ".json", ".jsonl", ".ndjson"]
-                        .iter()
-                        .any(|s| path.ends_with(s))
-                    {
-                        conn.execute(&format!(
-                            "CREATE VIEW current AS SELECT * FROM '{display_path}'"
-                        ))?;
-                    } else {
-                        return Err("Unsupported file format".into());
-                    }
-                }
+                let (setup, transcoded) = Self::setup(&conn, display_path, "", None, None)?;
+                (setup, transcoded, vec![])
             }
-        }
+            Kind::Database { display_path, .. } => {
+                let (setup, tables) = Self::attach(&conn, display_path, "", None)?;
+                (setup, None, tables)
+            }
+        };
 
         Ok(Self {
             name,
             kind,
             sql,
+            ops: Ops::default(),
             db,
+            setup,
+            tables,
+            read_options: String::new(),
+            sample: None,
+            pending: Arc::new(AtomicBool::new(transcoded.is_some())),
+            transcoded,
+            extracted: None,
+            cache: Cache::default(),
         })
     }
 
-    pub fn empty(name: String) -> Self {
-        Self::new(name, Kind::Empty, String::new()).unwrap()
+    /// Execute the statements creating the current view of a file, data files
+    /// are read with `read_options` when not empty and limited to `sample` rows,
+    /// text files in another encoding than utf-8 are read from a utf-8 copy
+    ///
+    /// The statements reading a copy are executed once it is made by `conn`,
+    /// the `previous` copy is kept when in the same encoding.
+    fn setup(
+        con: &Connection,
+        display_path: &str,
+        read_options: &str,
+        sample: Option<usize>,
+        previous: Option<&Arc<Copy>>,
+    ) -> Result<(Vec<String>, Option<Arc<Copy>>)> {
+        let (label, read_options) = split_encoding(read_options);
+        let transcoded = Self::transcode(display_path, label, previous)?;
+        let path = match &transcoded {
+            Some(transcoded) => transcoded.file.path.to_string_lossy().to_string(),
+            None => display_path.into(),
+        };
+        let statements = Self::file_setup(con, &path, &read_options, sample)?;
+        if transcoded.is_none() {
+            for statement in &statements {
+                con.execute(statement)?;
+            }
+        }
+        Ok((statements, transcoded))
+    }
+
+    /// Utf-8 copy of a plain local csv or json file in another encoding
+    fn transcode(
+        display_path: &str,
+        label: Option<&str>,
+        previous: Option<&Arc<Copy>>,
+    ) -> Result<Option<Arc<Copy>>> {
+        let text = [".csv", ".tsv", ".json", ".jsonl", ".ndjson"]
+            .iter()
+            .any(|ext| display_path.ends_with(ext));
+        if !text || is_pattern(display_path) || is_remote(display_path) {
+            return match label {
+                Some(_) => Err("Only plain csv and json files can be transcoded".into()),
+                None => Ok(None),
+            };
+        }
+        let path = Path::new(display_path);
+        let Some(encoding) = encoding::detect(path, label)? else {
+            return Ok(None);
+        };
+        let copy = match previous {
+            Some(copy) if copy.encoding == encoding => copy.clone(),
+            _ => Arc::new(Copy::new(path, encoding)?),
+        };
+        Ok(Some(copy))
     }
 
-    pub fn from_mem(name: String, df: DataFrame) -> Self {
-        Self::new(name, Kind::Eager(df), "FROM current SELECT *".into()).unwrap()
+    /// Engine of a database file, `.db` is used by both so its header is read
+    fn database_type(display_path: &str) -> Option<&'static str> {
+        if display_path.ends_with(".db") {
+            let mut header = [0; 16];
+            let sqlite = File::open(display_path)
+                .and_then(|mut file| file.read_exact(&mut header))
+                .is_ok_and(|_| &header == b"SQLite format 3\0");
+            return Some(if sqlite { "sqlite" } else { "duckdb" });
+        }
+        [
+            (".duckdb", "duckdb"),
+            (".sqlite", "sqlite"),
+            (".sqlite3", "sqlite"),
+        ]
+        .iter()
+        .find_map(|(ext, ty)| display_path.ends_with(ext).then_some(*ty))
+    }
+
+    /// Attach a database file read only, exposing each of its tables as a
+    /// view and the one chosen by the `table='name'` option as the current
+    /// view, the first one by default, with the names of the tables
+    fn attach(
+        con: &Connection,
+        display_path: &str,
+        read_options: &str,
+        sample: Option<usize>,
+    ) -> Result<(Vec<String>, Vec<String>)> {
+        let ty = Self::database_type(display_path).ok_or("Unsupported database format")?;
+        let chosen = match read_options.trim() {
+            "" => None,
+            option => {
+                let value = option
+                    .strip_prefix("table=")
+                    .ok_or("Only the table='name' option applies to databases")?;
+                Some(value.trim_matches('\'').replace("''", "'"))
+            }
+        };
+        let mut statements = vec![];
+        if ty != "duckdb" {
+            statements.push(format!("INSTALL {ty}"));
+            statements.push(format!("LOAD {ty}"));
+        }
+        statements.push(format!(
+            "ATTACH IF NOT EXISTS {} AS db (TYPE {ty}, READ_ONLY)",
+            sql::lit(display_path)
+        ));
+        for statement in &statements {
+            con.execute(statement)?;
+        }
+        let attached = statements.len();
+        let tables = Self::db_tables(con)?;
+        for (name, relation) in &tables {
+            statements.push(format!(
+                "CREATE OR REPLACE VIEW {} AS SELECT * FROM {relation}",
+                sql::ident(name)
+            ));
+        }
+        let table = chosen
+            .as_deref()
+            .or(tables.first().map(|(name, _)| name.as_str()))
+            .ok_or("Database has no table")?;
+        let limit = sample.map_or(String::new(), |rows| format!(" LIMIT {rows}"));
+        statements.push(format!(
+            "CREATE OR REPLACE VIEW current AS SELECT * FROM {}{limit}",
+            sql::ident(table)
+        ));
+        for statement in &statements[attached..] {
+            con.execute(statement)?;
+        }
+        Ok((
+            statements,
+            tables.into_iter().map(|(name, _)| name).collect(),
+        ))
     }
 
-    pub fn from_path(path: &Path) -> Self {
+    /// Name and qualified identifier of the tables then views of the attached
+    /// database, the schema is part of the name outside of the main one
+    fn db_tables(con: &Connection) -> Result<Vec<(String, String)>> {
+        let df = con
+            .query(DB_TABLES)?
+            .map(|d| d.map_err(|e| e.into()))
+            .collect::<Result<DataFrame>>()?;
+        Ok((0..df.num_rows())
+            .map(|row| {
+                let [schema, table] = [0, 1].map(|i| df.value(row, i).unwrap_or_default());
+                let relation = format!("db.{}.{}", sql::ident(&schema), sql::ident(&table));
+                let name = if schema == "main" {
+                    table
+                } else {
+                    format!("{schema}.{table}")
+                };
+                (name, relation)
+            })
+            .collect())
+    }
+
+    /// Statements creating the current view of a data or sql file
+    fn file_setup(
+        con: &Connection,
+        display_path: &str,
+        read_options: &str,
+        sample: Option<usize>,
+    ) -> Result<Vec<String>> {
+        if display_path.ends_with(".sql") {
+            if !read_options.is_empty() {
+                return Err("Read options only apply to data files".into());
+            }
+            let content = std::fs::read_to_string(display_path)?;
+            let queries = content
+                .split(';')
+                .filter(|s| !s.trim().is_empty())
+                .collect::<Vec<_>>();
+
+            Ok(match queries.as_slice() {
+                [] => vec!["CREATE TABLE current (i INTEGER)".into()], // TODO what to do as default ?
+                [content @ .., tail] => content
+                    .iter()
+                    .map(|q| q.trim().to_string())
+                    .chain([format!("CREATE VIEW current AS {}", tail.trim())])
+                    .collect(),
+            })
+        } else if display_path.ends_with(".xlsx") {
+            Self::workbook_setup(display_path, read_options, sample)
+        } else if let Some(format) = table_format(Path::new(display_path)) {
+            Self::table_setup(con, display_path, format, read_options, sample)
+        } else {
+            let path = display_path
+                .trim_end_matches(".gz")
+                .trim_end_matches(".zst");
+            let reader = READERS
+                .iter()
+                .find_map(|(ext, reader)| path.ends_with(ext).then_some(*reader))
+                .ok_or("Unsupported file format")?;
+            let (files, read_options) = match expand(display_path).transpose()? {
+                // Matching files are read as one relation merging their
+                // columns by name
+                Some(files) => {
+                    let list = files
+                        .iter()
+                        .map(|f| file_lit(&f.to_string_lossy()))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    (
+                        format!("[{list}]"),
+                        cmd::default_option(read_options, UNION_BY_NAME),
+                    )
+                }
+                None => (file_lit(display_path), read_options.to_string()),
+            };
+            let from = if read_options.is_empty() {
+                files
+            } else {
+                format!("{reader}({files}, {read_options})")
+            };
+            let limit = sample.map_or(String::new(), |rows| format!(" LIMIT {rows}"));
+            let mut statements = vec![];
+            if is_remote(display_path) {
+                statements.push("INSTALL httpfs".into());
+                statements.push("LOAD httpfs".into());
+            }
+            statements.push(format!(
+                "CREATE OR REPLACE VIEW current AS SELECT * FROM {from}{limit}"
+            ));
+            Ok(statements)
+        }
+    }
+
+    /// Statements creating the current view of a Delta Lake or Iceberg table,
+    /// at the version or snapshot chosen with the `version=N` or `snapshot=N`
+    /// option and the latest one by default
+    ///
+    /// Duckdb 0.9 has no delta extension, the data files of a version are
+    /// listed from the log of the table and read as parquet.
+    fn table_setup(
+        con: &Connection,
+        display_path: &str,
+        format: &str,
+        read_options: &str,
+        sample: Option<usize>,
+    ) -> Result<Vec<String>> {
+        let key = match format {
+            "delta" => "version",
+            _ => "snapshot",
+        };
+        let chosen = match read_options.trim() {
+            "" => None,
+            option => {
+                let prefix = format!("{key}=");
+                let value = option.strip_prefix(&prefix).ok_or(format!(
+                    "Only the {prefix}N option applies to {format} tables"
+                ))?;
+                let value = value.trim().trim_matches('\'');
+                let parsed = value
+                    .parse()
+                    .map_err(|_| format!("Invalid {key} {value}"))?;
+                Some(parsed)
+            }
+        };
+        let path = Path::new(display_path);
+        let mut statements = vec![];
+        let scan = if format == "delta" {
+            let (files, partitioned) = delta::files(con, path, chosen)?;
+            let list: Vec<_> = files
+                .iter()
+                .map(|f| file_lit(&f.to_string_lossy()))
+                .collect();
+            format!(
+                "read_parquet([{}], hive_partitioning={partitioned}, {UNION_BY_NAME})",
+                list.join(", ")
+            )
+        } else {
+            // Without a version hint the table is read from its latest metadata
+            let table = if path.join("metadata").join("version-hint.text").is_file() {
+                path.to_path_buf()
+            } else {
+                iceberg_metadata(path).ok_or("Iceberg table without metadata")?
+            };
+            statements.push("INSTALL iceberg".into());
+            statements.push("LOAD iceberg".into());
+            let table = sql::lit(&table.to_string_lossy());
+            match chosen {
+                Some(snapshot) => format!("iceberg_scan({table}, {snapshot}::UBIGINT)"),
+                None => format!("iceberg_scan({table})"),
+            }
+        };
+        let limit = sample.map_or(String::new(), |rows| format!(" LIMIT {rows}"));
+        statements.push(format!(
+            "CREATE OR REPLACE VIEW current AS SELECT * FROM {scan}{limit}"
+        ));
+        Ok(statements)
+    }
+
+    /// Statements creating the current view of a sheet of a workbook, chosen
+    /// with the `sheet='name'` option and the first one by default
+    ///
+    /// Duckdb has no reader of its own, sheets are read by the GDAL driver of
+    /// the spatial extension. A `range='A1:F500'` has no header, its columns
+    /// are named by their letters.
+    fn workbook_setup(
+        display_path: &str,
+        read_options: &str,
+        sample: Option<usize>,
+    ) -> Result<Vec<String>> {
+        let mut options = read_options.to_string();
+        let mut args = vec![sql::lit(display_path)];
+        if let Some(sheet) = cmd::option_text(read_options, "sheet") {
+            options = cmd::remove_option(&options, "sheet");
+            args.push(format!("layer={}", sql::lit(&sheet)));
+        }
+        let (select, mut limit, offset) = match cmd::option_value(read_options, "range") {
+            Some(range) => {
+                options = cmd::remove_option(&options, "range");
+                let ((c1, r1), (c2, r2)) = excel::range(&range.trim_matches('\'').to_uppercase())
+                    .ok_or("Invalid cell range, expected e.g. A1:F500")?;
+                args.push("open_options=['HEADERS=DISABLE']".into());
+                let columns = (c1..=c2)
+                    .map(|c| format!("Field{} AS {}", c + 1, excel::column_name(c)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (columns, Some(r2 - r1 + 1), r1)
+            }
+            None => {
+                args.push("open_options=['HEADERS=FORCE']".into());
+                ("*".into(), None, 0)
+            }
+        };
+        if !options.trim().is_empty() {
+            args.push(options);
+        }
+        if let Some(rows) = sample {
+            limit = Some(limit.map_or(rows, |limit| limit.min(rows)));
+        }
+        let limit = limit.map_or(String::new(), |rows| format!(" LIMIT {rows}"));
+        let offset = match offset {
+            0 => String::new(),
+            rows => format!(" OFFSET {rows}"),
+        };
+        Ok(vec![
+            "INSTALL spatial".into(),
+            "LOAD spatial".into(),
+            format!(
+                "CREATE OR REPLACE VIEW current AS SELECT {select} FROM st_read({}){limit}{offset}",
+                args.join(", ")
+            ),
+        ])
+    }
+
+    /// Read the file again with other reader options, the current view is
+    /// replaced for every source sharing this database
+    pub fn with_read_options(&self, read_options: String) -> Result<Self> {
+        self.reread(&self.conn()?, read_options, self.sample)
+    }
+
+    /// Read only the first rows of the file, or all of them with None
+    pub fn with_sample(&self, sample: Option<usize>) -> Result<Self> {
+        self.reread(&self.conn()?, self.read_options.clone(), sample)
+    }
+
+    /// Open a sample of the file when it is larger than `max_size` bytes and
+    /// lives on slow storage, so a huge file on a network share does not lock
+    /// up the machine. The size of remote objects is unknown and they are
+    /// the slowest storage of all, so they are always sampled.
+    pub fn guarded(self, max_size: u64) -> Self {
+        let remote =
+            matches!(&self.kind, Kind::File { display_path, .. } if is_remote(display_path));
+        let large = self.metadata().is_some_and(|meta| meta.size > max_size);
+        if remote || (large && self.path().is_some_and(slow_storage)) {
+            self.with_sample(Some(SAMPLE_ROWS)).unwrap_or(self)
+        } else {
+            self
+        }
+    }
+
+    /// Create the view of the file again, which scans the file to detect
+    /// its schema
+    pub fn reread(
+        &self,
+        con: &Connection,
+        read_options: String,
+        sample: Option<usize>,
+    ) -> Result<Self> {
+        let (setup, transcoded, tables) = match &self.kind {
+            Kind::File { display_path, .. } => {
+                let previous = self.transcoded.as_ref();
+                let (setup, transcoded) =
+                    Self::setup(con, display_path, &read_options, sample, previous)?;
+                (setup, transcoded, vec![])
+            }
+            Kind::Database { display_path, .. } => {
+                let (setup, tables) = Self::attach(con, display_path, &read_options, sample)?;
+                (setup, None, tables)
+            }
+            Kind::Empty
+            | Kind::Eager(_)
+            | Kind::Virtual(..)
+            | Kind::Ingest(_)
+            | Kind::Directory(_) => return Err("Read options only apply to data files".into()),
+        };
+        self.cache.clear();
+        Ok(Self {
+            setup,
+            tables,
+            read_options,
+            sample,
+            pending: Arc::new(AtomicBool::new(transcoded.is_some())),
+            transcoded,
+            ..self.clone()
+        })
+    }
+
+    /// Scan the provider again and replace the copy of its data
+    pub fn rescan(&self, con: &Connection) -> Result<Self> {
+        let Kind::Virtual(provider, _) = &self.kind else {
+            return Err("Only providers can be scanned again".into());
+        };
+        let df = provider.scan()?;
+        if df.num_columns() > 0 {
+            con.store("current", &df)?;
+        }
+        self.cache.clear();
+        Ok(Self {
+            kind: Kind::Virtual(provider.clone(), df),
+            ..self.clone()
+        })
+    }
+
+    /// Statements scanning the whole csv file while collecting its malformed
+    /// rows, then listing them
+    pub fn rejects_sql(&self) -> Result<(String, String)> {
+        let csv = match &self.kind {
+            Kind::File { display_path, .. } => {
+                let path = display_path
+                    .trim_end_matches(".gz")
+                    .trim_end_matches(".zst");
+                (path.ends_with(".csv") || path.ends_with(".tsv")).then_some(display_path)
+            }
+            Kind::Empty
+            | Kind::Eager(_)
+            | Kind::Virtual(..)
+            | Kind::Ingest(_)
+            | Kind::Database { .. }
+            | Kind::Directory(_) => None,
+        };
+        let Some(path) = csv else {
+            return Err("Only csv files have rejected rows".into());
+        };
+        let path = match &self.transcoded {
+            Some(transcoded) => transcoded.file.path.to_string_lossy().to_string(),
+            None => path.clone(),
+        };
+        let (_, options) = split_encoding(&self.read_options);
+        let options = cmd::default_option(&options, "ignore_errors=true");
+        Ok((
+            format!(
+                "SELECT count(*) FROM read_csv({}, {options}, rejects_table='{REJECTS}')",
+                file_lit(&path)
+            ),
+            format!("SELECT * FROM {REJECTS} ORDER BY line"),
+        ))
+    }
+
+    /// Options of the reader of a data file, e.g. `delim=';', header=true`
+    pub fn read_options(&self) -> &str {
+        &self.read_options
+    }
+
+    /// Batches fed by the host program, None for other sources
+    pub fn ingest(&self) -> Option<&Ingest> {
+        match &self.kind {
+            Kind::Ingest(ingest) => Some(ingest),
+            _ => None,
+        }
+    }
+
+    /// Sheets of the workbook, empty for other sources
+    pub fn sheets(&self) -> Vec<String> {
+        match &self.kind {
+            Kind::File { path, display_path } if display_path.ends_with(".xlsx") => {
+                excel::sheets(path).unwrap_or_default()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Tables and views of the database listed when attached, empty for
+    /// other sources
+    pub fn tables(&self) -> &[String] {
+        &self.tables
+    }
+
+    /// Tables to open in their own tab alongside a database opened on its
+    /// first table, empty once a table is chosen or for other sources
+    pub fn other_tables(&self) -> &[String] {
+        if self.sql != DEFAULT_SQL || cmd::option_value(&self.read_options, "table").is_some() {
+            return &[];
+        }
+        self.tables.get(1..).unwrap_or_default()
+    }
+
+    /// Another table of the same database
+    pub fn table(&self, name: &str) -> Self {
+        let sql = format!("SELECT * FROM {}", sql::ident(name));
+        self.relation(name.into(), sql)
+    }
+
+    /// Maximum number of rows read from the file, None if read entirely
+    pub fn sample(&self) -> Option<usize> {
+        self.sample
+    }
+
+    pub fn empty(name: String) -> Result<Self> {
+        Self::new(name, Kind::Empty, String::new())
+    }
+
+    pub fn from_mem(name: String, df: DataFrame) -> Result<Self> {
+        Self::new(name, Kind::Eager(df), DEFAULT_SQL.into())
+    }
+
+    /// Source scanning a custom provider, its data is queried by duckdb and
+    /// transformations are offered to the provider first
+    pub fn from_provider(name: String, provider: impl Provider) -> Result<Self> {
+        let df = provider.scan()?;
         Self::new(
+            name,
+            Kind::Virtual(Arc::new(provider), df),
+            DEFAULT_SQL.into(),
+        )
+    }
+
+    /// Source filled progressively by batches produced in a background
+    /// thread, they all must share the same schema
+    pub fn from_batches(
+        name: String,
+        batches: impl Iterator<Item = RecordBatch> + Send + 'static,
+    ) -> Result<Self> {
+        let ingest = Ingest::spawn(move || Ok(batches.map(Ok)), None);
+        Self::new(name, Kind::Ingest(ingest), DEFAULT_SQL.into())
+    }
+
+    /// Source filled progressively by decoding a stream in a background thread
+    pub fn from_reader(
+        name: String,
+        reader: impl Read + Send + 'static,
+        format: Format,
+    ) -> Result<Self> {
+        let ingest = Ingest::read(reader, format);
+        Self::new(name, Kind::Ingest(ingest), DEFAULT_SQL.into())
+    }
+
+    /// Source following the json records sent by a WebSocket or a
+    /// server-sent events endpoint, keeping the `retain` most recent rows
+    #[cfg(feature = "live")]
+    pub fn live(url: &str, retain: usize) -> Result<Self> {
+        let endpoint = url.to_string();
+        let ingest = Ingest::spawn(move || crate::live::subscribe(&endpoint), Some(retain));
+        Self::new(url.into(), Kind::Ingest(ingest), DEFAULT_SQL.into())
+    }
+
+    /// Source of a file, a directory or a pattern, failing when it cannot be
+    /// read
+    pub fn from_path(path: &Path) -> Result<Self> {
+        if (path.is_dir() && table_format(path).is_none()) || archive::is_archive(path) {
+            let name = path.file_name().unwrap_or(path.as_os_str());
+            let dir = path.canonicalize().unwrap_or(path.to_path_buf());
+            return Self::new(
+                name.to_string_lossy().to_string(),
+                Kind::Directory(dir),
+                String::new(),
+            );
+        }
+        let display_path = path.to_string_lossy().to_string();
+        // A pattern matching nothing fails here whatever reads its files
+        if let Some(files) = expand(&display_path) {
+            files?;
+        }
+        let name = if is_pattern(&display_path) {
+            display_path.clone()
+        } else {
             path.file_stem()
                 .unwrap_or_default()
                 .to_string_lossy()
-                .to_string(),
+                .to_string()
+        };
+        // Record streams duckdb cannot read are decoded in the background
+        if let Some(format) = Format::from_path(&display_path) {
+            let path = path.to_path_buf();
+            let ingest = Ingest::spawn(
+                move || ingest::decode(std::fs::File::open(path)?, format),
+                None,
+            );
+            return Self::new(name, Kind::Ingest(ingest), DEFAULT_SQL.into());
+        }
+        let canonical = if is_pattern(&display_path) {
+            // Absolute pattern matched against the changed files
+            let (dir, pattern) = split_pattern(path);
+            dir.canonicalize().unwrap_or(dir).join(pattern)
+        } else {
+            path.canonicalize().unwrap_or(path.to_path_buf())
+        };
+        let kind = if Self::database_type(&display_path).is_some() {
+            Kind::Database {
+                display_path,
+                path: canonical,
+            }
+        } else {
             Kind::File {
-                display_path: path.to_string_lossy().to_string(),
-                path: path.canonicalize().unwrap_or(path.to_path_buf()),
-            },
-            "FROM current SELECT *".into(),
-        )
-        .unwrap()
+                display_path,
+                path: canonical,
+            }
+        };
+        Self::new(name, kind, DEFAULT_SQL.into())
     }
 
+    /// Member of a zip or tar archive, read from an extracted copy
+    pub fn from_member(archive: &Path, member: &str) -> Result<Self> {
+        let extracted = archive::extract(archive, member)?;
+        let mut source = Self::from_path(&extracted.path)?;
+        source.name = Path::new(member)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        source.extracted = Some(Arc::new(extracted));
+        Ok(source)
+    }
+
+    /// Another query of the same source, transformations are dropped as they
+    /// refer to the columns of the previous query
     pub fn query(&self, sql: String) -> Self {
         Self {
-            name: self.name.clone(),
-            kind: self.kind.clone(),
             sql,
-            db: self.db.clone(),
+            ops: Ops::default(),
+            ..self.clone()
+        }
+    }
+
+    /// Another relation of the same database, without transformations
+    pub fn relation(&self, name: String, sql: String) -> Self {
+        Self {
+            name,
+            sql,
+            ops: Ops::default(),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_ops(&self, ops: Ops) -> Self {
+        Self {
+            ops,
+            ..self.clone()
         }
     }
 
+    pub fn ops(&self) -> &Ops {
+        &self.ops
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
     pub fn path(&self) -> Option<&Path> {
         match &self.kind {
-            Kind::Empty | Kind::Eager { .. } => None,
-            Kind::File { path, .. } => Some(path),
+            Kind::Empty | Kind::Eager { .. } | Kind::Virtual(..) | Kind::Ingest(_) => None,
+            Kind::File { path, .. } | Kind::Database { path, .. } | Kind::Directory(path) => {
+                Some(path)
+            }
+        }
+    }
+
+    /// Whether a file can be opened, from its extension
+    pub fn is_supported(path: &Path) -> bool {
+        let path = path.to_string_lossy();
+        let data = path.trim_end_matches(".gz").trim_end_matches(".zst");
+        READERS.iter().any(|(ext, _)| data.ends_with(ext))
+            || path.ends_with(".sql")
+            || Self::database_type(&path).is_some()
+            || Format::from_path(&path).is_some()
+            || archive::is_archive(Path::new(path.as_ref()))
+    }
+
+    /// Directory or archive listed by the source, None for other sources
+    pub fn directory(&self) -> Option<&Path> {
+        match &self.kind {
+            Kind::Directory(dir) => Some(dir),
+            _ => None,
+        }
+    }
+
+    /// Whether the source reads every file matching a glob pattern
+    pub fn is_pattern(&self) -> bool {
+        self.display_path().is_some_and(is_pattern)
+    }
+
+    /// Whether the view reads the files listed or copied when it was created,
+    /// so a change of the source files needs a reread
+    pub fn is_snapshot(&self) -> bool {
+        let delta = self.path().and_then(table_format) == Some("delta");
+        self.is_pattern() || self.transcoded.is_some() || delta
+    }
+
+    /// Path to watch for changes and whether to watch its subdirectories,
+    /// the directory holding the matching files of a pattern
+    pub fn watched(&self) -> Option<(PathBuf, bool)> {
+        let path = self.path()?;
+        if !self.is_pattern() {
+            // Commits of a table add files to its log directory
+            return Some((path.to_path_buf(), table_format(path).is_some()));
+        }
+        let (dir, pattern) = split_pattern(path);
+        let recursive =
+            pattern.components().count() > 1 || pattern.to_string_lossy().contains("**");
+        Some((dir, recursive))
+    }
+
+    /// Whether a change to this file affects the source
+    pub fn watches(&self, changed: &Path) -> bool {
+        if let Some(dir) = self.directory() {
+            return changed == dir || changed.parent() == Some(dir);
+        }
+        match self.path() {
+            Some(path) if self.is_pattern() => glob::Pattern::new(&path.to_string_lossy())
+                .is_ok_and(|pattern| pattern.matches_path(changed)),
+            Some(path) if table_format(path).is_some() => changed.starts_with(path),
+            Some(path) => path == changed,
+            None => false,
         }
     }
 
     pub fn display_path(&self) -> Option<&str> {
         match &self.kind {
-            Kind::Empty | Kind::Eager { .. } => None,
-            Kind::File { display_path, .. } => Some(display_path),
+            Kind::Empty
+            | Kind::Eager { .. }
+            | Kind::Virtual(..)
+            | Kind::Ingest(_)
+            | Kind::Directory(_) => None,
+            Kind::File { display_path, .. } | Kind::Database { display_path, .. } => {
+                Some(display_path)
+            }
+        }
+    }
+
+    /// Metadata of the source file, format and compression are read from the
+    /// extension
+    pub fn metadata(&self) -> Option<FileMeta> {
+        let (Kind::File { path, display_path } | Kind::Database { path, display_path }) =
+            &self.kind
+        else {
+            return None;
+        };
+        // The size of a table directory does not reflect its data files
+        if table_format(path).is_some() {
+            return None;
+        }
+        let files = match expand(display_path) {
+            Some(files) => files.ok()?,
+            None => vec![path.clone()],
+        };
+        let (mut size, mut modified) = (0, None);
+        for file in &files {
+            let meta = std::fs::metadata(file).ok()?;
+            size += meta.len();
+            modified = modified.max(meta.modified().ok());
+        }
+        let (stem, compression) = [(".gz", "+gzip"), (".zst", "+zstd")]
+            .iter()
+            .find_map(|(ext, name)| display_path.strip_suffix(ext).map(|s| (s, *name)))
+            .unwrap_or((display_path, ""));
+        let format = Path::new(stem)
+            .extension()
+            .map_or(String::new(), |ext| ext.to_string_lossy().to_lowercase());
+        Some(FileMeta {
+            size,
+            modified,
+            format: format + compression,
+            files: files.len(),
+        })
+    }
+
+    /// Whether the data is kept in memory by the source itself, dropping the
+    /// loaded rows would free nothing
+    pub fn is_virtual(&self) -> bool {
+        matches!(self.kind, Kind::Virtual(..))
+    }
+
+    pub fn in_memory(&self) -> bool {
+        match self.kind {
+            Kind::Eager(_) | Kind::Virtual(..) | Kind::Ingest(_) => true,
+            Kind::Empty | Kind::File { .. } | Kind::Database { .. } | Kind::Directory(_) => false,
         }
     }
 
     /// Fast load of a in memory data frame
     fn sync_full(&self) -> Option<DataFrame> {
+        if !self.ops.is_empty() {
+            return None;
+        }
         match &self.kind {
-            Kind::Empty => Some(DataFrame::empty()),
-            Kind::Eager(df) => Some(df.clone()),
-            Kind::File { .. } => None,
+            Kind::Empty | Kind::Directory(_) if self.sql.is_empty() => Some(DataFrame::empty()),
+            Kind::Eager(df) | Kind::Virtual(_, df) if self.sql == DEFAULT_SQL => Some(df.clone()),
+            Kind::Ingest(ingest) if self.sql == DEFAULT_SQL => Some(ingest.snapshot()),
+            Kind::Empty
+            | Kind::Eager(_)
+            | Kind::Virtual(..)
+            | Kind::Ingest(_)
+            | Kind::File { .. }
+            | Kind::Database { .. }
+            | Kind::Directory(_) => None,
         }
     }
 
+    /// Result of the transformations evaluated by the provider, only when
+    /// they are missing values and sorts over its data
+    fn pushdown(&self) -> Option<Result<DataFrame>> {
+        let Kind::Virtual(provider, _) = &self.kind else {
+            return None;
+        };
+        let ops = &self.ops;
+        if self.sql != DEFAULT_SQL
+            || ops.row_id
+            || !ops.casts.is_empty()
+            || !ops.columns.is_empty()
+            || !ops.filters.is_empty()
+        {
+            return None;
+        }
+        provider.pushdown(&Pushdown {
+            not_null: &ops.not_null,
+            sort: &ops.sort,
+        })
+    }
+
+    /// Whether the complete result of this query is cached
+    pub fn is_cached(&self) -> bool {
+        self.cache.contains(&self.load_sql())
+    }
+
+    /// Forget the cached results of every query, the data changed
+    pub fn clear_cache(&self) {
+        self.cache.clear()
+    }
+
+    /// Forget the cached result of this query, to free its memory
+    pub fn uncache(&self) {
+        self.cache.remove(&self.load_sql())
+    }
+
+    /// Checkout a pooled connection ready to query the source
     pub fn conn(&self) -> Result<Connection> {
-        Ok(self.db.conn()?)
+        let con = self.checkout()?;
+        self.prepare(&con)?;
+        Ok(con)
+    }
+
+    /// Checkout a pooled connection, the source must be prepared before it is
+    /// queried
+    pub fn checkout(&self) -> Result<Connection> {
+        let con = self.db.conn(Self::init_conn)?;
+        // Shell statements can change the session, keep it to this task
+        if !self.sql.is_empty() {
+            con.discard();
+        }
+        Ok(con)
+    }
+
+    /// Bring the data read by queries up to date, which can take a while so
+    /// tasks do it in the background
+    pub fn prepare(&self, con: &Connection) -> Result<()> {
+        // The view of a file in another encoding reads its copy
+        if let (Some(copy), Kind::File { display_path, .. }) = (&self.transcoded, &self.kind) {
+            if self.pending.load(Ordering::Relaxed) {
+                copy.sync(Path::new(display_path))?;
+                for statement in &self.setup {
+                    con.execute(statement)?;
+                }
+                self.pending.store(false, Ordering::Relaxed);
+            }
+        }
+        // Queries must see the batches ingested since the last one
+        if let Kind::Ingest(ingest) = &self.kind {
+            ingest.store(con)?;
+        }
+        Ok(())
     }
 
-    pub fn describe(&self, con: Connection) -> Result<Chunks> {
+    /// Session setup of a new connection
+    fn init_conn(con: &Connection) -> crate::duckdb::Result<()> {
+        con.execute("SET enable_progress_bar=true; SET enable_progress_bar_print=false;")?;
+        // Remote objects queried from the shell load the httpfs extension on
+        // first use, remote files load it in their setup
+        con.execute("SET autoinstall_known_extensions=true; SET autoload_known_extensions=true;")
+    }
+
+    /// Summary of some columns
+    pub fn describe<'a>(
+        &self,
+        con: &Connection,
+        names: impl Iterator<Item = &'a String>,
+    ) -> Result<Chunks> {
+        let cols: Vec<_> = names.map(|name| sql::ident(name)).collect();
+        let sql = format!(
+            "SUMMARIZE SELECT {} FROM ({})",
+            cols.join(", "),
+            self.load_sql()
+        );
+        Ok(con.query(&sql)?)
+    }
+
+    /// Schema of the loaded data without loading any row
+    pub fn schema(&self) -> Result<SchemaRef> {
+        self.schema_on(&self.conn()?)
+    }
+
+    /// Schema of the loaded data using this connection
+    pub fn schema_on(&self, con: &Connection) -> Result<SchemaRef> {
         // TODO handle empty
         if let Kind::Empty = self.kind {
             if self.sql.is_empty() {
-                return Err("Nothing to summarize".into());
+                return Err("Empty source".into());
             }
         }
-        let sql = format!("SUMMARIZE {}", self.sql);
-        Ok(con.query(&sql)?)
+        let chunks = con.query(&format!("SELECT * FROM ({}) LIMIT 0", self.load_sql()))?;
+        Ok(chunks.schema()?)
+    }
+
+    /// Source listing the schema changes from another source, both schemas
+    /// are fetched in a background thread
+    pub fn schema_diff(old: Arc<Source>, new: Arc<Source>) -> Result<Self> {
+        let name = format!("{} ⇄ {}", old.name(), new.name());
+        let ingest = Ingest::spawn(
+            move || Ok(std::iter::once(crate::schema_diff::batch(&old, &new))),
+            None,
+        );
+        Self::new(name, Kind::Ingest(ingest), DEFAULT_SQL.into())
+    }
+
+    /// Cumulated number of rows at the end of each row group when the source
+    /// streams a parquet file as is, empty otherwise
+    fn row_groups(&self, con: &Connection) -> Vec<usize> {
+        let Kind::File { display_path, .. } = &self.kind else {
+            return vec![];
+        };
+        if !display_path.ends_with(".parquet")
+            || self.sql != DEFAULT_SQL
+            || !self.ops.is_empty()
+            || self.sample.is_some()
+        {
+            return vec![];
+        }
+        let sql = format!(
+            "SELECT row_group_num_rows FROM parquet_metadata({}) \
+            WHERE column_id = 0 ORDER BY file_name, row_group_id",
+            file_lit(display_path)
+        );
+        // Progress is optional, errors are ignored
+        let metadata =
+            || -> Result<DataFrame> { con.query(&sql)?.map(|d| d.map_err(|e| e.into())).collect() };
+        let Ok(df) = metadata() else {
+            return vec![];
+        };
+        (0..df.num_rows())
+            .filter_map(|row| df.value(row, 0)?.parse::<usize>().ok())
+            .scan(0, |end, rows| {
+                *end += rows;
+                Some(*end)
+            })
+            .collect()
     }
 
     pub fn load(&self, con: Connection) -> Result<Chunks> {
-        let sql = self.init_sql();
-        Ok(con.query(sql)?)
+        Ok(con.query(&self.load_sql())?)
+    }
+
+    /// Everything executed for this source, the current view definition
+    /// followed by the query with all transformations applied
+    pub fn effective_sql(&self) -> String {
+        let mut script = String::new();
+        match self.kind {
+            Kind::Eager(_) => script.push_str("-- current is bound to an in memory data frame\n"),
+            Kind::Virtual(..) => script.push_str("-- current is bound to the data of a provider\n"),
+            Kind::Ingest(_) => script.push_str("-- current is bound to the ingested batches\n"),
+            Kind::Empty | Kind::File { .. } | Kind::Database { .. } | Kind::Directory(_) => {}
+        }
+        for statement in &self.setup {
+            script.push_str(statement);
+            script.push_str(";\n");
+        }
+        let query = self.load_sql();
+        if !query.is_empty() {
+            script.push_str(&sql::format(&query).unwrap_or(query));
+            script.push_str(";\n");
+        }
+        script
+    }
+
+    /// Query with all transformations applied
+    pub fn load_sql(&self) -> String {
+        self.ops.apply(self.init_sql())
     }
 
     pub fn init_sql(&self) -> &str {
         if self.sql.is_empty() {
             match self.kind {
-                Kind::Empty => "",
-                Kind::Eager(_) | Kind::File { .. } => "SELECT * FROM current",
+                Kind::Empty | Kind::Directory(_) => "",
+                Kind::Eager(_)
+                | Kind::Virtual(..)
+                | Kind::Ingest(_)
+                | Kind::File { .. }
+                | Kind::Database { .. } => "SELECT * FROM current",
             }
         } else {
             &self.sql
@@ -376,11 +1459,140 @@ impl Source {
     }
 }
 
+/// Whether a path is the URI of an object fetched over http or S3
+fn is_remote(path: &str) -> bool {
+    ["https://", "http://", "s3://"]
+        .iter()
+        .any(|scheme| path.starts_with(scheme))
+}
+
+/// Split the encoding label from the read options, the encoding is handled by
+/// dtex instead of duckdb
+fn split_encoding(read_options: &str) -> (Option<&str>, String) {
+    match cmd::option_value(read_options, "encoding") {
+        Some(value) => (
+            Some(value.trim_matches('\'')),
+            cmd::remove_option(read_options, "encoding"),
+        ),
+        None => (None, read_options.into()),
+    }
+}
+
+/// Duckdb extension scanning a Delta Lake or Iceberg table directory
+fn table_format(path: &Path) -> Option<&'static str> {
+    if path.join("_delta_log").is_dir() {
+        Some("delta")
+    } else if path.join("metadata").join("version-hint.text").is_file()
+        || iceberg_metadata(path).is_some()
+    {
+        Some("iceberg")
+    } else {
+        None
+    }
+}
+
+/// Latest metadata file of an Iceberg table, they are numbered as
+/// `v{N}.metadata.json` or `{N}-{uuid}.metadata.json`
+fn iceberg_metadata(path: &Path) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(path.join("metadata")).ok()?;
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            let num = name.strip_suffix(".metadata.json")?.trim_start_matches('v');
+            let digits = num.split(['-', '.']).next()?;
+            Some((digits.parse::<u64>().ok()?, path))
+        })
+        .max_by_key(|(num, _)| *num)
+        .map(|(_, path)| path)
+}
+
+/// Whether a local path is a glob pattern, and not a file whose name has
+/// wildcard characters
+fn is_pattern(path: &str) -> bool {
+    !is_remote(path) && has_wildcard(path) && !Path::new(path).exists()
+}
+
+fn has_wildcard(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Literal of a path read by duckdb, which expands wildcards, escaped so that
+/// a file whose name has wildcard characters only matches itself
+fn file_lit(path: &str) -> String {
+    if is_remote(path) || !has_wildcard(path) || is_pattern(path) {
+        return sql::lit(path);
+    }
+    let escaped: String = path
+        .chars()
+        .map(|c| match c {
+            '*' | '?' | '[' => format!("[{c}]"),
+            c => c.to_string(),
+        })
+        .collect();
+    sql::lit(&escaped)
+}
+
+/// Files matching a glob pattern, None if the path is not a pattern
+fn expand(display_path: &str) -> Option<Result<Vec<PathBuf>>> {
+    is_pattern(display_path).then(|| {
+        let files = glob::glob(display_path)?.collect::<std::result::Result<Vec<_>, _>>()?;
+        if files.is_empty() {
+            return Err("No file matches the pattern".into());
+        }
+        Ok(files)
+    })
+}
+
+/// Split a pattern into the directory before its first wildcard and the rest
+fn split_pattern(path: &Path) -> (PathBuf, PathBuf) {
+    let wildcard = |c: &std::path::Component| has_wildcard(&c.as_os_str().to_string_lossy());
+    let dir: PathBuf = path.components().take_while(|c| !wildcard(c)).collect();
+    let pattern = path.components().skip_while(|c| !wildcard(c)).collect();
+    if dir.as_os_str().is_empty() {
+        (PathBuf::from("."), pattern)
+    } else {
+        (dir, pattern)
+    }
+}
+
+/// Whether the file lives on a network or userspace file system, where
+/// reading it entirely can take minutes
+#[cfg(target_os = "linux")]
+fn slow_storage(path: &Path) -> bool {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    const SLOW: [u32; 6] = [
+        0x6969,     // NFS
+        0x517B,     // SMB
+        0xFE534D42, // SMB2
+        0xFF534D42, // CIFS
+        0x65735546, // FUSE (sshfs, rclone, ...)
+        0x01021997, // 9P (WSL, VMs)
+    ];
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stat = std::mem::MaybeUninit::<libc::statfs>::uninit();
+    if unsafe { libc::statfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return false;
+    }
+    let kind = unsafe { stat.assume_init() }.f_type as u32;
+    SLOW.contains(&kind)
+}
+
+/// File system kinds are only known on linux
+#[cfg(not(target_os = "linux"))]
+fn slow_storage(_: &Path) -> bool {
+    false
+}
+
 #[derive(Clone)]
 pub struct DataFrameImpl {
     schema: SchemaRef,
     pub batchs: Vec<RecordBatch>,
     row_count: usize,
+    mem_size: usize,
 }
 
 impl DataFrameImpl {
@@ -388,10 +1600,12 @@ impl DataFrameImpl {
         if self.schema.fields.is_empty() {
             self.schema = batch.schema();
             self.row_count = batch.num_rows();
+            self.mem_size = batch.get_array_memory_size();
             self.batchs = vec![batch];
         } else {
             assert_eq!(self.schema, batch.schema());
             self.row_count += batch.num_rows();
+            self.mem_size += batch.get_array_memory_size();
             self.batchs.push(batch);
         }
     }
@@ -409,6 +1623,7 @@ impl Default for DataFrameImpl {
             batchs: vec![],
             schema: Arc::new(Schema::empty()),
             row_count: 0,
+            mem_size: 0,
         }
     }
 }
@@ -446,10 +1661,151 @@ impl DataFrame {
         col.build()
     }
 
+    /// Extract rows at ascending positions into a single batch
+    pub fn rows(&self, rows: impl Iterator<Item = usize>) -> RecordBatch {
+        let mut slices = Vec::new();
+        let mut batchs = self.0.batchs.iter();
+        let mut current = batchs.next();
+        let mut start = 0;
+        for row in rows {
+            while let Some(batch) = current {
+                if row < start + batch.num_rows() {
+                    slices.push(batch.slice(row - start, 1));
+                    break;
+                }
+                start += batch.num_rows();
+                current = batchs.next();
+            }
+        }
+        concat_batches(self.schema(), &slices).unwrap()
+    }
+
+    /// Count, sum and mean of the non null values of a numeric column over some rows
+    pub fn stats(
+        &self,
+        rows: impl Iterator<Item = usize>,
+        idx: usize,
+    ) -> Option<(usize, f64, f64)> {
+        if !self.schema().field(idx).data_type().is_numeric() {
+            return None;
+        }
+        let batch = self.rows(rows);
+        let array = cast(batch.column(idx), &DataType::Float64).ok()?;
+        let array = array.as_primitive::<Float64Type>();
+        let (count, sum) = array
+            .iter()
+            .flatten()
+            .fold((0, 0.), |(count, sum), v| (count + 1, sum + v));
+        Some((count, sum, sum / count.max(1) as f64))
+    }
+
+    /// Raw bytes of a binary cell, None if null or not binary
+    pub fn bytes(&self, row: usize, idx: usize) -> Option<Vec<u8>> {
+        let batch = self.rows(std::iter::once(row));
+        let array = batch.column(idx);
+        if batch.num_rows() == 0 || array.is_null(0) {
+            return None;
+        }
+        match array.data_type() {
+            DataType::Binary => Some(array.as_binary::<i32>().value(0).to_vec()),
+            DataType::LargeBinary => Some(array.as_binary::<i64>().value(0).to_vec()),
+            _ => None,
+        }
+    }
+
+    /// Data type and unformatted value of a cell, strings are quoted and
+    /// floats keep their full precision
+    pub fn raw(&self, row: usize, idx: usize) -> Option<(DataType, String)> {
+        let batch = self.rows(std::iter::once(row));
+        if batch.num_rows() == 0 {
+            return None;
+        }
+        let array = batch.column(idx);
+        let value = if array.logical_nulls().is_some_and(|n| n.is_null(0)) {
+            "null".into()
+        } else {
+            match array.data_type() {
+                DataType::Utf8 => format!("{:?}", array.as_string::<i32>().value(0)),
+                DataType::LargeUtf8 => format!("{:?}", array.as_string::<i64>().value(0)),
+                DataType::Float32 => format!("{:?}", array.as_primitive::<Float32Type>().value(0)),
+                DataType::Float64 => format!("{:?}", array.as_primitive::<Float64Type>().value(0)),
+                _ => ArrayFormatter::try_new(array, &FormatOptions::default())
+                    .unwrap()
+                    .value(0)
+                    .to_string(),
+            }
+        };
+        Some((array.data_type().clone(), value))
+    }
+
+    /// Formatted value of a cell, None if null
+    pub fn value(&self, row: usize, idx: usize) -> Option<String> {
+        self.values(idx, row, 1).pop().flatten()
+    }
+
+    /// Formatted values of a column over consecutive rows, None if null
+    pub fn values(&self, idx: usize, mut skip: usize, mut take: usize) -> Vec<Option<String>> {
+        let mut values = Vec::with_capacity(take);
+        for batch in &self.0.batchs {
+            let len = batch.num_rows();
+            if take == 0 {
+                break;
+            } else if skip >= len {
+                skip -= len;
+                continue;
+            }
+            let array = batch.column(idx);
+            let nulls = array.logical_nulls();
+            let fmt = ArrayFormatter::try_new(array, &FormatOptions::default()).unwrap();
+            let end = len.min(skip + take);
+            values.extend((skip..end).map(|i| {
+                let null = nulls.as_ref().is_some_and(|n| n.is_null(i));
+                (!null).then(|| fmt.value(i).to_string())
+            }));
+            take -= end - skip;
+            skip = 0;
+        }
+        values
+    }
+
+    /// Find the closest row after or before `from` whose nullity in a column matches
+    pub fn seek_null(&self, idx: usize, from: usize, forward: bool, null: bool) -> Option<usize> {
+        let mut offset = 0;
+        let mut found = None;
+        for batch in &self.0.batchs {
+            let len = batch.num_rows();
+            let nulls = batch.column(idx).logical_nulls();
+            let matches = |i: &usize| nulls.as_ref().is_some_and(|n| n.is_null(*i)) == null;
+            if forward {
+                if from < offset + len {
+                    let start = (from + 1).saturating_sub(offset);
+                    if let Some(i) = (start..len).find(matches) {
+                        return Some(offset + i);
+                    }
+                }
+            } else {
+                let end = from.saturating_sub(offset).min(len);
+                if let Some(i) = (0..end).rev().find(matches) {
+                    found = Some(offset + i);
+                }
+                if offset + len >= from {
+                    break;
+                }
+            }
+            offset += len;
+        }
+        found
+    }
+
     pub fn num_rows(&self) -> usize {
         self.0.row_count
     }
 
+    /// Memory used by the loaded batches in bytes
+    pub fn mem_size(&self) -> usize {
+        self.0.mem_size
+    }
+
     pub fn num_columns(&self) -> usize {
         self.0.schema.fields().len()
     }
@@ -470,6 +1826,31 @@ impl DataFrame {
             None => *self = self.concat(iter),
         }
     }
+
+    /// Whether many small batches could be merged
+    pub fn is_fragmented(&self) -> bool {
+        self.0.batchs.len() > self.0.row_count / COMPACT_ROWS + 1
+    }
+
+    /// Merge consecutive small batches into batches of at least `COMPACT_ROWS`
+    pub fn compact(&self) -> Self {
+        let mut batches = Vec::new();
+        let mut group = Vec::new();
+        let mut rows = 0;
+        for batch in &self.0.batchs {
+            rows += batch.num_rows();
+            group.push(batch.clone());
+            if rows >= COMPACT_ROWS {
+                batches.push(concat_batches(self.schema(), &group).unwrap());
+                group.clear();
+                rows = 0;
+            }
+        }
+        if !group.is_empty() {
+            batches.push(concat_batches(self.schema(), &group).unwrap());
+        }
+        batches.into_iter().collect()
+    }
 }
 
 impl From<RecordBatch> for DataFrame {
@@ -1,8 +1,9 @@
 use std::time::{Duration, Instant};
 
+use crate::style::Glyphs;
+
 const SPINNER_DELAY: Duration = Duration::from_millis(200);
 pub const SPINNER_TICK: u128 = 100;
-const SPINNERS_UNICODE: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
 
 pub struct Spinner {
     start: Option<Instant>,
@@ -13,7 +14,12 @@ impl Spinner {
         Self { start: None }
     }
 
-    pub fn state(&mut self, is_loading: bool) -> Option<char> {
+    /// Time spent loading so far
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.start.map(|start| start.elapsed())
+    }
+
+    pub fn state(&mut self, is_loading: bool, glyphs: &Glyphs) -> Option<&'static str> {
         if is_loading && self.start.is_none() {
             self.start.replace(Instant::now());
         } else if !is_loading && self.start.is_some() {
@@ -24,8 +30,8 @@ impl Spinner {
             // Wait for some frame to prevent flashing
             if elapsed > SPINNER_DELAY {
                 let tick = ((elapsed - SPINNER_DELAY).as_millis() / SPINNER_TICK) as usize
-                    % SPINNERS_UNICODE.len();
-                return Some(SPINNERS_UNICODE[tick]);
+                    % glyphs.spinner.len();
+                return Some(glyphs.spinner[tick]);
             }
         }
         None
@@ -0,0 +1,527 @@
+use sqlparser::{
+    dialect::DuckDbDialect,
+    tokenizer::{Token, Tokenizer, Whitespace},
+};
+
+use crate::error::Result;
+
+/// Keywords written in upper case by the formatter, other words are kept
+const KEYWORDS: &[&str] = &[
+    "ALL",
+    "AND",
+    "ANTI",
+    "AS",
+    "ASC",
+    "ASOF",
+    "BETWEEN",
+    "BY",
+    "CASE",
+    "CROSS",
+    "DESC",
+    "DISTINCT",
+    "ELSE",
+    "END",
+    "EXCEPT",
+    "EXCLUDE",
+    "EXISTS",
+    "FALSE",
+    "FIRST",
+    "FROM",
+    "FULL",
+    "GLOB",
+    "GROUP",
+    "HAVING",
+    "ILIKE",
+    "IN",
+    "INNER",
+    "INTERSECT",
+    "IS",
+    "JOIN",
+    "LAST",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "NATURAL",
+    "NOT",
+    "NULL",
+    "NULLS",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "POSITIONAL",
+    "QUALIFY",
+    "RECURSIVE",
+    "RIGHT",
+    "SELECT",
+    "SEMI",
+    "THEN",
+    "TRUE",
+    "UNION",
+    "USING",
+    "VALUES",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+];
+/// Keywords starting a clause on its own line
+const CLAUSES: &[&str] = &[
+    "EXCEPT",
+    "FROM",
+    "GROUP",
+    "HAVING",
+    "INTERSECT",
+    "LIMIT",
+    "OFFSET",
+    "ORDER",
+    "QUALIFY",
+    "SELECT",
+    "UNION",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+];
+/// Keywords qualifying a join
+const JOINS: &[&str] = &[
+    "ANTI",
+    "ASOF",
+    "CROSS",
+    "FULL",
+    "INNER",
+    "LEFT",
+    "NATURAL",
+    "POSITIONAL",
+    "RIGHT",
+    "SEMI",
+];
+const INDENT: &str = "    ";
+
+/// Quote an identifier
+pub fn ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quote a string literal
+pub fn lit(str: &str) -> String {
+    format!("'{}'", str.replace('\'', "''"))
+}
+
+/// Tokens of a query with their byte offset
+pub fn tokens(query: &str) -> Result<Vec<(usize, Token)>> {
+    let tokens = Tokenizer::new(&DuckDbDialect {}, query).tokenize_with_location()?;
+    let mut chars = query.char_indices().peekable();
+    let (mut line, mut column) = (1, 1);
+    Ok(tokens
+        .into_iter()
+        .map(|t| {
+            while (line, column) < (t.location.line, t.location.column) {
+                match chars.next() {
+                    Some((_, '\n')) => (line, column) = (line + 1, 1),
+                    Some(_) => column += 1,
+                    None => break,
+                }
+            }
+            (chars.peek().map_or(query.len(), |(i, _)| *i), t.token)
+        })
+        .collect())
+}
+
+/// Reformat a query with upper case keywords, each clause on its own line
+/// and the select list, conditions and subqueries indented
+///
+/// The query is only tokenized, so comments and syntax unknown to the parser
+/// are kept as written.
+pub fn format(query: &str) -> Result<String> {
+    let tokens: Vec<_> = tokens(query)?
+        .into_iter()
+        .filter(|(_, t)| {
+            !matches!(
+                t,
+                Token::Whitespace(Whitespace::Space | Whitespace::Tab | Whitespace::Newline)
+                    | Token::EOF
+            )
+        })
+        .collect();
+    let text = |i: usize| {
+        let end = tokens.get(i + 1).map_or(query.len(), |t| t.0);
+        query[tokens[i].0..end].trim_end()
+    };
+    let is_comment = |i: usize| matches!(tokens[i].1, Token::Whitespace(_));
+    let next = |i: usize| (i + 1..tokens.len()).find(|j| !is_comment(*j));
+    // Upper case keyword of an unquoted word, `left(` is a function call
+    let keyword = |i: usize| match &tokens[i].1 {
+        Token::Word(w) if w.quote_style.is_none() => {
+            let upper = w.value.to_ascii_uppercase();
+            let call = matches!(upper.as_str(), "LEFT" | "RIGHT")
+                && next(i).is_some_and(|j| tokens[j].1 == Token::LParen);
+            (KEYWORDS.binary_search(&upper.as_str()).is_ok() && !call).then_some(upper)
+        }
+        _ => None,
+    };
+    let is_clause = |i: usize| keyword(i).is_some_and(|k| CLAUSES.contains(&k.as_str()));
+    // Closing parenthesis of an opening one
+    let matching = |i: usize| {
+        let mut depth = 0;
+        (i..tokens.len()).find(|j| {
+            match tokens[*j].1 {
+                Token::LParen => depth += 1,
+                Token::RParen => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        })
+    };
+    // Whether the select list starting after `i` has several items
+    let several = |i: usize| {
+        let mut depth = 0;
+        for (j, (_, token)) in tokens.iter().enumerate().skip(i + 1) {
+            match token {
+                Token::LParen | Token::LBracket | Token::LBrace => depth += 1,
+                Token::RParen if depth == 0 => break,
+                Token::RParen | Token::RBracket | Token::RBrace => depth -= 1,
+                Token::Comma if depth == 0 => return true,
+                Token::SemiColon => break,
+                _ if depth == 0 && is_clause(j) => break,
+                _ => {}
+            }
+        }
+        false
+    };
+    let newline = |out: &mut String, depth: usize| {
+        out.truncate(out.trim_end().len());
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        for _ in 0..depth {
+            out.push_str(INDENT);
+        }
+    };
+
+    let mut out = String::new();
+    // Open parentheses and brackets, with whether they hold a subquery
+    let mut parens: Vec<bool> = vec![];
+    // Current clause of each nested query and whether its select list
+    // has an item per line
+    let mut levels = vec![(String::new(), false)];
+    // Start of a select list with an item per line
+    let mut first_item = None;
+    let mut between = false;
+    // No space before the next token
+    let mut glue = true;
+    // Previous token and whether it is a keyword
+    let mut prev: Option<(&Token, bool)> = None;
+    for (i, (_, token)) in tokens.iter().enumerate() {
+        let top = !matches!(parens.last(), Some(false));
+        let depth = levels.len() - 1;
+        let (clause, items) = levels.last().unwrap().clone();
+        if let Token::Whitespace(comment) = token {
+            if !glue {
+                out.push(' ');
+            }
+            out.push_str(text(i));
+            glue = matches!(comment, Whitespace::SingleLineComment { .. });
+            if glue {
+                newline(&mut out, depth + !clause.is_empty() as usize);
+            }
+            continue;
+        }
+        let kw = keyword(i);
+        let after_keyword = prev.is_some_and(|(_, kw)| kw);
+        match kw.as_deref() {
+            Some(k) if top && CLAUSES.contains(&k) => {
+                newline(&mut out, depth);
+                let mut items = false;
+                if k == "SELECT" && several(i) {
+                    let mut j = next(i);
+                    if j.and_then(keyword)
+                        .is_some_and(|k| k == "DISTINCT" || k == "ALL")
+                    {
+                        j = j.and_then(next);
+                        if j.and_then(keyword).is_some_and(|k| k == "ON") {
+                            j = j.and_then(next).and_then(matching).and_then(next);
+                        }
+                    }
+                    first_item = j;
+                    items = true;
+                }
+                *levels.last_mut().unwrap() = (k.into(), items);
+                between = false;
+                glue = true;
+            }
+            Some(k) if top && !after_keyword && (k == "JOIN" || JOINS.contains(&k)) => {
+                newline(&mut out, depth);
+                *levels.last_mut().unwrap() = ("JOIN".into(), false);
+                glue = true;
+            }
+            Some("BETWEEN") => between = true,
+            Some("AND") if between => between = false,
+            Some("AND" | "OR")
+                if top && matches!(clause.as_str(), "WHERE" | "HAVING" | "QUALIFY" | "JOIN") =>
+            {
+                newline(&mut out, depth + 1);
+                glue = true;
+            }
+            _ => {}
+        }
+        if first_item == Some(i) {
+            newline(&mut out, depth + 1);
+            glue = true;
+        }
+        // Operands after which an operator is binary
+        let operand = match prev {
+            Some((Token::Word(_), kw)) => !kw,
+            Some((token, _)) => matches!(
+                token,
+                Token::Number(..)
+                    | Token::SingleQuotedString(_)
+                    | Token::DoubleQuotedString(_)
+                    | Token::RParen
+                    | Token::RBracket
+            ),
+            None => false,
+        };
+        let attached = match token {
+            Token::Comma | Token::Period | Token::SemiColon | Token::Colon => true,
+            Token::RParen | Token::RBracket | Token::RBrace | Token::DoubleColon => true,
+            // Function call or subscript
+            Token::LParen | Token::LBracket => operand,
+            _ => false,
+        };
+        if !glue && !attached {
+            out.push(' ');
+        }
+        let close = matches!(token, Token::RParen | Token::RBracket | Token::RBrace);
+        if close && parens.pop() == Some(true) {
+            levels.pop();
+            newline(&mut out, levels.len() - 1);
+        }
+        match &kw {
+            Some(kw) => out.push_str(kw),
+            None => out.push_str(text(i)),
+        }
+        glue = match token {
+            Token::LParen => {
+                let sub = next(i)
+                    .and_then(keyword)
+                    .is_some_and(|k| matches!(k.as_str(), "SELECT" | "WITH" | "FROM" | "VALUES"));
+                parens.push(sub);
+                if sub {
+                    levels.push((String::new(), false));
+                }
+                true
+            }
+            Token::Comma if top && items => {
+                newline(&mut out, depth + 1);
+                true
+            }
+            Token::SemiColon => {
+                newline(&mut out, 0);
+                levels = vec![(String::new(), false)];
+                parens.clear();
+                true
+            }
+            Token::LBracket | Token::LBrace => {
+                parens.push(false);
+                true
+            }
+            Token::Period | Token::DoubleColon => true,
+            // Sign of a number
+            Token::Minus | Token::Plus => !operand,
+            _ => false,
+        };
+        prev = Some((token, kw.is_some()));
+    }
+    out.truncate(out.trim_end().len());
+    Ok(out)
+}
+
+/// Predicate matching rows equal to a cell value, null is compared with IS
+/// The value literal is implicitly cast by duckdb to the column type
+pub fn eq(name: &str, value: Option<&str>, negate: bool) -> String {
+    let not = if negate { "NOT " } else { "" };
+    match value {
+        Some(value) if negate => format!("{} IS DISTINCT FROM {}", ident(name), lit(value)),
+        Some(value) => format!("{} = {}", ident(name), lit(value)),
+        None => format!("{} IS {not}NULL", ident(name)),
+    }
+}
+
+/// Predicate matching rows with one of these values, None standing for null
+pub fn is_in(name: &str, values: &[Option<String>]) -> String {
+    let list: Vec<_> = values.iter().flatten().map(|v| lit(v)).collect();
+    let mut preds = Vec::new();
+    if !list.is_empty() {
+        preds.push(format!("{} IN ({})", ident(name), list.join(", ")));
+    }
+    if values.iter().any(Option::is_none) {
+        preds.push(format!("{} IS NULL", ident(name)));
+    }
+    preds.join(" OR ")
+}
+
+/// Predicate matching rows whose value contains a pattern or match a regex
+pub fn matches(name: &str, pattern: &str, regex: bool) -> String {
+    if regex {
+        format!("regexp_matches({}::VARCHAR, {})", ident(name), lit(pattern))
+    } else {
+        format!(
+            "{}::VARCHAR ILIKE {} ESCAPE '\\'",
+            ident(name),
+            lit(&format!("%{}%", escape_like(pattern)))
+        )
+    }
+}
+
+/// Escape the wildcards of a LIKE pattern so they match literally
+fn escape_like(pattern: &str) -> String {
+    pattern
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Expression converting a column to a type, null when conversion fail
+///
+/// Accept `int`, `double`, `text`, `date` optionally followed by a strptime format
+/// or any duckdb type
+pub fn try_cast(name: &str, target: &str) -> String {
+    let (ty, fmt) = target
+        .split_once(' ')
+        .map_or((target, ""), |(ty, fmt)| (ty, fmt.trim()));
+    match ty.to_lowercase().as_str() {
+        "date" if !fmt.is_empty() => {
+            format!("TRY_STRPTIME({}::VARCHAR, {})::DATE", ident(name), lit(fmt))
+        }
+        "int" => format!("TRY_CAST({} AS BIGINT)", ident(name)),
+        "double" => format!("TRY_CAST({} AS DOUBLE)", ident(name)),
+        "text" => format!("TRY_CAST({} AS VARCHAR)", ident(name)),
+        "date" => format!("TRY_CAST({} AS DATE)", ident(name)),
+        _ => format!("TRY_CAST({} AS {target})", ident(name)),
+    }
+}
+
+/// Query reporting for each text column the percentage of values parsable as other types
+pub fn type_report(query: &str, names: &[String]) -> String {
+    let selects: Vec<_> = names
+        .iter()
+        .map(|name| {
+            let col = ident(name);
+            let pct = |ty: &str| {
+                format!("round(100 * count(TRY_CAST({col} AS {ty})) / count({col}), 1)")
+            };
+            format!(
+                "SELECT {} AS column, count({col}) AS values, {} AS int, {} AS double, {} AS date, {} AS bool FROM ({query})",
+                lit(name),
+                pct("BIGINT"),
+                pct("DOUBLE"),
+                pct("DATE"),
+                pct("BOOLEAN"),
+            )
+        })
+        .collect();
+    format!(
+        "SELECT *, CASE WHEN int = 100 THEN 'int' WHEN double = 100 THEN 'double' WHEN date = 100 THEN 'date' WHEN bool = 100 THEN 'bool' END AS suggest FROM ({})",
+        selects.join(" UNION ALL ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyword_tables_sorted() {
+        // Keywords are looked up with a binary search
+        assert!(KEYWORDS.windows(2).all(|w| w[0] < w[1]));
+        assert!(CLAUSES.iter().chain(JOINS).all(|k| KEYWORDS.contains(k)));
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(ident("a\"b"), "\"a\"\"b\"");
+        assert_eq!(lit("it's"), "'it''s'");
+    }
+
+    #[test]
+    fn clauses_and_items() {
+        assert_eq!(
+            format("select a, b from t where a > 1 and b < 2 order by a").unwrap(),
+            "SELECT\n    a,\n    b\nFROM t\nWHERE a > 1\n    AND b < 2\nORDER BY a"
+        );
+        assert_eq!(
+            format("select count(*) from t").unwrap(),
+            "SELECT count(*)\nFROM t"
+        );
+        assert_eq!(format("select 1; select 2").unwrap(), "SELECT 1;\nSELECT 2");
+    }
+
+    #[test]
+    fn keyword_casing() {
+        assert_eq!(
+            format("SeLeCt a FrOm t WhErE a iS nOt NuLl").unwrap(),
+            "SELECT a\nFROM t\nWHERE a IS NOT NULL"
+        );
+        // Words that are not keywords keep their case
+        assert_eq!(
+            format("select Name from Users").unwrap(),
+            "SELECT Name\nFROM Users"
+        );
+    }
+
+    #[test]
+    fn quoted_identifiers() {
+        assert_eq!(
+            format("select \"from\", \"Select\" from \"where\"").unwrap(),
+            "SELECT\n    \"from\",\n    \"Select\"\nFROM \"where\""
+        );
+    }
+
+    #[test]
+    fn string_literals() {
+        assert_eq!(
+            format("select a from t where b = 'select from where and'").unwrap(),
+            "SELECT a\nFROM t\nWHERE b = 'select from where and'"
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            format("select a from t -- from where\nwhere a = 1").unwrap(),
+            "SELECT a\nFROM t -- from where\nWHERE a = 1"
+        );
+    }
+
+    #[test]
+    fn joins_and_functions() {
+        assert_eq!(
+            format("select * from t left join u on t.id = u.id and t.x = u.x").unwrap(),
+            "SELECT *\nFROM t\nLEFT JOIN u ON t.id = u.id\n    AND t.x = u.x"
+        );
+        // `left(` is a function call and the AND of BETWEEN stays inline
+        assert_eq!(
+            format("select left(s, 2) from t where x between 1 and 2").unwrap(),
+            "SELECT left(s, 2)\nFROM t\nWHERE x BETWEEN 1 AND 2"
+        );
+    }
+
+    #[test]
+    fn subqueries() {
+        assert_eq!(
+            format("select * from (select a from t) where a in (select b from u)").unwrap(),
+            "SELECT *\nFROM (\n    SELECT a\n    FROM t\n)\nWHERE a IN (\n    SELECT b\n    FROM u\n)"
+        );
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(
+            format("select -1, a - 1, x::int, l[1] from t").unwrap(),
+            "SELECT\n    -1,\n    a - 1,\n    x::int,\n    l[1]\nFROM t"
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TABS: AtomicUsize = AtomicUsize::new(0);
+static ROWS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+static TASKS: AtomicUsize = AtomicUsize::new(0);
+
+/// Resource usage of the running explorer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of open tabs
+    pub tabs: usize,
+    /// Number of loaded rows across all tabs
+    pub rows: usize,
+    /// Memory used by the loaded Arrow data in bytes
+    pub bytes: usize,
+    /// Number of running background tasks
+    pub tasks: usize,
+}
+
+/// Current resource usage, can be called from any thread
+pub fn stats() -> Stats {
+    Stats {
+        tabs: TABS.load(Ordering::Relaxed),
+        rows: ROWS.load(Ordering::Relaxed),
+        bytes: BYTES.load(Ordering::Relaxed),
+        tasks: TASKS.load(Ordering::Relaxed),
+    }
+}
+
+/// Publish the usage of the open tabs
+pub(crate) fn record(tabs: usize, rows: usize, bytes: usize) {
+    TABS.store(tabs, Ordering::Relaxed);
+    ROWS.store(rows, Ordering::Relaxed);
+    BYTES.store(bytes, Ordering::Relaxed);
+}
+
+/// Count a running background task until dropped
+pub(crate) struct TaskGuard;
+
+impl TaskGuard {
+    pub fn new() -> Self {
+        TASKS.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        TASKS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
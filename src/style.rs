@@ -1,3 +1,4 @@
+use serde::Deserialize;
 use tui::{none, Color, Style};
 
 pub fn primary() -> Style {
@@ -16,6 +17,64 @@ pub fn selected() -> Style {
     none().fg(Color::DarkYellow)
 }
 
+pub fn marked() -> Style {
+    none().fg(Color::Magenta).bold()
+}
+
+/// Color of the nth chart series
+pub fn series(n: usize) -> Style {
+    const COLORS: [Color; 6] = [
+        Color::Blue,
+        Color::Green,
+        Color::Yellow,
+        Color::Magenta,
+        Color::Cyan,
+        Color::Red,
+    ];
+    none().fg(COLORS[n % COLORS.len()])
+}
+
+pub fn changed() -> Style {
+    none().fg(Color::Yellow).bold()
+}
+
+/// Marker of cells cut by the column width
+pub fn truncated() -> Style {
+    none().fg(Color::DarkCyan).bold()
+}
+
+/// Colors of the grid backgrounds, as 256 colors palette indexes
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Background of the cursor row and column
+    pub crosshair: u8,
+    /// Background of every other row
+    pub stripe: u8,
+    /// Stripe rows from the start
+    pub zebra: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            crosshair: 236,
+            stripe: 235,
+            zebra: false,
+        }
+    }
+}
+
+impl Theme {
+    pub fn crosshair(&self, style: Style) -> Style {
+        style.bg(Color::AnsiValue(self.crosshair))
+    }
+
+    pub fn stripe(&self, style: Style) -> Style {
+        style.bg(Color::AnsiValue(self.stripe))
+    }
+}
+
 pub fn separator() -> Style {
     none().fg(Color::DarkGrey).dim()
 }
@@ -51,3 +110,57 @@ pub fn tab_selected() -> Style {
 pub(crate) fn error() -> Style {
     none().fg(Color::Red).bold()
 }
+
+/// Symbols drawn by the interface
+pub struct Glyphs {
+    /// Index of a marked row and count of marked rows
+    pub marked: &'static str,
+    /// Sort direction in a column header
+    pub asc: &'static str,
+    pub desc: &'static str,
+    /// End of a cut cell and count of cut cells
+    pub truncated: &'static str,
+    /// Index of a pinned row
+    pub pinned: &'static str,
+    /// Columns out of the screen on each side
+    pub left: &'static str,
+    pub right: &'static str,
+    /// Periodic refresh interval
+    pub watch: &'static str,
+    /// Tab sharing its layout
+    pub linked: &'static str,
+    /// Frames of the loading spinner
+    pub spinner: &'static [&'static str],
+    /// Done and remaining parts of a progress bar
+    pub bar: (&'static str, &'static str),
+}
+
+pub const UNICODE: Glyphs = Glyphs {
+    marked: "✓",
+    asc: "▲",
+    desc: "▼",
+    truncated: "…",
+    pinned: "•",
+    left: "«",
+    right: "»",
+    watch: "⟳",
+    linked: "⇅",
+    spinner: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+    bar: ("█", "░"),
+};
+
+/// Plain words and ascii for screen readers, with a still spinner as they
+/// would announce every frame of an animation
+pub const ACCESSIBLE: Glyphs = Glyphs {
+    marked: "*",
+    asc: " asc ",
+    desc: " desc ",
+    truncated: "~",
+    pinned: ">",
+    left: "left",
+    right: "right",
+    watch: "every ",
+    linked: "linked",
+    spinner: &["*"],
+    bar: ("#", "-"),
+};
@@ -1,5 +1,12 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use arrow::{
+    datatypes::{DataType, SchemaRef},
+    record_batch::RecordBatch,
+};
 use libduckdb_sys::duckdb_query_progress_type;
 use tui::{
     crossterm::event::{KeyCode as Key, KeyEvent},
@@ -7,15 +14,40 @@ use tui::{
 };
 
 use crate::{
+    catalog::CatalogView,
+    chart::{self, Chart},
+    clipboard,
+    cmd::{self, Action, CmdLine, Match, Outcome},
     describe::DescriberView,
-    fmt::GridBuffer,
-    grid::Grid,
+    distinct::DistinctView,
+    embed::{Activation, Selection},
+    error::{Result, StrError},
+    excel::{self, SheetPicker},
+    export,
+    files::FilesView,
+    filter::{self, FilterBuilder},
+    fmt::{rtrim, str_width, wrap, Decimal, GridBuffer, Interval, Size},
+    global_search::GlobalSearchView,
+    grid::{Grid, Settings},
+    group::GroupView,
+    histogram::HistogramView,
+    history::{self, HistoryView},
+    image::Format,
+    inspect::Inspect,
+    missing::MissingView,
     navigator::Navigator,
+    ops::Ops,
+    plot::PlotView,
+    progress::{self, Progress},
+    report::ReportView,
+    search::Search,
     shell::Shell,
-    source::{FrameLoader, Source, StreamingFrame},
+    source::{DataFrame, FileMeta, FrameLoader, Source, StreamingFrame},
     spinner::Spinner,
-    style,
-    task::Runner,
+    sql,
+    style::{self, Glyphs},
+    task::{DuckTask, Runner, Task, Tasks},
+    validate::{self, Rule},
     view::{View, ViewState},
     OnKey,
 };
@@ -23,8 +55,69 @@ use crate::{
 enum State {
     Normal,
     Description(DescriberView),
-    Shell(SourceView),
+    Shell(Box<SourceView>),
     Nav(Navigator),
+    Cmd(CmdLine),
+    Distinct(DistinctView),
+    Chart(Box<dyn Chart>),
+    Report(ReportView),
+    Catalog(CatalogView),
+    Group(GroupView),
+    Inspect(Inspect),
+    History(HistoryView),
+    Filter(FilterBuilder),
+    GlobalSearch(GlobalSearchView),
+    Sheets(SheetPicker),
+    Files(FilesView),
+}
+
+/// Maximum width of the raw value of the current cell in the status line
+const RAW_WIDTH: usize = 30;
+/// Default interval between watch refreshes
+const WATCH_INTERVAL: Duration = Duration::from_secs(30);
+/// Interval between checks for newly ingested batches
+const INGEST_INTERVAL: Duration = Duration::from_millis(200);
+/// Maximum delay between the keys of a chord
+const CHORD_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Periodic refresh of the current query
+struct Watch {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl Watch {
+    /// Time until the next refresh if enabled
+    fn remaining(&self) -> Option<Duration> {
+        self.last
+            .map(|last| self.interval.saturating_sub(last.elapsed()))
+    }
+}
+
+/// Maximum number of lines of the cell preview pane
+pub const PREVIEW_LINES: usize = 3;
+
+/// Default number of viewports loaded ahead when scrolling down
+pub const PREFETCH: usize = 5;
+
+/// Rows streamed at once while looking for a cell further down
+const SEEK_STEP: usize = 1 << 16;
+/// Rows streamed at once while loading in the background
+const IDLE_STEP: usize = 1 << 16;
+
+/// Rows expected to fit in the idle load memory cap, at most a step ahead of
+/// the loaded ones
+fn idle_goal(df: &DataFrame, cap: usize) -> usize {
+    let row_size = df.mem_size() / df.num_rows().max(1);
+    let fit = cap.saturating_sub(df.mem_size()) / row_size.max(1);
+    df.num_rows() + fit.clamp(1, IDLE_STEP)
+}
+
+/// Pending forward search of a null or non null cell
+struct Seek {
+    idx: usize,
+    from: usize,
+    null: bool,
 }
 
 pub struct SourceView {
@@ -33,16 +126,120 @@ pub struct SourceView {
     loader: FrameLoader,
     pub grid: Grid,
     load_error: Option<String>,
+    seek: Option<Seek>,
+    /// Reloading the same query, keep the cursor position
+    refreshing: bool,
+    /// Viewports loaded ahead when scrolling down
+    pub prefetch: usize,
+    /// Keep loading in the background up to this memory size in bytes
+    pub idle_load: Option<usize>,
+    /// In-flight background queries
+    tasks: Tasks,
+    /// Loading postponed until the view is first drawn
+    deferred: bool,
+    /// Data dropped while unfocused, keep the grid as is when reloaded
+    evicted: bool,
+    /// Background merge of the small batches of a frame
+    compaction: Option<(DataFrame, Task<(), Option<DataFrame>>)>,
+    /// Time spent loading the rows of the last query, accumulated between
+    /// ticks as rows can be loaded long after the query started
+    took: Duration,
+    ticked: Instant,
+    /// Transformations before each change, restored by undo
+    undo: Vec<Ops>,
+    /// The last load applies a transformation change, undone if it fails
+    changed_ops: bool,
+    /// Error of a transformation that was undone
+    pub rejected: Option<String>,
 }
 
 impl SourceView {
-    pub fn new(source: Arc<Source>, runner: &Runner) -> Self {
-        Self {
+    pub fn new(source: Arc<Source>, runner: &Runner, settings: Settings) -> Self {
+        let mut view = Self {
             source: source.clone(),
             frame: StreamingFrame::empty(),
-            loader: FrameLoader::load(source, runner),
-            grid: Grid::new(),
+            loader: FrameLoader::Finished(None),
+            grid: Grid::new(settings),
+            load_error: None,
+            seek: None,
+            refreshing: false,
+            prefetch: PREFETCH,
+            idle_load: None,
+            tasks: Tasks::default(),
+            deferred: false,
+            evicted: false,
+            compaction: None,
+            took: Duration::ZERO,
+            ticked: Instant::now(),
+            undo: vec![],
+            changed_ops: false,
+            rejected: None,
+        };
+        view.set_source(source, runner);
+        view
+    }
+
+    /// View that only start loading when first drawn
+    pub fn deferred(source: Arc<Source>, settings: Settings) -> Self {
+        Self {
+            source,
+            frame: StreamingFrame::empty(),
+            loader: FrameLoader::Finished(None),
+            grid: Grid::new(settings),
             load_error: None,
+            seek: None,
+            refreshing: false,
+            prefetch: PREFETCH,
+            idle_load: None,
+            tasks: Tasks::default(),
+            deferred: true,
+            evicted: false,
+            compaction: None,
+            took: Duration::ZERO,
+            ticked: Instant::now(),
+            undo: vec![],
+            changed_ops: false,
+            rejected: None,
+        }
+    }
+
+    /// Merge small batches in the background once idle
+    pub fn compact(&mut self, runner: &Runner) {
+        let df = self.frame.df();
+        if self.compaction.is_none()
+            && !self.frame.is_loading()
+            && self.loader.is_loading().is_none()
+            && df.is_fragmented()
+        {
+            let from = df.clone();
+            let task = runner.task((), None, move |ctx| {
+                let compacted = from.compact();
+                ctx.lock(|r| *r = Some(compacted))
+            });
+            self.compaction = Some((df.clone(), task));
+        }
+    }
+
+    /// Schema of the loaded data
+    pub fn schema(&self) -> &SchemaRef {
+        self.frame.df().schema()
+    }
+
+    /// Drop the loaded data, it will be loaded again when next drawn
+    pub fn evict(&mut self) {
+        self.source.uncache();
+        let schema = self.frame.df().schema().clone();
+        self.frame = StreamingFrame::full(RecordBatch::new_empty(schema).into());
+        self.loader = FrameLoader::Finished(None);
+        self.deferred = true;
+        self.evicted = true;
+    }
+
+    /// Start a deferred loading
+    pub fn start(&mut self, runner: &Runner) {
+        if self.deferred {
+            self.deferred = false;
+            self.load(self.source.clone(), runner, false);
         }
     }
 
@@ -53,42 +250,281 @@ impl SourceView {
             loader: FrameLoader::Finished(None),
             grid: self.grid.clone(),
             load_error: None,
+            seek: None,
+            refreshing: false,
+            prefetch: self.prefetch,
+            idle_load: self.idle_load,
+            tasks: Tasks::default(),
+            deferred: false,
+            evicted: false,
+            compaction: None,
+            took: Duration::ZERO,
+            ticked: Instant::now(),
+            undo: vec![],
+            changed_ops: false,
+            rejected: None,
         }
     }
 
     pub fn set_source(&mut self, source: Arc<Source>, runner: &Runner) {
+        self.load(source, runner, true);
+    }
+
+    /// Whether the last load failed
+    pub fn failed(&self) -> bool {
+        self.load_error.is_some() || self.frame.err().is_some()
+    }
+
+    /// Execute the current query again
+    pub fn refresh(&mut self, runner: &Runner) {
+        self.source.clear_cache();
+        self.load(self.source.clone(), runner, false);
+        self.refreshing = true;
+    }
+
+    /// Start loading a source, superseding the running load unless it is the
+    /// same query and `coalesce` is set
+    fn load(&mut self, source: Arc<Source>, runner: &Runner, coalesce: bool) {
+        let key = source.load_sql();
         self.source = source.clone();
+        self.took = Duration::ZERO;
+        self.ticked = Instant::now();
+        self.seek = None;
+        self.refreshing = false;
+        self.changed_ops = false;
+        if self.deferred {
+            return;
+        }
+        if coalesce && self.loader.is_loading().is_some() && self.tasks.running("load", &key) {
+            return;
+        }
         self.loader = FrameLoader::load(source, runner);
+        if let Some(handle) = self.loader.handle() {
+            self.tasks.track("load", key, handle);
+        }
+    }
+
+    /// Name of the column under the cursor
+    pub fn col_name(&self) -> Option<String> {
+        let df = self.frame.df();
+        (df.num_columns() > 0).then(|| df.schema().field(self.grid.c_idx()).name().clone())
+    }
+
+    /// Cycle the sort of the current column, as an additional key if `multi`
+    pub fn sort(&mut self, multi: bool, runner: &Runner) {
+        let Some(name) = self.col_name() else {
+            return;
+        };
+        if multi {
+            self.grid.sort.cycle(&name);
+        } else {
+            self.grid.sort.toggle(&name);
+        }
+        let mut ops = self.source.ops().clone();
+        ops.sort = self.grid.sort.keys().to_vec();
+        self.set_ops(ops, runner);
+    }
+
+    /// Filter rows equal, or not, to the current cell
+    pub fn filter(&mut self, negate: bool, runner: &Runner) {
+        let df = self.frame.df();
+        if df.num_columns() == 0 || df.num_rows() == 0 {
+            return;
+        }
+        let idx = self.grid.c_idx();
+        let name = df.schema().field(idx).name();
+        let value = df.value(self.grid.nav.c_row(), idx);
+        self.add_filter(sql::eq(name, value.as_deref(), negate), runner);
+    }
+
+    /// Append a computed column
+    pub fn add_column(&mut self, expr: String, runner: &Runner) {
+        let mut ops = self.source.ops().clone();
+        ops.columns.push(expr);
+        self.set_ops(ops, runner);
+    }
+
+    /// Convert the current column to another type
+    pub fn cast(&mut self, target: &str, runner: &Runner) {
+        let Some(name) = self.col_name() else {
+            return;
+        };
+        let mut ops = self.source.ops().clone();
+        let expr = sql::try_cast(&name, target);
+        match ops.casts.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, e)) => *e = expr,
+            None => ops.casts.push((name, expr)),
+        }
+        self.set_ops(ops, runner);
+    }
+
+    /// Toggle dropping rows with a null in the current column, or in any visible column if `all`
+    pub fn toggle_not_null(&mut self, all: bool, runner: &Runner) {
+        let df = self.frame.df();
+        if df.num_columns() == 0 {
+            return;
+        }
+        let schema = df.schema();
+        let names: Vec<String> = if all {
+            self.grid
+                .projected()
+                .iter()
+                .map(|idx| schema.field(*idx).name().clone())
+                .collect()
+        } else {
+            vec![schema.field(self.grid.c_idx()).name().clone()]
+        };
+        let mut ops = self.source.ops().clone();
+        if names.iter().all(|n| ops.not_null.contains(n)) {
+            ops.not_null.retain(|n| !names.contains(n));
+        } else {
+            for name in names {
+                if !ops.not_null.contains(&name) {
+                    ops.not_null.push(name);
+                }
+            }
+        }
+        self.set_ops(ops, runner);
+    }
+
+    /// Toggle the row_id column
+    pub fn toggle_row_id(&mut self, runner: &Runner) {
+        let mut ops = self.source.ops().clone();
+        ops.row_id = !ops.row_id;
+        self.set_ops(ops, runner);
+    }
+
+    pub fn add_filter(&mut self, filter: String, runner: &Runner) {
+        let mut ops = self.source.ops().clone();
+        ops.filters.push(filter);
+        self.set_ops(ops, runner);
+    }
+
+    /// Restore the transformations preceding the last change
+    pub fn undo(&mut self, runner: &Runner) {
+        if let Some(ops) = self.undo.pop() {
+            self.set_source(Arc::new(self.source.with_ops(ops)), runner);
+        }
+    }
+
+    fn set_ops(&mut self, ops: Ops, runner: &Runner) {
+        self.undo.push(self.source.ops().clone());
+        self.set_source(Arc::new(self.source.with_ops(ops)), runner);
+        self.changed_ops = true;
+    }
+
+    /// Move the cursor to the next or previous null or non null cell of the current column
+    pub fn seek(&mut self, null: bool, forward: bool) {
+        let df = self.frame.df();
+        if df.num_columns() == 0 {
+            return;
+        }
+        let idx = self.grid.c_idx();
+        let from = self.grid.nav.c_row();
+        match df.seek_null(idx, from, forward, null) {
+            Some(row) => self.grid.focus(row, idx),
+            None if forward => self.seek = Some(Seek { idx, from, null }),
+            None => {}
+        }
+    }
+
+    /// Continue a pending seek with newly streamed rows
+    fn tick_seek(&mut self) {
+        if let Some(seek) = &mut self.seek {
+            let df = self.frame.df();
+            match df.seek_null(seek.idx, seek.from, true, seek.null) {
+                Some(row) => {
+                    self.grid.focus(row, seek.idx);
+                    self.seek = None;
+                }
+                None if self.frame.is_streaming() && self.frame.err().is_none() => {
+                    seek.from = seek.from.max(df.num_rows().saturating_sub(1));
+                }
+                None => self.seek = None,
+            }
+        }
     }
 }
 
 impl View for SourceView {
     fn tick(&mut self) -> ViewState {
+        if self.loader.is_loading().is_some() || self.frame.is_loading() {
+            self.took += self.ticked.elapsed();
+        }
+        self.ticked = Instant::now();
         // Tick
         match self.loader.tick() {
             Some(Ok(new)) => {
+                let same_schema = new.df().schema() == self.frame.df().schema();
+                if self.evicted && same_schema {
+                    // Same data, keep cursor and selection
+                } else if self.refreshing && same_schema {
+                    self.grid.highlight_changes(self.frame.df().clone());
+                } else {
+                    self.grid.reload(same_schema, &self.source.ops().sort);
+                }
+                self.refreshing = false;
+                self.evicted = false;
                 self.frame = new;
-                self.grid = Grid::new();
                 self.load_error = None;
             }
+            Some(Err(e)) if self.changed_ops => {
+                // Keep the rows of the previous transformations, they still
+                // match the loaded frame
+                let ops = self.undo.pop().unwrap_or_default();
+                self.grid.sort_by(&ops.sort);
+                self.source = Arc::new(self.source.with_ops(ops));
+                self.changed_ops = false;
+                self.rejected = Some(e.0);
+            }
             Some(Err(e)) => self.load_error = Some(e.0),
             None => {}
         }
-        self.frame.goal(self.grid.nav.goal().saturating_add(1));
+        let df = self.frame.df();
+        let goal = match &self.seek {
+            Some(_) => df.num_rows() + SEEK_STEP,
+            None => {
+                let goal = self.grid.nav.prefetch_goal(self.prefetch, df.num_rows());
+                let idle = self
+                    .idle_load
+                    .filter(|cap| df.mem_size() < *cap)
+                    .map_or(0, |cap| idle_goal(df, cap));
+                goal.max(idle).saturating_add(1)
+            }
+        };
+        self.frame.goal(goal);
         self.frame.tick();
+        if let Some((from, task)) = &self.compaction {
+            if let Some(compacted) = task.lock(Option::take) {
+                self.frame.compacted(from, compacted);
+                self.compaction = None;
+            }
+        }
+        self.tick_seek();
 
         ViewState {
             loading: if let Some(progress) = self.loader.is_loading() {
-                Some(("load", progress))
+                Some(("load".into(), progress))
             } else if self.frame.is_loading() {
-                Some((
-                    "stream",
-                    duckdb_query_progress_type {
-                        percentage: -1.,
-                        rows_processed: 0,
-                        total_rows_to_process: 0,
-                    },
-                ))
+                let rows = self.frame.df().num_rows();
+                Some(match self.frame.row_group() {
+                    Some((current, count, total)) => (
+                        format!("row group {current}/{count}").into(),
+                        duckdb_query_progress_type {
+                            percentage: rows as f64 * 100. / total.max(1) as f64,
+                            rows_processed: rows as u64,
+                            total_rows_to_process: total as u64,
+                        },
+                    ),
+                    None => (
+                        "stream".into(),
+                        duckdb_query_progress_type {
+                            percentage: -1.,
+                            rows_processed: 0,
+                            total_rows_to_process: 0,
+                        },
+                    ),
+                })
             } else {
                 None
             },
@@ -102,35 +538,251 @@ impl View for SourceView {
 
 pub struct Tab {
     pub view: SourceView,
-    runner: Runner,
+    pub runner: Runner,
     shell: Shell,
     state: State,
     spinner: Spinner,
+    pub notice: Option<Result<String>>,
+    search: Option<Search>,
+    preview: bool,
+    image: Option<(Vec<u8>, u16)>,
+    rules: Vec<Rule>,
+    watch: Watch,
+    /// Description left before its computation finished
+    describing: Option<DescriberView>,
+    /// Last time this tab was drawn
+    focused: Instant,
+    /// Running export of the full result and its destination
+    export: Option<(String, DuckTask<()>)>,
+    /// File read again with other options or sample size
+    reread: Option<DuckTask<Source>>,
+    /// Source to open in a new tab
+    pub open: Option<Source>,
+    /// Pattern to search in every tab
+    pub search_all: Option<String>,
+    /// Id of the tab, stable while tabs are closed
+    pub id: usize,
+    /// Tab id, row and column to jump to
+    pub jump: Option<(usize, usize, usize)>,
+    /// Scroll together with the other linked tabs
+    pub linked: bool,
+    /// Row activated with Enter
+    pub activated: Option<Activation>,
+    /// Size, age and format of the source file
+    meta: Option<FileMeta>,
+    /// Number of ingested batches shown
+    ingested: usize,
+    /// Statistics of the marked cells and the frame, column and selection
+    /// they were computed for
+    stats: Option<(StatsKey, Option<Stats>)>,
+    /// Display settings of the grids opened by this tab
+    pub settings: Settings,
+    /// Start of a pending `g` chord
+    chord: Option<Instant>,
 }
 
+/// Loaded frame, column index and selection changes
+type StatsKey = (usize, usize, usize);
+/// Count, sum and mean of numeric cells
+type Stats = (usize, f64, f64);
+
 impl Tab {
-    pub fn open(runner: Runner, source: Source) -> Self {
-        let source = Arc::new(source);
+    pub fn open(runner: Runner, source: Source, settings: Settings) -> Self {
+        let view = SourceView::new(Arc::new(source), &runner, settings);
+        Self::with_view(runner, view, settings)
+    }
+
+    /// Open a tab that only start loading when first focused
+    pub fn open_deferred(runner: Runner, source: Source, settings: Settings) -> Self {
+        let view = SourceView::deferred(Arc::new(source), settings);
+        Self::with_view(runner, view, settings)
+    }
+
+    fn with_view(runner: Runner, view: SourceView, settings: Settings) -> Self {
+        let meta = view.source.metadata();
+        // Let the user choose among the sheets of a workbook instead of
+        // silently showing the first one
+        let sheets = view.source.sheets();
+        let state = if let Some(dir) = view.source.directory() {
+            State::Files(FilesView::new(dir.to_path_buf(), settings))
+        } else if sheets.len() > 1
+            && cmd::option_value(view.source.read_options(), "sheet").is_none()
+        {
+            State::Sheets(SheetPicker::new(sheets))
+        } else {
+            State::Normal
+        };
         Self {
-            state: State::Normal,
-            shell: Shell::new(source.init_sql()),
-            view: SourceView::new(source, &runner),
+            state,
+            shell: Shell::new(view.source.init_sql()),
+            view,
             spinner: Spinner::new(),
+            notice: None,
+            search: None,
+            preview: false,
+            image: None,
+            rules: vec![],
+            watch: Watch {
+                interval: WATCH_INTERVAL,
+                last: None,
+            },
+            describing: None,
+            focused: Instant::now(),
+            export: None,
+            reread: None,
+            open: None,
+            search_all: None,
+            id: 0,
+            jump: None,
+            linked: false,
+            activated: None,
+            meta,
+            ingested: 0,
+            stats: None,
             runner,
+            settings,
+            chord: None,
         }
     }
 
     pub fn draw(&mut self, c: &mut Canvas, buf: &mut GridBuffer) -> bool {
+        self.focused = Instant::now();
+        if self.chord_remaining() == Some(Duration::ZERO) {
+            self.chord = None;
+            self.state = State::Nav(Navigator::new(self.view.grid.nav.clone()));
+        }
+        self.view.start(&self.runner);
+        self.view.compact(&self.runner);
+        let source = self.view.source.clone();
+        if let Some(ingest) = source.ingest() {
+            let batches = ingest.batches();
+            if batches != self.ingested
+                && self.view.loader.is_loading().is_none()
+                && !self.view.frame.is_loading()
+            {
+                self.ingested = batches;
+                self.view.refresh(&self.runner);
+            }
+            if let Some(err) = ingest.take_err() {
+                self.notice = Some(Err(err.into()));
+            }
+        }
+        if let Some(err) = self.view.rejected.take() {
+            self.notice = Some(Err(err.into()));
+        }
+        if let Some(result) = self.export.as_mut().and_then(|(_, task)| task.tick()) {
+            let (path, _) = self.export.take().unwrap();
+            self.notice = Some(result.map(|_| format!("Full result exported to {path}")));
+        }
+        if let Some(result) = self.reread.as_mut().and_then(|task| task.tick()) {
+            self.reread = None;
+            match result {
+                Ok(source) => {
+                    self.view.source = Arc::new(source);
+                    self.view.refresh(&self.runner);
+                }
+                Err(err) => self.notice = Some(Err(err)),
+            }
+        }
+        if let State::Files(files) = &mut self.state {
+            match files.opened() {
+                Some(Ok(source)) => self.open = Some(source),
+                Some(Err(err)) => self.notice = Some(Err(err)),
+                None => {}
+            }
+        }
+        if let State::Chart(chart) = &mut self.state {
+            let glyphs = self.settings.glyphs();
+            return draw_chart(chart.as_mut(), &mut self.spinner, glyphs, c);
+        }
         let status_line = c.reserve_btm(1);
-        let state_line = match &self.state {
-            State::Normal | State::Description(_) => c.reserve_btm(0),
-            State::Shell(_) | State::Nav(_) => c.reserve_btm(1),
+        let state_height = match &self.state {
+            State::Normal
+            | State::Description(_)
+            | State::Distinct(_)
+            | State::Report(_)
+            | State::Catalog(_)
+            | State::Group(_)
+            | State::GlobalSearch(_)
+            | State::Files(_) => 0,
+            State::Shell(view) => {
+                let columns: Vec<_> = self
+                    .view
+                    .frame
+                    .df()
+                    .schema()
+                    .fields()
+                    .iter()
+                    .map(|f| f.name().clone())
+                    .collect();
+                self.shell
+                    .load_signatures(self.view.source.clone(), &self.runner);
+                self.shell
+                    .load_relations(self.view.source.clone(), &self.runner);
+                let err = view.load_error.as_deref().or(view.frame.err());
+                let suggestion = self.shell.suggest(err, &columns) as usize;
+                let hint = self.shell.hint().is_some() as usize;
+                self.shell.height() + hint + suggestion + self.shell.lint(&columns).len()
+            }
+            State::Nav(_) | State::Cmd(_) => 1,
+            State::Inspect(inspect) => inspect.height(c.width()),
+            State::History(history) => history.height(c.width()),
+            State::Filter(builder) => builder.height(),
+            State::Sheets(picker) => picker.height(),
+            State::Chart(_) => unreachable!(),
+        };
+        let state_line = c.reserve_btm(state_height);
+
+        // Statistics of the marked cells of the current column, computed
+        // again only when the frame, the column or the marks change
+        let stats = match self.state {
+            State::Normal if self.view.grid.selection.len() > 0 => {
+                let df = self.view.frame.df();
+                let grid = &self.view.grid;
+                let key = (
+                    Arc::as_ptr(&df.0) as usize,
+                    grid.c_idx(),
+                    grid.selection.changes(),
+                );
+                match &self.stats {
+                    Some((cached, stats)) if *cached == key => *stats,
+                    _ => {
+                        let stats = (df.num_columns() > 0)
+                            .then(|| df.stats(grid.selection.rows(), grid.c_idx()))
+                            .flatten();
+                        self.stats = Some((key, stats));
+                        stats
+                    }
+                }
+            }
+            _ => None,
         };
 
         // Tick
+        if let Some(search) = &mut self.search {
+            if search.tick() {
+                let grid = &mut self.view.grid;
+                if let Some((row, col)) = search.first_from(grid.nav.c_row()) {
+                    grid.focus(row, col)
+                }
+            }
+        }
+        let retry = matches!(self.state, State::Normal) && self.view.failed();
+        let sample = self
+            .view
+            .source
+            .sample()
+            .filter(|_| matches!(self.state, State::Normal));
         let view: &mut dyn View = match &mut self.state {
-            State::Shell(view) => view,
+            State::Shell(view) => view.as_mut(),
             State::Description(desrc) => desrc,
+            State::Distinct(distinct) => distinct,
+            State::Report(report) => report,
+            State::Catalog(catalog) => catalog,
+            State::Group(group) => group,
+            State::History(history) => history,
+            State::GlobalSearch(global) => global,
+            State::Files(files) => files,
             _ => &mut self.view,
         };
         let ViewState {
@@ -140,35 +792,121 @@ impl Tab {
             frame,
             grid,
         } = view.tick();
+        let search = self.search.as_ref();
+        let export = self.export.as_ref();
+        let loading = loading
+            .or_else(|| search.and_then(|s| s.is_loading().map(|p| ("search".into(), p))))
+            .or_else(|| export.map(|(_, task)| ("export".into(), task.progress())))
+            .or_else(|| {
+                let reread = self.reread.as_ref();
+                reread.map(|task| ("reading".into(), task.progress()))
+            });
+        let err = err.or_else(|| search.and_then(|s| s.err()));
 
-        let spinner = self.spinner.state(loading.is_some());
+        let glyphs = self.settings.glyphs();
+        let spinner = self.spinner.state(loading.is_some(), glyphs);
+        let bar = loading
+            .as_ref()
+            .and_then(|(_, p)| Progress::new(p, self.spinner.elapsed()));
+
+        // Full value of the current cell
+        let (row, idx) = (grid.nav.c_row(), grid.c_idx());
+        let in_frame = row < frame.nb_row() && idx < frame.nb_col();
+        let value = ((self.preview || self.settings.accessible) && in_frame)
+            .then(|| frame.value(row, idx))
+            .flatten();
+        let preview = self.preview.then_some(value.as_ref());
+        let image = (self.preview && in_frame)
+            .then(|| frame.bytes(row, idx))
+            .flatten()
+            .and_then(|bytes| Format::detect(&bytes).map(|format| (format, bytes)));
+        let raw = in_frame.then(|| frame.raw(row, idx)).flatten();
+        // Current cell read by screen readers
+        let announce = self.settings.accessible.then(|| {
+            if in_frame {
+                format!(
+                    "row {}, column {}: {}",
+                    row + 1,
+                    frame.col_name(idx),
+                    value.as_deref().unwrap_or("null")
+                )
+            } else {
+                "no cell".into()
+            }
+        });
+        let label = image
+            .as_ref()
+            .map(|(format, bytes)| format!("[{} image, {} bytes]", format.name(), bytes.len()));
+        let mut preview_lines = match (&label, &preview) {
+            (Some(label), _) => vec![label.as_str()],
+            (None, Some(Some(value))) => wrap(value, c.width(), PREVIEW_LINES),
+            (None, Some(None)) => vec!["null"],
+            (None, None) => vec![],
+        };
+        self.image = None;
+        if let Some((format, bytes)) = image {
+            if format.displayable() {
+                // Leave room for the image above its label
+                preview_lines.splice(0..0, [""; PREVIEW_LINES]);
+                self.image = Some((bytes, (1 + state_height + preview_lines.len()) as u16));
+            }
+        }
+        let preview_line = c.reserve_btm(preview_lines.len());
+        let announce_line = c.reserve_btm(self.settings.accessible as usize);
 
         // Print error message
         if let Some(err) = &err {
+            if retry {
+                c.btm().draw("Press r to retry", style::index());
+            }
             for line in err.lines().rev() {
                 c.btm().draw(line, style::error());
             }
         }
+        // Print notice message
+        match &self.notice {
+            Some(Ok(msg)) => {
+                c.btm().draw(msg, style::progress());
+            }
+            Some(Err(err)) => {
+                c.btm().draw(&err.0, style::error());
+            }
+            None => {}
+        }
+        if let Some(rows) = sample {
+            c.btm().draw(
+                format_args!(
+                    "First {rows} rows of a large or remote file, press U to load everything"
+                ),
+                style::progress(),
+            );
+        }
         // Draw grid
-        let GridUI { col_name, status } = grid.draw(c, buf, frame);
+        let GridUI {
+            col_name,
+            selected,
+            truncated,
+            status,
+            cols,
+        } = grid.draw(c, buf, frame);
+        let progress = grid.nav.progress();
 
         // Draw full screen info if frame is empty
         if frame.nb_row() == 0 {
-            if let Some((task, progress)) = loading {
+            if let Some((task, progress)) = &loading {
                 // Loading bar
                 if spinner.is_some() {
-                    let msg = if progress.percentage > 0. {
-                        format!(
-                            "{task} - {}/{} {:>2.0}%",
+                    let msg = match &bar {
+                        Some(bar) => format!(
+                            "{task} {} {bar} - {}/{}",
+                            bar.bar(progress::SCREEN_BAR, glyphs),
                             progress.rows_processed,
                             progress.total_rows_to_process,
-                            progress.percentage
-                        )
-                    } else {
-                        format!("{task}")
+                        ),
+                        None => task.to_string(),
                     };
                     let pad_top = c.height().saturating_sub(1) / 2;
-                    let pad_left = c.width().saturating_sub(msg.len()) / 2;
+                    let pad_left = c.width().saturating_sub(str_width(&msg)) / 2;
                     for _ in 0..pad_top {
                         c.line("", none());
                     }
@@ -201,6 +939,18 @@ impl Tab {
                 State::Description(_) => ("DESC", style::state_other()),
                 State::Shell(_) => ("SQL", style::state_action()),
                 State::Nav(_) => ("GOTO", style::state_action()),
+                State::Cmd(_) => ("CMD", style::state_action()),
+                State::Inspect(_) => ("INSP", style::state_other()),
+                State::History(_) => ("HIST", style::state_other()),
+                State::Filter(_) => ("FLTR", style::state_action()),
+                State::GlobalSearch(_) => ("FIND", style::state_other()),
+                State::Sheets(_) => ("XLSX", style::state_action()),
+                State::Files(_) => ("DIR", style::state_other()),
+                State::Distinct(_) => ("VALS", style::state_other()),
+                State::Report(_) => ("RPRT", style::state_other()),
+                State::Catalog(_) => ("CTLG", style::state_other()),
+                State::Group(_) => ("GRP", style::state_other()),
+                State::Chart(_) => unreachable!(),
             },
             Status::Size => ("SIZE", style::state_action()),
             Status::Projection => ("PROJ", style::state_alternate()),
@@ -208,17 +958,12 @@ impl Tab {
         l.draw(format_args!(" {status} "), style);
         l.draw(" ", style::primary());
 
-        if let Some((task, progress)) = loading {
+        if let Some((task, _)) = &loading {
             if let Some(c) = spinner {
                 l.rdraw(format_args!("{c}"), style::progress());
-                if progress.percentage > 0. {
+                if let Some(bar) = &bar {
                     l.rdraw(
-                        format_args!(
-                            " {}/{} {:>2.0}%",
-                            progress.rows_processed,
-                            progress.total_rows_to_process,
-                            progress.percentage
-                        ),
+                        format_args!(" {} {bar}", bar.bar(progress::STATUS_BAR, glyphs)),
                         style::progress(),
                     );
                 }
@@ -229,13 +974,84 @@ impl Tab {
             if streaming {
                 l.rdraw(format_args!(" ~"), style::primary());
             } else {
-                l.rdraw(
-                    format_args!(" {:>3}%", self.grid().nav.progress()),
-                    style::primary(),
-                );
+                l.rdraw(format_args!(" {:>3}%", progress), style::primary());
             }
         }
 
+        let mem_size = self.mem_size();
+        if mem_size > 0 {
+            l.rdraw(format_args!(" {}", Size(mem_size)), style::primary());
+        }
+        if let Some(search) = search {
+            l.rdraw(
+                format_args!(" /{} {}", search.pattern, search.nb_match()),
+                style::selected(),
+            );
+        }
+        if self.watch.last.is_some() {
+            l.rdraw(
+                format_args!(" {}{}", glyphs.watch, Interval(self.watch.interval)),
+                style::selected(),
+            );
+        }
+        let ops = self.view.source.ops();
+        if !ops.not_null.is_empty() {
+            l.rdraw(
+                format_args!(" not null {}", ops.not_null.len()),
+                style::selected(),
+            );
+        }
+        if !ops.filters.is_empty() {
+            l.rdraw(
+                format_args!(" filter {}", ops.filters.len()),
+                style::selected(),
+            );
+        }
+        if let Some((count, sum, mean)) = stats {
+            l.rdraw(
+                format_args!(" n={count} Σ={} μ={}", Decimal(sum), Decimal(mean)),
+                style::marked(),
+            );
+        }
+        if selected > 0 {
+            l.rdraw(
+                format_args!(" {selected}{}", glyphs.marked),
+                style::marked(),
+            );
+        }
+        if truncated > 0 {
+            l.rdraw(
+                format_args!(" {truncated}{}", glyphs.truncated),
+                style::truncated(),
+            );
+        }
+        let right = cols.total.saturating_sub(cols.last + 1);
+        if cols.total > 0 && (cols.first > 0 || right > 0 || cols.hidden > 0) {
+            if cols.hidden > 0 {
+                l.rdraw(format_args!(" {} hidden", cols.hidden), style::separator());
+            }
+            l.rdraw(
+                format_args!(
+                    " {} {} | cols {}-{}/{} | {} {}",
+                    glyphs.left,
+                    cols.first,
+                    cols.first + 1,
+                    cols.last + 1,
+                    cols.total,
+                    right,
+                    glyphs.right
+                ),
+                style::separator(),
+            );
+        }
+        if let Some((ty, value)) = &raw {
+            let mut fmt_buf = String::new();
+            l.rdraw(
+                format_args!(" {}", rtrim(value, &mut fmt_buf, RAW_WIDTH)),
+                style::index(),
+            );
+            l.rdraw(format_args!(" {ty}"), style::separator());
+        }
         if let Some(name) = col_name {
             l.rdraw(name, style::primary());
             l.rdraw(" ", style::primary());
@@ -243,34 +1059,343 @@ impl Tab {
         if let Some(path) = &self.view.source.display_path() {
             l.draw(path, style::progress());
         }
+        if let Some(meta) = &self.meta {
+            l.draw(format_args!(" {meta}"), style::index());
+        }
+
+        // Draw preview
+        c.consume(preview_line);
+        let style = match preview {
+            Some(None) => style::separator(),
+            _ => style::primary(),
+        };
+        for line in preview_lines.iter().rev() {
+            c.btm().draw(line, style);
+        }
+
+        // Announce the current cell for screen readers
+        if let Some(msg) = announce {
+            let mut fmt_buf = String::new();
+            let mut l = c.consume(announce_line).btm();
+            l.draw(rtrim(msg, &mut fmt_buf, l.width()), style::primary());
+            if let State::Normal = self.state {
+                l.cursor();
+            }
+        }
 
         // Draw state specific
         c.consume(state_line);
         match &mut self.state {
-            State::Normal | State::Description(_) => {}
+            State::Normal
+            | State::Description(_)
+            | State::Distinct(_)
+            | State::Report(_)
+            | State::Catalog(_)
+            | State::Group(_)
+            | State::GlobalSearch(_)
+            | State::Files(_) => {}
             State::Shell(v) => {
                 self.shell
                     .draw(c, v.loader.is_loading().is_some(), v.load_error.is_some())
             }
             State::Nav(nav) => nav.draw(c),
+            State::Cmd(cmd) => cmd.draw(c),
+            State::Inspect(inspect) => inspect.draw(c),
+            State::History(history) => history.draw(c),
+            State::Filter(builder) => builder.draw(c),
+            State::Sheets(picker) => picker.draw(c),
+            State::Chart(_) => unreachable!(),
         }
 
         loading.is_some()
     }
 
+    /// Ask for a pattern to search in every tab
+    pub fn prompt_search_all(&mut self) {
+        self.state = State::Cmd(CmdLine::new(Action::SearchAll));
+    }
+
+    /// Browse the tables, views and macros of the database
+    pub fn show_catalog(&mut self) {
+        self.state = State::Catalog(CatalogView::new(
+            self.view.source.clone(),
+            &self.runner,
+            self.settings,
+        ))
+    }
+
+    /// List the matches of a search in every tab
+    pub fn show_search_all(&mut self, view: GlobalSearchView) {
+        self.state = State::GlobalSearch(view);
+    }
+
+    /// The source file changed on disk
+    pub fn file_changed(&mut self) {
+        self.meta = self.view.source.metadata();
+        if let State::Files(files) = &mut self.state {
+            files.refresh();
+        } else if self.view.source.is_snapshot() {
+            // The matching files are listed, or the file copied, when the view
+            // is created
+            let source = &self.view.source;
+            self.reread(source.read_options().into(), source.sample());
+        } else {
+            self.refresh();
+        }
+    }
+
+    /// Execute the query again, the data of a provider is copied when scanned
+    /// so it is scanned again first
+    fn refresh(&mut self) {
+        if self.view.source.is_virtual() {
+            let task = self
+                .runner
+                .duckdb(self.view.source.clone(), |source, con| source.rescan(&con));
+            self.reread = Some(task);
+        } else {
+            self.view.refresh(&self.runner);
+        }
+    }
+
+    /// Drop the loaded data if not drawn for this long
+    pub fn evict(&mut self, after: Duration) {
+        if self.focused.elapsed() >= after && !self.view.deferred && !self.view.source.in_memory() {
+            self.view.evict();
+        }
+    }
+
+    /// Number of loaded rows of this tab
+    pub fn num_rows(&self) -> usize {
+        self.view.frame.df().num_rows()
+    }
+
+    /// Memory used by the loaded data of this tab
+    pub fn mem_size(&self) -> usize {
+        let mut size = self.view.frame.df().mem_size();
+        if let State::Shell(view) = &self.state {
+            size += view.frame.df().mem_size();
+        }
+        size
+    }
+
     pub fn on_key(&mut self, event: &KeyEvent) -> bool {
+        self.notice = None;
+        // A lone `g` opens the navigator, the key is then handled by it
+        if let Some(start) = self.chord.take() {
+            if event.code == Key::Char('g') && start.elapsed() < CHORD_TIMEOUT {
+                self.view.grid.nav.top();
+                return false;
+            }
+            self.state = State::Nav(Navigator::new(self.view.grid.nav.clone()));
+        }
         match &mut self.state {
             State::Normal => match (self.grid().on_key(event), event.code) {
                 (OnKey::Pass, code) => match code {
-                    Key::Char('$') => self.state = State::Shell(self.view.take()),
-                    Key::Char('g') => {
-                        self.state = State::Nav(Navigator::new(self.grid().nav.clone()))
+                    Key::Char('$') => self.state = State::Shell(Box::new(self.view.take())),
+                    Key::Char('g') => self.chord = Some(Instant::now()),
+                    Key::Char('d') => self.describe(),
+                    Key::Enter => {
+                        let row = self.view.grid.nav.c_row();
+                        self.activated = Some(Activation {
+                            tab: self.view.source.name().into(),
+                            row,
+                            values: self
+                                .view
+                                .grid
+                                .rename_batch(self.view.frame.df().rows(std::iter::once(row))),
+                        });
+                    }
+                    Key::Char('y') => {
+                        let batch = self.marked_rows();
+                        self.notice = Some(export::tsv(&batch).and_then(|tsv| {
+                            clipboard::copy(&tsv)?;
+                            Ok(format!("{} rows copied", batch.num_rows()))
+                        }));
+                    }
+                    Key::Char('Y') => {
+                        let markdown = self.view.grid.markdown(self.view.frame.df());
+                        self.notice = Some(
+                            clipboard::copy(&markdown)
+                                .map(|_| "Screen copied as Markdown".into())
+                                .map_err(|e| e.into()),
+                        );
+                    }
+                    Key::Char('W') => {
+                        self.notice = Some(match self.view.source.path() {
+                            Some(path) => clipboard::copy(&path.to_string_lossy())
+                                .map(|_| "File path copied".into())
+                                .map_err(|e| e.into()),
+                            None => Err("No file for this tab".into()),
+                        });
+                    }
+                    Key::Char('Q') => {
+                        self.notice = Some(
+                            clipboard::copy(&self.view.source.load_sql())
+                                .map(|_| "Query copied".into())
+                                .map_err(|e| e.into()),
+                        );
+                    }
+                    Key::Char('e') => self.state = State::Cmd(CmdLine::new(Action::Export)),
+                    Key::Char('B') => {
+                        if let Some(name) = self.view.col_name() {
+                            let columns = self
+                                .view
+                                .schema()
+                                .fields()
+                                .iter()
+                                .map(|f| (f.name().clone(), f.data_type().is_numeric()))
+                                .collect();
+                            self.state = State::Group(GroupView::new(
+                                self.view.source.clone(),
+                                name,
+                                columns,
+                                &self.runner,
+                                self.settings,
+                            ))
+                        }
                     }
-                    Key::Char('d') => {
-                        self.state = State::Description(DescriberView::new(
+                    Key::Char('A') => {
+                        self.state = State::Cmd(CmdLine::with_input(
+                            Action::ReadOptions,
+                            self.view.source.read_options(),
+                        ))
+                    }
+                    Key::Char('#') => self.rejects(),
+                    Key::Char('U') if self.view.source.sample().is_some() => {
+                        self.reread(self.view.source.read_options().into(), None)
+                    }
+                    Key::Char('&') => {
+                        let columns = self
+                            .view
+                            .schema()
+                            .fields()
+                            .iter()
+                            .map(|f| (f.name().clone(), f.data_type().clone()))
+                            .collect();
+                        self.state =
+                            State::Filter(FilterBuilder::new(self.view.source.clone(), columns))
+                    }
+                    Key::Char(':') => {
+                        self.state =
+                            State::History(HistoryView::new(&self.view.source, self.settings))
+                    }
+                    Key::Char('I') => {
+                        self.state = State::Inspect(Inspect::new(self.view.source.effective_sql()))
+                    }
+                    Key::Char('C') => self.show_catalog(),
+                    Key::Char('S') => {
+                        let source = &self.view.source;
+                        let select = self.view.grid.select_list(self.view.frame.df());
+                        let sql = source.ops().select(source.init_sql(), &select);
+                        self.notice = Some(
+                            clipboard::copy(&sql)
+                                .map(|_| "View copied as SQL".into())
+                                .map_err(|e| e.into()),
+                        );
+                        self.shell.set(&sql);
+                        // The snippet already applies the view transformations
+                        let mut view = self.view.take();
+                        view.grid = Grid::new(self.settings);
+                        let source = source.with_ops(Ops::default()).query(sql);
+                        view.set_source(Arc::new(source), &self.runner);
+                        self.state = State::Shell(Box::new(view));
+                    }
+                    Key::Char('E') => {
+                        if self.export.take().is_some() {
+                            self.notice = Some(Ok("Export canceled".into()));
+                        } else {
+                            self.state = State::Cmd(CmdLine::new(Action::ExportAll))
+                        }
+                    }
+                    Key::Char(c @ ('o' | 'O')) => {
+                        self.view.sort(c == 'O', &self.runner);
+                        self.search = None;
+                    }
+                    Key::Char('f') => {
+                        if let Some(name) = self.view.col_name() {
+                            self.state = State::Distinct(DistinctView::new(
+                                self.view.source.clone(),
+                                name,
+                                &self.runner,
+                                self.settings,
+                            ))
+                        }
+                    }
+                    Key::Char(c @ ('=' | '!')) => {
+                        self.view.filter(c == '!', &self.runner);
+                        self.search = None;
+                    }
+                    Key::Backspace => {
+                        self.view.undo(&self.runner);
+                        self.search = None;
+                    }
+                    Key::Char(']') => self.view.seek(true, true),
+                    Key::Char('[') => self.view.seek(true, false),
+                    Key::Char('}') => self.view.seek(false, true),
+                    Key::Char('{') => self.view.seek(false, false),
+                    Key::Char('/') => {
+                        self.state = State::Cmd(CmdLine::new(Action::Search(Match::Literal)))
+                    }
+                    Key::Char('?') => {
+                        self.state = State::Cmd(CmdLine::new(Action::SearchRow(Match::Literal)))
+                    }
+                    Key::Char('F') => {
+                        self.state = State::Cmd(CmdLine::new(Action::Filter(Match::Literal)))
+                    }
+                    Key::Char('+') => self.state = State::Cmd(CmdLine::new(Action::Derive)),
+                    Key::Char('r') if self.view.failed() => self.refresh(),
+                    Key::Char('r') => self.state = State::Cmd(CmdLine::new(Action::Rename)),
+                    Key::Char('c') => self.state = State::Cmd(CmdLine::new(Action::Cast)),
+                    Key::Char('v') => self.preview = !self.preview,
+                    Key::Char('R') => self.refresh(),
+                    Key::Char('w') => {
+                        self.watch.last = match self.watch.last {
+                            Some(_) => None,
+                            None => Some(Instant::now()),
+                        }
+                    }
+                    Key::Char('V') => self.state = State::Cmd(CmdLine::new(Action::Plot)),
+                    Key::Char('D') => self.state = State::Cmd(CmdLine::new(Action::Duplicates)),
+                    Key::Char('T') => self.type_report(),
+                    Key::Char('a') => self.state = State::Cmd(CmdLine::new(Action::Validate)),
+                    Key::Char('M') => {
+                        let schema = self.view.frame.df().schema();
+                        let names = schema.fields().iter().map(|f| f.name().clone()).collect();
+                        self.state = State::Chart(Box::new(MissingView::new(
                             self.view.source.clone(),
+                            names,
                             &self.runner,
-                        ))
+                        )))
+                    }
+                    Key::Char('b') => match self.histogram() {
+                        Ok(histogram) => self.state = State::Chart(Box::new(histogram)),
+                        Err(err) => self.notice = Some(Err(err)),
+                    },
+                    Key::Char('i') => {
+                        self.view.toggle_row_id(&self.runner);
+                        self.search = None;
+                    }
+                    Key::Char(c @ ('x' | 'X')) => {
+                        self.view.toggle_not_null(c == 'X', &self.runner);
+                        self.search = None;
+                    }
+                    Key::Char('n') => {
+                        let grid = &mut self.view.grid;
+                        if let Some((row, col)) = self
+                            .search
+                            .as_ref()
+                            .and_then(|s| s.first_from(grid.nav.c_row() + 1))
+                        {
+                            grid.focus(row, col)
+                        }
+                    }
+                    Key::Char('N') => {
+                        let grid = &mut self.view.grid;
+                        if let Some((row, col)) =
+                            self.search.as_ref().and_then(|s| s.prev(grid.nav.c_row()))
+                        {
+                            grid.focus(row, col)
+                        }
                     }
                     _ => {}
                 },
@@ -279,14 +1404,16 @@ impl Tab {
             },
             State::Description(_) => match (self.grid().on_key(event), event.code) {
                 (OnKey::Pass, code) => match code {
-                    Key::Char('$') => self.state = State::Shell(self.view.take()),
-                    Key::Char('g') => {
-                        self.state = State::Nav(Navigator::new(self.grid().nav.clone()))
+                    Key::Char('x') => {
+                        if let State::Description(describer) = &mut self.state {
+                            describer.interrupt()
+                        }
                     }
-                    Key::Esc => self.state = State::Normal,
+                    Key::Char('$') => self.state = State::Shell(Box::new(self.view.take())),
+                    Key::Esc => self.leave_description(),
                     _ => {}
                 },
-                (OnKey::Quit, _) => self.state = State::Normal,
+                (OnKey::Quit, _) => self.leave_description(),
                 _ => {}
             },
             State::Shell(view) => {
@@ -302,7 +1429,15 @@ impl Tab {
                         && !view.frame.is_loading()
                         && view.frame.err().is_none()
                     {
-                        std::mem::swap(&mut self.view, view);
+                        history::push(
+                            view.source.init_sql(),
+                            view.took,
+                            view.frame.df().num_rows(),
+                            !view.frame.is_streaming(),
+                            self.settings.history,
+                        );
+                        std::mem::swap(&mut self.view, view.as_mut());
+                        self.search = None;
                         self.state = State::Normal
                     }
                 } else if OnKey::Quit == result {
@@ -316,19 +1451,501 @@ impl Tab {
                     self.state = State::Normal
                 }
             },
+            State::Distinct(distinct) => match (distinct.grid.on_key(event), event.code) {
+                (OnKey::Pass, Key::Enter) => {
+                    if let Some(filter) = distinct.filter() {
+                        self.view.add_filter(filter, &self.runner);
+                        self.search = None;
+                    }
+                    self.state = State::Normal
+                }
+                (OnKey::Pass, Key::Esc) | (OnKey::Quit, _) => self.state = State::Normal,
+                _ => {}
+            },
+            State::Report(report) => match (report.grid.on_key(event), event.code) {
+                (OnKey::Pass, Key::Enter) => {
+                    if let Some(filter) = report.filters.get(report.grid.nav.c_row()) {
+                        self.view.add_filter(filter.clone(), &self.runner);
+                        self.search = None;
+                        self.state = State::Normal
+                    }
+                }
+                (OnKey::Pass, Key::Esc) | (OnKey::Quit, _) => self.state = State::Normal,
+                _ => {}
+            },
+            State::Group(group) => match (group.grid.on_key(event), event.code) {
+                (OnKey::Pass, Key::Enter) => group.toggle(&self.runner),
+                (OnKey::Pass, Key::Esc) | (OnKey::Quit, _) => self.state = State::Normal,
+                _ => {}
+            },
+            State::Catalog(catalog) => match (catalog.grid.on_key(event), event.code) {
+                (OnKey::Pass, Key::Char('c')) => catalog.toggle(),
+                (OnKey::Pass, code @ (Key::Enter | Key::Char('$'))) => {
+                    if let Some(object) = catalog.selected() {
+                        if code == Key::Enter && object.is_relation() {
+                            let sql = format!("SELECT * FROM {}", object.ident());
+                            let source = self.view.source.relation(object.name.clone(), sql);
+                            self.open = Some(source);
+                            self.state = State::Normal;
+                        } else {
+                            self.shell.insert(&object.ident());
+                            self.state = State::Shell(Box::new(self.view.take()));
+                        }
+                    }
+                }
+                (OnKey::Pass, Key::Esc) | (OnKey::Quit, _) => self.state = State::Normal,
+                _ => {}
+            },
+            State::History(history) => match (history.grid.on_key(event), event.code) {
+                (OnKey::Pass, code @ (Key::Enter | Key::Char('$'))) => {
+                    if let Some(sql) = history.selected() {
+                        let source = Arc::new(self.view.source.query(sql.into()));
+                        if code == Key::Enter {
+                            // Restored instantly when its result is still cached
+                            self.view.set_source(source, &self.runner);
+                            self.search = None;
+                            self.state = State::Normal;
+                        } else {
+                            self.shell.set(sql);
+                            let mut view = self.view.take();
+                            view.set_source(source, &self.runner);
+                            self.state = State::Shell(Box::new(view));
+                        }
+                    }
+                }
+                (OnKey::Pass, Key::Esc) | (OnKey::Quit, _) => self.state = State::Normal,
+                _ => {}
+            },
+            State::GlobalSearch(global) => match (global.grid.on_key(event), event.code) {
+                (OnKey::Pass, Key::Enter) => {
+                    if let Some(hit) = global.selected() {
+                        self.jump = Some(hit);
+                        self.state = State::Normal;
+                    }
+                }
+                (OnKey::Pass, Key::Esc) | (OnKey::Quit, _) => self.state = State::Normal,
+                _ => {}
+            },
+            State::Filter(builder) => match builder.on_key(event, &self.runner) {
+                filter::Outcome::Continue => {}
+                filter::Outcome::Cancel => self.state = State::Normal,
+                filter::Outcome::Apply(filter) => {
+                    self.view.add_filter(filter, &self.runner);
+                    self.search = None;
+                    self.state = State::Normal
+                }
+            },
+            State::Files(files) => match (files.grid.on_key(event), event.code) {
+                (OnKey::Pass, Key::Enter) => files.open(&self.runner),
+                // Nothing lies behind the listing of a directory
+                (OnKey::Quit, _) => return true,
+                _ => {}
+            },
+            State::Sheets(picker) => match picker.on_key(event) {
+                excel::Outcome::Continue => {}
+                excel::Outcome::Cancel => self.state = State::Normal,
+                excel::Outcome::Pick(options) => {
+                    self.state = State::Normal;
+                    let read_options = match self.view.source.read_options() {
+                        "" => options,
+                        prev => format!("{prev}, {options}"),
+                    };
+                    self.reread(read_options, self.view.source.sample());
+                }
+            },
+            State::Chart(_) => {
+                if let Key::Esc | Key::Char('q') = event.code {
+                    self.state = State::Normal
+                }
+            }
+            State::Inspect(inspect) => match inspect.on_key(event.code) {
+                (OnKey::Quit, _) => self.state = State::Normal,
+                (_, Some(copied)) => {
+                    self.notice = Some(
+                        copied
+                            .map(|_| "Effective SQL copied".into())
+                            .map_err(|e| e.into()),
+                    )
+                }
+                _ => {}
+            },
+            State::Cmd(cmd) => match cmd.on_key(event) {
+                Outcome::Continue => {}
+                Outcome::Cancel => self.state = State::Normal,
+                Outcome::Submit(action, arg) => {
+                    self.state = State::Normal;
+                    self.exec(action, arg);
+                }
+            },
         }
         false
     }
 
+    /// Read the source file again in the background, scanning a slow or
+    /// remote file would block the interface
+    fn reread(&mut self, read_options: String, sample: Option<usize>) {
+        let task = self
+            .runner
+            .duckdb(self.view.source.clone(), move |source, con| {
+                source.reread(&con, read_options, sample)
+            });
+        self.reread = Some(task);
+    }
+
+    fn exec(&mut self, action: Action, arg: String) {
+        match action {
+            Action::Rename => self.view.grid.rename(arg),
+            Action::Duplicates => {
+                let keys = if arg.is_empty() {
+                    "*".to_string()
+                } else {
+                    let keys: Vec<_> = arg.split(',').map(|k| sql::ident(k.trim())).collect();
+                    keys.join(", ")
+                };
+                self.state = State::Report(ReportView::new(
+                    "duplicates",
+                    self.view.source.clone(),
+                    move |query| {
+                        format!("SELECT {keys}, count(*) AS count FROM ({query}) GROUP BY ALL HAVING count(*) > 1 ORDER BY count DESC LIMIT 1000")
+                    },
+                    &self.runner,
+                    self.settings,
+                ))
+            }
+            Action::SearchAll => self.search_all = Some(arg),
+            Action::Validate => {
+                if let Err(err) = self.add_rules(&arg) {
+                    self.notice = Some(Err(err));
+                } else if !self.rules.is_empty() {
+                    self.validate();
+                }
+            }
+            Action::ReadOptions => self.reread(arg, self.view.source.sample()),
+            _ if arg.is_empty() => {}
+            Action::Export => {
+                let batch = self.marked_rows();
+                let nb_row = batch.num_rows();
+                self.notice = Some(
+                    export::to_file(batch, &arg)
+                        .map(|_| format!("{nb_row} rows exported to {arg}")),
+                );
+            }
+            Action::ExportAll => {
+                let columns = self.view.grid.select_list(self.view.frame.df());
+                let source = self.view.source.clone();
+                self.export = Some((
+                    arg.clone(),
+                    export::full(source, &columns, &arg, &self.runner),
+                ));
+            }
+            Action::Search(m) | Action::SearchRow(m) => {
+                let df = self.view.frame.df().clone();
+                if df.num_columns() == 0 {
+                    return;
+                }
+                let cols = if let Action::SearchRow(_) = action {
+                    (0..df.num_columns()).collect()
+                } else {
+                    vec![self.view.grid.c_idx()]
+                };
+                self.search = Some(Search::new(
+                    arg,
+                    m == Match::Regex,
+                    cols,
+                    self.view.source.clone(),
+                    df,
+                    self.view.frame.is_streaming(),
+                    &self.runner,
+                ));
+            }
+            Action::Filter(m) => {
+                if let Some(name) = self.view.col_name() {
+                    let filter = sql::matches(&name, &arg, m == Match::Regex);
+                    self.view.add_filter(filter, &self.runner);
+                    self.search = None;
+                }
+            }
+            Action::Derive => {
+                self.view.add_column(arg, &self.runner);
+                self.search = None;
+            }
+            Action::Cast => {
+                self.view.cast(&arg, &self.runner);
+                self.search = None;
+            }
+            Action::Plot => match self.plot(&arg) {
+                Ok(plot) => self.state = State::Chart(Box::new(plot)),
+                Err(err) => self.notice = Some(Err(err)),
+            },
+        }
+    }
+
+    /// Add a rule or the rules of a file prefixed by @
+    fn add_rules(&mut self, arg: &str) -> Result<()> {
+        if arg.is_empty() {
+            return Ok(());
+        }
+        match arg.strip_prefix('@') {
+            Some(path) => self.rules.extend(Rule::load(path.trim().as_ref())?),
+            None => self.rules.push(Rule::parse(arg)?),
+        }
+        Ok(())
+    }
+
+    /// Check all rules, drilling down to the failing rows of a rule on selection
+    fn validate(&mut self) {
+        let query = self.view.source.load_sql();
+        let sql = validate::report(&query, &self.rules);
+        let filters = self.rules.iter().map(|r| r.violation(&query)).collect();
+        self.state = State::Report(
+            ReportView::new(
+                "validate",
+                self.view.source.clone(),
+                move |_| sql,
+                &self.runner,
+                self.settings,
+            )
+            .with_filters(filters),
+        )
+    }
+
+    /// Report how text columns could be converted
+    fn type_report(&mut self) {
+        let names: Vec<_> = self
+            .view
+            .frame
+            .df()
+            .schema()
+            .fields()
+            .iter()
+            .filter(|f| matches!(f.data_type(), DataType::Utf8 | DataType::LargeUtf8))
+            .map(|f| f.name().clone())
+            .collect();
+        if names.is_empty() {
+            self.notice = Some(Err(StrError("no text column".into())));
+            return;
+        }
+        self.state = State::Report(ReportView::new(
+            "types",
+            self.view.source.clone(),
+            move |query| sql::type_report(query, &names),
+            &self.runner,
+            self.settings,
+        ))
+    }
+
+    /// List the malformed rows of a csv file
+    fn rejects(&mut self) {
+        match self.view.source.rejects_sql() {
+            Ok((scan, list)) => {
+                // The rejects table is temporary, both must run on the same connection
+                let task = self.runner.duckdb(self.view.source.clone(), move |_, con| {
+                    // The rejects table must not be seen by other tasks
+                    con.discard();
+                    con.execute(&scan)?;
+                    con.query(&list)?
+                        .map(|d| d.map_err(|e| e.into()))
+                        .collect::<Result<DataFrame>>()
+                });
+                self.state = State::Report(ReportView::from_task("rejects", task, self.settings))
+            }
+            Err(err) => self.notice = Some(Err(err)),
+        }
+    }
+
+    /// Open the description, reusing a still running one of the same query
+    fn describe(&mut self) {
+        let key = self.view.source.load_sql();
+        let describer = match self.describing.take() {
+            Some(describer) if self.view.tasks.running("describe", &key) => describer,
+            _ => {
+                let describer =
+                    DescriberView::new(self.view.source.clone(), &self.runner, self.settings);
+                if let Some(handle) = describer.handle() {
+                    self.view.tasks.track("describe", key, handle);
+                }
+                describer
+            }
+        };
+        self.state = State::Description(describer);
+    }
+
+    /// Return to normal mode, keeping an unfinished description for later
+    fn leave_description(&mut self) {
+        if let State::Description(describer) = std::mem::replace(&mut self.state, State::Normal) {
+            if describer.handle().is_some() {
+                self.describing = Some(describer);
+            }
+        }
+    }
+
+    /// Distribution of the current column
+    fn histogram(&self) -> Result<HistogramView> {
+        let df = self.view.frame.df();
+        let Some(name) = self.view.col_name() else {
+            return Err(StrError("no column to plot".into()));
+        };
+        let field = df.schema().field(self.view.grid.c_idx()).clone();
+        let expr = chart::numeric(&name, field.data_type())
+            .ok_or_else(|| StrError(format!("column {name} is not numeric")))?;
+        Ok(HistogramView::new(
+            self.view.source.clone(),
+            name,
+            expr,
+            &self.runner,
+        ))
+    }
+
+    /// Plot comma separated columns against the current one
+    fn plot(&self, ys: &str) -> Result<PlotView> {
+        let df = self.view.frame.df();
+        let Some(x) = self.view.col_name() else {
+            return Err(StrError("no column to plot".into()));
+        };
+        let schema = df.schema();
+        let col = |name: &str| -> Result<(String, String)> {
+            let field = schema
+                .field_with_name(name)
+                .map_err(|_| StrError(format!("unknown column {name}")))?;
+            let expr = chart::numeric(name, field.data_type())
+                .ok_or_else(|| StrError(format!("column {name} is not numeric")))?;
+            Ok((name.to_string(), expr))
+        };
+        let x = col(&x)?;
+        let ys = ys
+            .split(',')
+            .map(|name| col(name.trim()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(PlotView::new(self.view.source.clone(), x, ys, &self.runner))
+    }
+
+    /// Refresh the query periodically
+    pub fn watch(&mut self, interval: Duration) {
+        self.watch = Watch {
+            interval,
+            last: Some(Instant::now()),
+        };
+    }
+
+    /// Execute the query again if the watch interval elapsed, focused or not
+    pub fn tick_watch(&mut self) {
+        if self.watch.remaining() == Some(Duration::ZERO)
+            && self.view.loader.is_loading().is_none()
+            && !self.view.frame.is_loading()
+            && self.reread.is_none()
+        {
+            self.refresh();
+            self.watch.last = Some(Instant::now());
+        }
+    }
+
+    /// Time until the next watch refresh if enabled
+    pub fn watch_remaining(&self) -> Option<Duration> {
+        self.watch.remaining()
+    }
+
+    /// Time until the pending `gg` chord expires
+    fn chord_remaining(&self) -> Option<Duration> {
+        self.chord
+            .map(|start| CHORD_TIMEOUT.saturating_sub(start.elapsed()))
+    }
+
+    /// Time until the tab must be drawn again without any event
+    pub fn next_wake(&self) -> Option<Duration> {
+        let ingesting = self
+            .view
+            .source
+            .ingest()
+            .is_some_and(|ingest| !ingest.is_done())
+            .then_some(INGEST_INTERVAL);
+        [
+            self.watch.remaining(),
+            ingesting,
+            self.view.grid.highlight_remaining(),
+            self.chord_remaining(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Image previewed in the current cell with its offset from the bottom of the screen
+    pub fn image(&self) -> Option<(&[u8], u16)> {
+        self.image
+            .as_ref()
+            .map(|(bytes, offset)| (bytes.as_slice(), *offset))
+    }
+
+    /// Rows picked in this tab
+    pub fn selection(&self) -> Selection {
+        Selection {
+            tab: self.view.source.name().into(),
+            rows: self.marked_rows(),
+        }
+    }
+
+    pub fn is_normal(&self) -> bool {
+        matches!(self.state, State::Normal)
+    }
+
+    /// Marked rows or the row under the cursor if none are marked
+    fn marked_rows(&self) -> RecordBatch {
+        let df = self.view.frame.df();
+        let grid = &self.view.grid;
+        let batch = if grid.selection.len() > 0 {
+            df.rows(grid.selection.rows())
+        } else {
+            df.rows(std::iter::once(grid.nav.c_row()))
+        };
+        grid.rename_batch(batch)
+    }
+
     pub fn grid(&mut self) -> &mut Grid {
         match &mut self.state {
             State::Shell(view) => &mut view.grid,
             State::Description(desrc) => &mut desrc.grid,
+            State::Distinct(distinct) => &mut distinct.grid,
+            State::Report(report) => &mut report.grid,
+            State::Catalog(catalog) => &mut catalog.grid,
+            State::Group(group) => &mut group.grid,
+            State::History(history) => &mut history.grid,
+            State::GlobalSearch(global) => &mut global.grid,
+            State::Files(files) => &mut files.grid,
             _ => &mut self.view.grid,
         }
     }
 }
 
+/// Draw a full screen chart with its status line
+fn draw_chart(
+    chart: &mut dyn Chart,
+    spinner: &mut Spinner,
+    glyphs: &Glyphs,
+    c: &mut Canvas,
+) -> bool {
+    let status_line = c.reserve_btm(1);
+    let loading = chart.tick();
+    let spinner = spinner.state(loading.is_some(), glyphs);
+
+    // Print error message
+    if let Some(err) = chart.err() {
+        for line in err.lines().rev() {
+            c.btm().draw(line, style::error());
+        }
+    }
+    chart.draw(c);
+
+    // Draw status
+    let mut l = c.consume(status_line).btm();
+    l.draw(format_args!(" {} ", chart.label()), style::state_other());
+    l.draw(" ", style::primary());
+    if let (Some((task, _)), Some(c)) = (loading, spinner) {
+        l.rdraw(format_args!("{c}"), style::progress());
+        l.rdraw(format_args!(" {task}"), style::progress());
+    }
+    loading.is_some()
+}
+
 #[derive(PartialEq, Eq)]
 pub enum Status {
     Normal,
@@ -338,5 +1955,20 @@ pub enum Status {
 
 pub struct GridUI {
     pub col_name: Option<String>, // TODO borrow
+    pub selected: usize,
+    /// Number of visible truncated cells in the current column
+    pub truncated: usize,
     pub status: Status,
+    pub cols: ColsView,
+}
+
+/// Horizontal position of the viewport in the columns
+pub struct ColsView {
+    /// Offset of the first and last visible columns
+    pub first: usize,
+    pub last: usize,
+    /// Number of projected columns
+    pub total: usize,
+    /// Number of columns hidden by the projection
+    pub hidden: usize,
 }
@@ -1,7 +1,8 @@
 use std::{
+    collections::HashMap,
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, Weak,
     },
     thread::Thread,
 };
@@ -10,9 +11,45 @@ use libduckdb_sys::duckdb_query_progress_type;
 
 use crate::{
     duckdb::{ConnCtx, Connection},
+    stats::TaskGuard,
     Source,
 };
 
+/// Default maximum number of duckdb tasks executing at once
+pub const MAX_TASKS: usize = 4;
+
+static LIMIT: AtomicUsize = AtomicUsize::new(MAX_TASKS);
+static RUNNING: Mutex<usize> = Mutex::new(0);
+static SLOT_FREED: Condvar = Condvar::new();
+
+/// Set the maximum number of duckdb tasks executing at once, excess tasks are
+/// queued until a slot is freed
+pub fn set_max_tasks(limit: usize) {
+    LIMIT.store(limit.max(1), Ordering::Relaxed);
+    SLOT_FREED.notify_all();
+}
+
+/// Execution slot of a duckdb task
+pub struct Permit;
+
+impl Permit {
+    pub fn acquire() -> Self {
+        let mut running = RUNNING.lock().unwrap();
+        while *running >= LIMIT.load(Ordering::Relaxed) {
+            running = SLOT_FREED.wait(running).unwrap();
+        }
+        *running += 1;
+        Self
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        *RUNNING.lock().unwrap() -= 1;
+        SLOT_FREED.notify_one();
+    }
+}
+
 #[derive(Clone)]
 pub struct Runner(Thread);
 
@@ -31,12 +68,35 @@ impl Runner {
         let wake = self.0.clone();
         let done = Arc::new(AtomicBool::new(false));
 
-        let con = source.conn().expect("TODO");
+        let alive = Arc::new(());
+
+        let con = match source.checkout() {
+            Ok(con) => con,
+            Err(err) => {
+                // Fail without running the task
+                sender.send(Err(err)).ok();
+                return DuckTask {
+                    receiver,
+                    ctx: None,
+                    done: Arc::new(AtomicBool::new(true)),
+                    _alive: alive,
+                };
+            }
+        };
         let ctx = con.ctx();
         {
             let done = done.clone();
+            let alive = Arc::downgrade(&alive);
+            let guard = TaskGuard::new();
             std::thread::spawn(move || {
-                let result = task(source, con);
+                let _guard = guard;
+                let permit = Permit::acquire();
+                // Skip tasks canceled while queued
+                if Weak::strong_count(&alive) == 0 {
+                    return;
+                }
+                let result = source.prepare(&con).and_then(|_| task(source, con));
+                drop(permit);
                 done.store(true, Ordering::Relaxed);
                 if sender.send(result).is_ok() {
                     // Only succeeded if the result is expected
@@ -46,8 +106,9 @@ impl Runner {
         }
         DuckTask {
             receiver,
-            ctx,
+            ctx: Some(ctx),
             done,
+            _alive: alive,
         }
     }
 
@@ -67,7 +128,13 @@ impl Runner {
                 inner: inner.clone(),
                 wake: self.0.clone(),
             };
-            std::thread::spawn(move || task(ctx)).thread().clone()
+            let guard = TaskGuard::new();
+            std::thread::spawn(move || {
+                let _guard = guard;
+                task(ctx)
+            })
+            .thread()
+            .clone()
         };
         Task {
             inner: Some(inner),
@@ -95,10 +162,11 @@ impl<S, T> Ctx<S, T> {
         &self.inner.state
     }
 
-    pub fn lock(&self, update: impl FnOnce(&mut T)) {
+    pub fn lock<R>(&self, update: impl FnOnce(&mut T) -> R) -> R {
         let mut lock = self.inner.lock.lock().unwrap();
-        update(&mut lock);
+        let result = update(&mut lock);
         drop(lock);
+        result
     }
 
     pub fn wait(&self) {
@@ -144,13 +212,30 @@ impl<S, T> Drop for Task<S, T> {
 
 pub struct DuckTask<T> {
     receiver: oneshot::Receiver<crate::error::Result<T>>,
-    ctx: ConnCtx,
+    /// Connection context, None if no connection could be opened
+    ctx: Option<ConnCtx>,
     done: Arc<AtomicBool>,
+    _alive: Arc<()>,
 }
 
 impl<T> DuckTask<T> {
     pub fn progress(&self) -> duckdb_query_progress_type {
-        self.ctx.progress()
+        match &self.ctx {
+            Some(ctx) => ctx.progress(),
+            None => duckdb_query_progress_type {
+                percentage: -1.,
+                rows_processed: 0,
+                total_rows_to_process: 0,
+            },
+        }
+    }
+
+    /// Handle to interrupt this task from elsewhere
+    pub fn handle(&self) -> TaskHandle {
+        TaskHandle {
+            ctx: self.ctx.clone(),
+            done: self.done.clone(),
+        }
     }
 
     pub fn tick(&mut self) -> Option<crate::error::Result<T>> {
@@ -168,8 +253,44 @@ impl<T> DuckTask<T> {
 
 impl<T> Drop for DuckTask<T> {
     fn drop(&mut self) {
-        if !self.done.load(Ordering::Relaxed) {
-            self.ctx.interrupt()
+        self.handle().interrupt()
+    }
+}
+
+#[derive(Clone)]
+pub struct TaskHandle {
+    ctx: Option<ConnCtx>,
+    done: Arc<AtomicBool>,
+}
+
+impl TaskHandle {
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// Interrupt the running query if not already done
+    pub fn interrupt(&self) {
+        if let Some(ctx) = self.ctx.as_ref().filter(|_| !self.is_done()) {
+            ctx.interrupt()
+        }
+    }
+}
+
+/// Registry of the in-flight duckdb tasks of a tab, one per kind
+#[derive(Default)]
+pub struct Tasks(HashMap<&'static str, (String, TaskHandle)>);
+
+impl Tasks {
+    /// Whether an identical request of this kind is still running
+    pub fn running(&mut self, kind: &'static str, key: &str) -> bool {
+        self.0.retain(|_, (_, handle)| !handle.is_done());
+        self.0.get(kind).is_some_and(|(k, _)| k == key)
+    }
+
+    /// Track a new task, interrupting the one it supersedes
+    pub fn track(&mut self, kind: &'static str, key: String, handle: TaskHandle) {
+        if let Some((_, old)) = self.0.insert(kind, (key, handle)) {
+            old.interrupt()
         }
     }
 }
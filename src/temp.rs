@@ -0,0 +1,53 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::ErrorKind,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::error::Result;
+
+/// File in the temporary directory, removed when dropped
+pub struct TempFile {
+    pub path: PathBuf,
+}
+
+impl TempFile {
+    /// New empty file ending with `suffix`, such as `.csv.gz`, so readers
+    /// still recognize the format
+    ///
+    /// The temporary directory is shared, so the file is only created when
+    /// nothing exists at its path, which could be a link planted by another
+    /// user.
+    pub fn new(suffix: &str) -> Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        loop {
+            let name = format!(
+                "dtex-{}-{}{suffix}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            );
+            let path = std::env::temp_dir().join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Open the file to replace its content
+    pub fn write(&self) -> Result<File> {
+        let file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        Ok(file)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
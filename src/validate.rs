@@ -0,0 +1,349 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{
+    error::{Result, StrError},
+    sql,
+};
+
+/// Data validation rule
+#[derive(Debug, PartialEq)]
+pub enum Rule {
+    NotNull(String),
+    Unique(String),
+    Range(String, String, String),
+    Regex(String, String),
+    Sql(String),
+}
+
+impl Rule {
+    /// Parse a rule, one of:
+    /// - `<col> not null`
+    /// - `<col> unique`
+    /// - `<col> between <min> and <max>`
+    /// - `<col> ~ <regex>`
+    /// - any sql predicate
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(StrError("empty rule".into()));
+        }
+        let (col, rest) = split_col(text);
+        let rest = rest.trim();
+        let lower = rest.to_ascii_lowercase();
+        Ok(if lower == "not null" {
+            Rule::NotNull(col)
+        } else if lower == "unique" {
+            Rule::Unique(col)
+        } else if let Some(range) = lower.strip_prefix("between ") {
+            let Some(pos) = range.find(" and ") else {
+                return Err(StrError(format!("expected '<min> and <max>' in '{text}'")));
+            };
+            // Keep original case of bounds, ascii lowercase preserve offsets
+            let range = &rest["between ".len()..];
+            let min = range[..pos].trim().to_string();
+            let max = range[pos + " and ".len()..].trim().to_string();
+            Rule::Range(col, min, max)
+        } else if let Some(pattern) = rest.strip_prefix('~') {
+            Rule::Regex(col, pattern.trim().to_string())
+        } else {
+            Rule::Sql(text.to_string())
+        })
+    }
+
+    /// Parse rules from a TOML or YAML file, or from any other file with one
+    /// rule per line, ignoring empty lines and comments starting with #
+    pub fn load(path: &Path) -> Result<Vec<Self>> {
+        let content = std::fs::read_to_string(path)?;
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let file: File = match ext.to_ascii_lowercase().as_str() {
+            "toml" => toml::from_str(&content).map_err(|e| StrError(e.to_string()))?,
+            "yaml" | "yml" => {
+                serde_yaml::from_str(&content).map_err(|e| StrError(e.to_string()))?
+            }
+            _ => {
+                return content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                    .map(Rule::parse)
+                    .collect()
+            }
+        };
+        let mut rules = vec![];
+        for decl in file.rules {
+            decl.push_into(&mut rules)?;
+        }
+        Ok(rules)
+    }
+
+    pub fn describe(&self) -> String {
+        // Quote columns that would not parse back as a single word
+        let quote = |col: &str| match col.contains(|c: char| c == '"' || c.is_whitespace()) {
+            true => sql::ident(col),
+            false => col.to_string(),
+        };
+        match self {
+            Rule::NotNull(col) => format!("{} not null", quote(col)),
+            Rule::Unique(col) => format!("{} unique", quote(col)),
+            Rule::Range(col, min, max) => format!("{} between {min} and {max}", quote(col)),
+            Rule::Regex(col, pattern) => format!("{} ~ {pattern}", quote(col)),
+            Rule::Sql(pred) => pred.clone(),
+        }
+    }
+
+    /// Predicate matching the rows breaking this rule within a query
+    pub fn violation(&self, query: &str) -> String {
+        match self {
+            Rule::NotNull(col) => format!("{} IS NULL", sql::ident(col)),
+            Rule::Unique(col) => format!(
+                "{col} IN (SELECT {col} FROM ({query}) GROUP BY ALL HAVING count(*) > 1)",
+                col = sql::ident(col)
+            ),
+            Rule::Range(col, min, max) => format!(
+                "coalesce({} NOT BETWEEN {min} AND {max}, false)",
+                sql::ident(col)
+            ),
+            Rule::Regex(col, pattern) => format!(
+                "coalesce(NOT regexp_full_match({}::VARCHAR, {}), false)",
+                sql::ident(col),
+                sql::lit(pattern)
+            ),
+            Rule::Sql(pred) => format!("coalesce(NOT ({pred}), false)"),
+        }
+    }
+}
+
+/// Rules file
+#[derive(Deserialize)]
+struct File {
+    rules: Vec<Decl>,
+}
+
+/// Rule declared in a file, checking every given constraint on a column or
+/// an sql predicate
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Decl {
+    column: Option<String>,
+    #[serde(default)]
+    not_null: bool,
+    #[serde(default)]
+    unique: bool,
+    min: Option<Bound>,
+    max: Option<Bound>,
+    regex: Option<String>,
+    check: Option<String>,
+}
+
+impl Decl {
+    fn push_into(self, rules: &mut Vec<Rule>) -> Result<()> {
+        let len = rules.len();
+        rules.extend(self.check.map(Rule::Sql));
+        if let Some(col) = self.column {
+            if self.not_null {
+                rules.push(Rule::NotNull(col.clone()));
+            }
+            if self.unique {
+                rules.push(Rule::Unique(col.clone()));
+            }
+            let (min, max) = (self.min.map(Bound::lit), self.max.map(Bound::lit));
+            match (min, max) {
+                (Some(min), Some(max)) => rules.push(Rule::Range(col.clone(), min, max)),
+                (Some(min), None) => {
+                    rules.push(Rule::Sql(format!("{} >= {min}", sql::ident(&col))))
+                }
+                (None, Some(max)) => {
+                    rules.push(Rule::Sql(format!("{} <= {max}", sql::ident(&col))))
+                }
+                (None, None) => {}
+            }
+            if let Some(pattern) = self.regex {
+                rules.push(Rule::Regex(col, pattern));
+            }
+        } else if self.not_null
+            || self.unique
+            || self.min.is_some()
+            || self.max.is_some()
+            || self.regex.is_some()
+        {
+            return Err(StrError("missing column of rule".into()));
+        }
+        if rules.len() == len {
+            return Err(StrError("rule without any constraint".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Bound of a range, numbers are used as is and strings are quoted
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Bound {
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl Bound {
+    fn lit(self) -> String {
+        match self {
+            Bound::Int(n) => n.to_string(),
+            Bound::Float(n) => n.to_string(),
+            Bound::Str(s) => sql::lit(&s),
+        }
+    }
+}
+
+/// Query counting the rows breaking each rule
+pub fn report(query: &str, rules: &[Rule]) -> String {
+    let selects: Vec<_> = rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| {
+            format!(
+                "SELECT {i} AS idx, {} AS rule, count(*) FILTER (WHERE {}) AS fail, count(*) AS total FROM ({query})",
+                sql::lit(&rule.describe()),
+                rule.violation(query)
+            )
+        })
+        .collect();
+    format!(
+        "SELECT rule, CASE WHEN fail = 0 THEN 'pass' ELSE 'fail' END AS status, fail, total FROM ({}) ORDER BY idx",
+        selects.join(" UNION ALL ")
+    )
+}
+
+/// Split a leading column name, optionally double quoted with doubled quotes
+/// as escapes
+fn split_col(text: &str) -> (String, &str) {
+    if let Some(quoted) = text.strip_prefix('"') {
+        let mut col = String::new();
+        let mut chars = quoted.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' if chars.next_if(|(_, c)| *c == '"').is_some() => col.push('"'),
+                '"' => return (col, &quoted[i + 1..]),
+                c => col.push(c),
+            }
+        }
+    }
+    match text.split_once(char::is_whitespace) {
+        Some((col, rest)) => (col.to_string(), rest),
+        None => (text.to_string(), ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(text: &str) -> Rule {
+        Rule::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(rule("id not null"), Rule::NotNull("id".into()));
+        assert_eq!(rule(" id  NOT NULL "), Rule::NotNull("id".into()));
+        assert_eq!(rule("id Unique"), Rule::Unique("id".into()));
+        assert_eq!(
+            rule("age BETWEEN 0 AND 150"),
+            Rule::Range("age".into(), "0".into(), "150".into())
+        );
+        assert_eq!(
+            rule("day between DATE 'A' and DATE 'b'"),
+            Rule::Range("day".into(), "DATE 'A'".into(), "DATE 'b'".into())
+        );
+        assert_eq!(
+            rule("mail ~ [^@]+@[^@]+"),
+            Rule::Regex("mail".into(), "[^@]+@[^@]+".into())
+        );
+        assert_eq!(rule("a < b"), Rule::Sql("a < b".into()));
+    }
+
+    #[test]
+    fn parse_quoted_column() {
+        assert_eq!(
+            rule("\"first name\" not null"),
+            Rule::NotNull("first name".into())
+        );
+        assert_eq!(rule("\"a\"\"b\" unique"), Rule::Unique("a\"b".into()));
+        // Unterminated quote is the start of a predicate
+        assert_eq!(rule("\"a b unique"), Rule::Sql("\"a b unique".into()));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(Rule::parse("").is_err());
+        assert!(Rule::parse("   ").is_err());
+        assert!(Rule::parse("age between 1").is_err());
+        assert!(Rule::parse("age between 1 or 2").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let rules = [
+            Rule::NotNull("id".into()),
+            Rule::Unique("first name".into()),
+            Rule::Unique("a\"b".into()),
+            Rule::Range("age".into(), "0".into(), "150".into()),
+            Rule::Regex("mail".into(), ".+@.+".into()),
+            Rule::Sql("a < b".into()),
+        ];
+        for expected in rules {
+            assert_eq!(rule(&expected.describe()), expected);
+        }
+    }
+
+    #[test]
+    fn declarations() {
+        let file: File = toml::from_str(
+            r#"
+            [[rules]]
+            column = "age"
+            not_null = true
+            min = 0
+            max = 150
+
+            [[rules]]
+            column = "name"
+            min = "a"
+
+            [[rules]]
+            check = "a < b"
+            "#,
+        )
+        .unwrap();
+        let mut rules = vec![];
+        for decl in file.rules {
+            decl.push_into(&mut rules).unwrap();
+        }
+        assert_eq!(
+            rules,
+            [
+                Rule::NotNull("age".into()),
+                Rule::Range("age".into(), "0".into(), "150".into()),
+                Rule::Sql("\"name\" >= 'a'".into()),
+                Rule::Sql("a < b".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_declarations() {
+        for toml in [
+            "[[rules]]\nnot_null = true",
+            "[[rules]]\ncolumn = \"a\"",
+            "[[rules]]\ncolumn = \"a\"\nnull = true",
+        ] {
+            let decls = toml::from_str::<File>(toml).map(|f| {
+                f.rules
+                    .into_iter()
+                    .try_for_each(|d| d.push_into(&mut vec![]))
+            });
+            assert!(!matches!(decls, Ok(Ok(()))), "{toml}");
+        }
+    }
+}
@@ -1,9 +1,11 @@
+use std::borrow::Cow;
+
 use libduckdb_sys::duckdb_query_progress_type;
 
 use crate::grid::{Frame, Grid};
 
 pub struct ViewState<'a> {
-    pub loading: Option<(&'static str, duckdb_query_progress_type)>,
+    pub loading: Option<(Cow<'static, str>, duckdb_query_progress_type)>,
     pub streaming: bool,
     pub frame: &'a dyn Frame,
     pub err: Option<&'a str>,